@@ -1,174 +1,756 @@
 use algebra::{
-    bls12_381::{Fq, Fq2, G1Affine, G2Affine},
-    bytes::FromBytes,
+    bls12_381::{g1, g2, Fq, Fq2, G1Affine, G2Affine},
     curves::models::short_weierstrass_jacobian::GroupAffine,
-    fields::{Field, PrimeField, SquareRootField},
-    io::Cursor,
-    prelude::Zero,
-    BigInteger384, SWModelParameters,
+    fields::{
+        Field, Fp2, Fp256, Fp256Parameters, Fp2Parameters, Fp384, Fp384Parameters, FpParameters,
+        PrimeField, SquareRootField,
+    },
+    prelude::{One, Zero},
+    BigInteger, SWModelParameters,
 };
 use core::ops::{AddAssign, MulAssign, Neg};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeLess};
 
-pub fn read_g1affine(data: [u8; 96]) -> Result<G1Affine, ()> {
-    let uncompressed = G1Uncompressed::new(data);
-    uncompressed.into_affine()
+pub fn read_g1affine(data: [u8; 96]) -> Result<G1Affine, AffineDecodeError> {
+    read_affine::<g1::Parameters>(&data, Infinity::Allow)
 }
 
-pub fn read_g2affine(data: [u8; 192]) -> Result<G2Affine, ()> {
-    let uncompressed = G2Uncompressed::new(data);
-    uncompressed.into_affine()
+pub fn read_g2affine(data: [u8; 192]) -> Result<G2Affine, AffineDecodeError> {
+    read_affine::<g2::Parameters>(&data, Infinity::Allow)
 }
 
-pub fn read_compressed_g1affine(data: [u8; 48]) -> Result<G1Affine, ()> {
-    let uncompressed = G1Compressed::new(data);
-    uncompressed.into_affine()
+pub fn read_compressed_g1affine(data: [u8; 48]) -> Result<G1Affine, AffineDecodeError> {
+    read_compressed_affine::<g1::Parameters>(&data, Infinity::Allow, SignConvention::Lexicographic)
 }
 
-pub fn read_compressed_g2affine(data: [u8; 96]) -> Result<G2Affine, ()> {
-    let uncompressed = G2Compressed::new(data);
-    uncompressed.into_affine()
+pub fn read_compressed_g2affine(data: [u8; 96]) -> Result<G2Affine, AffineDecodeError> {
+    read_compressed_affine::<g2::Parameters>(&data, Infinity::Allow, SignConvention::Lexicographic)
 }
 
-fn read_fq(cursor: &mut Cursor<&[u8]>) -> Result<Fq, ()> {
-    let mut bi = BigInteger384::read(cursor).map_err(|_e| ())?;
-    let mut res: BigInteger384 = 0.into();
-    for (i, res) in bi.as_mut().iter_mut().zip(res.as_mut().iter_mut().rev()) {
-        *res = i.to_be();
-    }
+pub fn read_g1affine_slice(data: &[u8]) -> Result<(G1Affine, usize), AffineDecodeError> {
+    read_affine_slice::<g1::Parameters>(data, Infinity::Allow)
+}
 
-    Ok(Fq::from_repr(res))
+pub fn read_g2affine_slice(data: &[u8]) -> Result<(G2Affine, usize), AffineDecodeError> {
+    read_affine_slice::<g2::Parameters>(data, Infinity::Allow)
 }
 
-struct G1Uncompressed {
-    data: [u8; 96],
+pub fn read_compressed_g1affine_slice(data: &[u8]) -> Result<(G1Affine, usize), AffineDecodeError> {
+    read_compressed_affine_slice::<g1::Parameters>(
+        data,
+        Infinity::Allow,
+        SignConvention::Lexicographic,
+    )
 }
 
-impl G1Uncompressed {
-    fn new(data: [u8; 96]) -> Self {
-        G1Uncompressed { data }
-    }
+pub fn read_compressed_g2affine_slice(data: &[u8]) -> Result<(G2Affine, usize), AffineDecodeError> {
+    read_compressed_affine_slice::<g2::Parameters>(
+        data,
+        Infinity::Allow,
+        SignConvention::Lexicographic,
+    )
+}
+
+pub fn read_compressed_g1affine_ct(data: [u8; 48]) -> Result<G1Affine, AffineDecodeError> {
+    read_compressed_affine_ct::<g1::Parameters>(&data, Infinity::Allow)
+}
 
-    fn into_affine(&self) -> Result<G1Affine, ()> {
-        let affine = self.into_affine_unchecked()?;
+pub fn read_compressed_g2affine_ct(data: [u8; 96]) -> Result<G2Affine, AffineDecodeError> {
+    read_compressed_affine_ct::<g2::Parameters>(&data, Infinity::Allow)
+}
 
-        if !affine.is_on_curve() {
-            return Err(());
-        } else if !affine.is_in_correct_subgroup_assuming_on_curve() {
-            return Err(());
-        } else {
-            Ok(affine)
+/// Checks that a G1 point is on the curve and in the prime order subgroup.
+pub fn validate_g1(point: &G1Affine) -> Result<(), AffineDecodeError> {
+    validate(point)
+}
+
+/// Checks that a G2 point is on the curve and in the prime order subgroup.
+pub fn validate_g2(point: &G2Affine) -> Result<(), AffineDecodeError> {
+    validate(point)
+}
+
+pub fn write_g1affine(point: &G1Affine) -> [u8; 96] {
+    let mut res = [0u8; 96];
+    write_affine(point, &mut res);
+    res
+}
+
+pub fn write_g2affine(point: &G2Affine) -> [u8; 192] {
+    let mut res = [0u8; 192];
+    write_affine(point, &mut res);
+    res
+}
+
+pub fn write_compressed_g1affine(point: &G1Affine) -> [u8; 48] {
+    let mut res = [0u8; 48];
+    write_compressed_affine(point, &mut res, SignConvention::Lexicographic);
+    res
+}
+
+pub fn write_compressed_g2affine(point: &G2Affine) -> [u8; 96] {
+    let mut res = [0u8; 96];
+    write_compressed_affine(point, &mut res, SignConvention::Lexicographic);
+    res
+}
+
+/// Reason for rejecting a point encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AffineDecodeError {
+    /// The encoding has a wrong number of bytes.
+    InvalidLength,
+    /// The compression, infinity and sign flags are inconsistent with the encoding.
+    InvalidFlags,
+    /// The point does not satisfy the curve equation.
+    NotOnCurve,
+    /// The point is on the curve, but not in the prime order subgroup.
+    WrongSubgroup,
+    /// A coordinate is not smaller than the field modulus.
+    NonCanonicalField,
+    /// The point at infinity was encountered where it is not allowed.
+    UnexpectedInfinity,
+}
+
+/// Determines how the readers treat an encoding of the point at infinity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Infinity {
+    /// The point at infinity is accepted if the rest of the encoding consists of zeroes.
+    Allow,
+    /// The point at infinity is always rejected, e.g. for proof elements.
+    Reject,
+}
+
+/// Determines which of the two possible y-coordinates the sign flag of a compressed point
+/// refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignConvention {
+    /// The flag is set if `y` is lexicographically largest, as in bellman and zcash.
+    Lexicographic,
+    /// The flag is set if `sgn0(y)` is set, as in the IETF BLS drafts.
+    Sgn0,
+}
+
+impl SignConvention {
+    /// Returns the value of the sign flag for `y`.
+    pub fn sign<F: EncodedField>(self, y: &F) -> bool {
+        match self {
+            SignConvention::Lexicographic => *y > y.neg(),
+            SignConvention::Sgn0 => y.sgn0(),
         }
     }
+}
 
-    fn into_affine_unchecked(&self) -> Result<G1Affine, ()> {
-        // Create a copy of this representation.
-        let mut copy = self.data;
+/// Base field of a curve which elements have a fixed size big-endian encoding.
+pub trait EncodedField: SquareRootField {
+    /// Size of the encoded field element in bytes.
+    const SIZE: usize;
 
-        if copy[0] & (1 << 7) != 0 {
-            // Distinguisher bit is set, but this should be uncompressed!
-            return Err(());
-        }
+    /// Reads the field element from the first `SIZE` bytes of `data` without copying them.
+    ///
+    /// If `mask_flags` is set, the three most significant bits, which carry the point flags,
+    /// are ignored. Fields whose modulus leaves fewer than three free bits in the encoding
+    /// have no room for the flags and fail with `InvalidFlags`.
+    fn read_slice(data: &[u8], mask_flags: bool) -> Result<Self, AffineDecodeError>;
 
-        if copy[0] & (1 << 6) != 0 {
-            // This is the point at infinity, which means that if we mask away
-            // the first two bits, the entire representation should consist
-            // of zeroes.
-            copy[0] &= 0x3f;
+    /// Same as `read_slice` without branching on the value of the encoding. An encoding not
+    /// smaller than the modulus is read as zero, which is reported through the returned
+    /// `Choice`.
+    fn read_slice_ct(data: &[u8], mask_flags: bool) -> Result<(Self, Choice), AffineDecodeError>;
 
-            if copy.iter().all(|b| *b == 0) {
-                Ok(G1Affine::zero())
-            } else {
-                return Err(());
+    /// Square root, and whether it exists, computed without branching on the value when the
+    /// characteristic is 3 mod 4. Other fields fall back to `SquareRootField::sqrt`.
+    fn sqrt_ct(&self) -> (Self, Choice);
+
+    /// Writes the field element into exactly `SIZE` bytes.
+    fn write(&self, buf: &mut [u8]);
+
+    /// The `sgn0` function of the IETF hash-to-curve specification.
+    fn sgn0(&self) -> bool;
+}
+
+macro_rules! impl_encoded_prime_field {
+    ($field: ident, $params: ident, $size: expr) => {
+        impl<P: $params> EncodedField for $field<P> {
+            const SIZE: usize = $size;
+
+            fn read_slice(data: &[u8], mask_flags: bool) -> Result<Self, AffineDecodeError> {
+                read_fp(data, mask_flags, Canonicity::Strict)
             }
-        } else {
-            if copy[0] & (1 << 5) != 0 {
-                // The bit indicating the y-coordinate should be lexicographically
-                // largest is set, but this is an uncompressed element.
-                return Err(());
+
+            fn read_slice_ct(
+                data: &[u8],
+                mask_flags: bool,
+            ) -> Result<(Self, Choice), AffineDecodeError> {
+                read_fp_ct(data, mask_flags)
+            }
+
+            fn sqrt_ct(&self) -> (Self, Choice) {
+                sqrt_fp_ct(self)
             }
 
-            // Unset the three most significant bits.
-            copy[0] &= 0x1f;
+            fn write(&self, buf: &mut [u8]) {
+                write_fp(self, buf)
+            }
+
+            fn sgn0(&self) -> bool {
+                self.into_repr().is_odd()
+            }
+        }
+    };
+}
 
-            let mut cursor = Cursor::new(&copy as &[u8]);
-            let x = read_fq(&mut cursor)?;
-            let y = read_fq(&mut cursor)?;
+impl_encoded_prime_field!(Fp256, Fp256Parameters, 32);
+impl_encoded_prime_field!(Fp384, Fp384Parameters, 48);
+
+impl<P> EncodedField for Fp2<P>
+where
+    P: Fp2Parameters,
+    P::Fp: EncodedField,
+{
+    const SIZE: usize = 2 * P::Fp::SIZE;
+
+    fn read_slice(data: &[u8], mask_flags: bool) -> Result<Self, AffineDecodeError> {
+        let c1 = P::Fp::read_slice(&data[..P::Fp::SIZE], mask_flags)?;
+        let c0 = P::Fp::read_slice(&data[P::Fp::SIZE..], false)?;
+        Ok(Fp2::new(c0, c1))
+    }
+
+    fn read_slice_ct(data: &[u8], mask_flags: bool) -> Result<(Self, Choice), AffineDecodeError> {
+        let (c1, c1_canonical) = P::Fp::read_slice_ct(&data[..P::Fp::SIZE], mask_flags)?;
+        let (c0, c0_canonical) = P::Fp::read_slice_ct(&data[P::Fp::SIZE..], false)?;
+        Ok((Fp2::new(c0, c1), c1_canonical & c0_canonical))
+    }
+
+    fn sqrt_ct(&self) -> (Self, Choice) {
+        sqrt_fp2_ct(self)
+    }
+
+    fn write(&self, buf: &mut [u8]) {
+        self.c1.write(&mut buf[..P::Fp::SIZE]);
+        self.c0.write(&mut buf[P::Fp::SIZE..]);
+    }
+
+    fn sgn0(&self) -> bool {
+        self.c0.sgn0() || (self.c0.is_zero() && self.c1.sgn0())
+    }
+}
+
+/// Determines how field element encodings not smaller than the modulus are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Canonicity {
+    /// Encodings not smaller than the modulus are rejected.
+    Strict,
+    /// Encodings not smaller than the modulus are reduced modulo the field modulus.
+    Lenient,
+}
 
-            Ok(G1Affine::new(x, y, false))
+/// Reads a BLS12-381 base field element from its 48 byte big-endian encoding.
+pub fn read_fq(data: &[u8; 48], canonicity: Canonicity) -> Result<Fq, AffineDecodeError> {
+    read_fp(data, false, canonicity)
+}
+
+/// Reads a BLS12-381 quadratic extension field element encoded as `c1 || c0`.
+pub fn read_fq2(data: &[u8; 96], canonicity: Canonicity) -> Result<Fq2, AffineDecodeError> {
+    let c1 = read_fp(&data[..48], false, canonicity)?;
+    let c0 = read_fp(&data[48..], false, canonicity)?;
+    Ok(Fq2::new(c0, c1))
+}
+
+/// Reads `fp` from a big-endian integer.
+///
+/// Limbs are assembled with `u64::from_be_bytes`, which is defined on values rather than on
+/// memory, so the result is the same on little- and big-endian hosts.
+fn read_fp<F: PrimeField>(
+    data: &[u8],
+    mask_flags: bool,
+    canonicity: Canonicity,
+) -> Result<F, AffineDecodeError> {
+    let mut res = read_repr::<F>(data, mask_flags)?;
+    while res >= F::Params::MODULUS {
+        if canonicity == Canonicity::Strict {
+            return Err(AffineDecodeError::NonCanonicalField);
         }
+
+        res.sub_noborrow(&F::Params::MODULUS);
     }
+
+    Ok(F::from_repr(res))
 }
 
-struct G2Uncompressed {
-    data: [u8; 192],
+/// Same as `read_fp` with `Canonicity::Strict`, without branching on the value. A
+/// non-canonical encoding is read as zero.
+fn read_fp_ct<F: PrimeField>(
+    data: &[u8],
+    mask_flags: bool,
+) -> Result<(F, Choice), AffineDecodeError> {
+    let mut res = read_repr::<F>(data, mask_flags)?;
+    let canonical = ct_lt_limbs(res.as_ref(), F::Params::MODULUS.as_ref());
+    for limb in res.as_mut().iter_mut() {
+        *limb = u64::conditional_select(&0, limb, canonical);
+    }
+
+    Ok((F::from_repr(res), canonical))
 }
 
-impl G2Uncompressed {
-    fn new(data: [u8; 192]) -> Self {
-        G2Uncompressed { data }
+/// Reads the limbs of a big-endian integer, clearing the flags if `mask_flags` is set.
+fn read_repr<F: PrimeField>(data: &[u8], mask_flags: bool) -> Result<F::BigInt, AffineDecodeError> {
+    let mut res = F::BigInt::default();
+    if data.len() < res.as_ref().len() * 8 {
+        return Err(AffineDecodeError::InvalidLength);
     }
 
-    fn into_affine(&self) -> Result<G2Affine, ()> {
-        let affine = self.into_affine_unchecked()?;
+    for (limb, chunk) in res.as_mut().iter_mut().rev().zip(data.chunks(8)) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        *limb = u64::from_be_bytes(bytes);
+    }
 
-        if !affine.is_on_curve() {
-            return Err(());
-        } else if !affine.is_in_correct_subgroup_assuming_on_curve() {
-            return Err(());
-        } else {
-            Ok(affine)
+    if mask_flags {
+        // the flags would overlap the value
+        if F::Params::MODULUS_BITS as usize > res.as_ref().len() * 64 - 3 {
+            return Err(AffineDecodeError::InvalidFlags);
         }
+
+        // Unset the three most significant bits.
+        let last = res.as_ref().len() - 1;
+        res.as_mut()[last] &= u64::max_value() >> 3;
     }
 
-    fn into_affine_unchecked(&self) -> Result<G2Affine, ()> {
-        // Create a copy of this representation.
-        let mut copy = self.data;
+    Ok(res)
+}
+
+/// Computes `a^((p + 1) / 4)`, which is a square root of `a` if there is one and `p = 3 mod 4`.
+fn sqrt_fp_ct<F: PrimeField + SquareRootField>(a: &F) -> (F, Choice) {
+    let modulus = F::Params::MODULUS;
+    if modulus.as_ref()[0] & 3 != 3 {
+        return sqrt_vartime(a);
+    }
 
-        if copy[0] & (1 << 7) != 0 {
-            // Distinguisher bit is set, but this should be uncompressed!
-            return Err(());
+    let mut exp = modulus;
+    exp.add_nocarry(&F::BigInt::from(1));
+    exp.div2();
+    exp.div2();
+
+    let y = a.pow(exp);
+    (y, ct_eq_fp(&y.square(), a))
+}
+
+/// Algorithm 9 of "Square root computation over even extension fields", Adj and
+/// Rodríguez-Henríquez, for `p = 3 mod 4` and `u^2 = -1`. Both branches are computed and the
+/// final check replaces the early exit for non-squares.
+fn sqrt_fp2_ct<P: Fp2Parameters>(a: &Fp2<P>) -> (Fp2<P>, Choice) {
+    let modulus = <P::Fp as PrimeField>::Params::MODULUS;
+    if modulus.as_ref()[0] & 3 != 3 || P::NONRESIDUE != -P::Fp::one() {
+        return sqrt_vartime(a);
+    }
+
+    // (p - 3) / 4
+    let mut exp = modulus;
+    exp.div2();
+    exp.div2();
+    let a1 = a.pow(exp);
+    let mut x0 = a1;
+    x0.mul_assign(a);
+    let mut alpha = a1;
+    alpha.mul_assign(&x0);
+
+    // x = u * x0 if alpha = -1
+    let ux0 = Fp2::new(-x0.c1, x0.c0);
+
+    // x = (1 + alpha)^((p - 1) / 2) * x0 otherwise
+    let mut exp = modulus;
+    exp.div2();
+    let mut bx0 = alpha;
+    bx0.add_assign(&Fp2::one());
+    bx0 = bx0.pow(exp);
+    bx0.mul_assign(&x0);
+
+    let minus_one = -Fp2::<P>::one();
+    let alpha_is_minus_one =
+        ct_eq_fp(&alpha.c0, &minus_one.c0) & ct_eq_fp(&alpha.c1, &minus_one.c1);
+    let x = Fp2::new(
+        ct_select_fp(&bx0.c0, &ux0.c0, alpha_is_minus_one),
+        ct_select_fp(&bx0.c1, &ux0.c1, alpha_is_minus_one),
+    );
+
+    let x2 = x.square();
+    (x, ct_eq_fp(&x2.c0, &a.c0) & ct_eq_fp(&x2.c1, &a.c1))
+}
+
+fn sqrt_vartime<F: SquareRootField>(a: &F) -> (F, Choice) {
+    match a.sqrt() {
+        Some(y) => (y, Choice::from(1)),
+        None => (F::zero(), Choice::from(0)),
+    }
+}
+
+/// Constant-time equality of two prime field elements.
+fn ct_eq_fp<F: PrimeField>(a: &F, b: &F) -> Choice {
+    a.into_repr().as_ref().ct_eq(b.into_repr().as_ref())
+}
+
+/// Constant-time `b` if `choice` is set, `a` otherwise.
+fn ct_select_fp<F: PrimeField>(a: &F, b: &F, choice: Choice) -> F {
+    let mut repr = a.into_repr();
+    for (limb, b) in repr.as_mut().iter_mut().zip(b.into_repr().as_ref()) {
+        *limb = u64::conditional_select(limb, b, choice);
+    }
+
+    F::from_repr(repr)
+}
+
+/// Constant-time `a < b` of two little-endian sequences of limbs of the same length.
+fn ct_lt_limbs(a: &[u64], b: &[u64]) -> Choice {
+    let mut lt = Choice::from(0);
+    let mut eq = Choice::from(1);
+    for (a, b) in a.iter().rev().zip(b.iter().rev()) {
+        lt |= eq & a.ct_lt(b);
+        eq &= a.ct_eq(b);
+    }
+    lt
+}
+
+/// Writes `fp` as a big-endian integer, the inverse of `read_fp`.
+fn write_fp<F: PrimeField>(fp: &F, buf: &mut [u8]) {
+    let repr = fp.into_repr();
+    for (limb, chunk) in repr.as_ref().iter().rev().zip(buf.chunks_mut(8)) {
+        chunk.copy_from_slice(&limb.to_be_bytes());
+    }
+}
+
+/// Checks that a point is on the curve and in the prime order subgroup.
+pub fn validate<P: SWModelParameters>(point: &GroupAffine<P>) -> Result<(), AffineDecodeError> {
+    if !point.is_on_curve() {
+        Err(AffineDecodeError::NotOnCurve)
+    } else if !point.is_in_correct_subgroup_assuming_on_curve() {
+        Err(AffineDecodeError::WrongSubgroup)
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads an uncompressed point, `2 * SIZE` bytes long, of any curve with an encoded base field.
+pub fn read_affine<P>(data: &[u8], infinity: Infinity) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    if data.len() != 2 * P::BaseField::SIZE {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    read_affine_slice(data, infinity).map(|(affine, _)| affine)
+}
+
+/// Reads an uncompressed point from the beginning of `data` without copying it.
+///
+/// Returns the point and the number of bytes consumed.
+pub fn read_affine_slice<P>(
+    data: &[u8],
+    infinity: Infinity,
+) -> Result<(GroupAffine<P>, usize), AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    let size = P::BaseField::SIZE;
+    if data.len() < 2 * size {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    let affine = read_affine_slice_unchecked(&data[..2 * size], infinity)?;
+    validate(&affine)?;
+
+    Ok((affine, 2 * size))
+}
+
+fn read_affine_slice_unchecked<P>(
+    data: &[u8],
+    infinity: Infinity,
+) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    let size = P::BaseField::SIZE;
+
+    if data[0] & (1 << 7) != 0 {
+        // Distinguisher bit is set, but this should be uncompressed!
+        return Err(AffineDecodeError::InvalidFlags);
+    }
+
+    if data[0] & (1 << 6) != 0 {
+        if infinity == Infinity::Reject {
+            return Err(AffineDecodeError::UnexpectedInfinity);
         }
 
-        if copy[0] & (1 << 6) != 0 {
-            // This is the point at infinity, which means that if we mask away
-            // the first two bits, the entire representation should consist
-            // of zeroes.
-            copy[0] &= 0x3f;
+        // This is the point at infinity, which means that if we mask away
+        // the first two bits, the entire representation should consist
+        // of zeroes.
+        if data[0] & 0x3f == 0 && data[1..].iter().all(|b| *b == 0) {
+            Ok(GroupAffine::zero())
+        } else {
+            Err(AffineDecodeError::InvalidFlags)
+        }
+    } else {
+        if data[0] & (1 << 5) != 0 {
+            // The bit indicating the y-coordinate should be lexicographically
+            // largest is set, but this is an uncompressed element.
+            return Err(AffineDecodeError::InvalidFlags);
+        }
 
-            if copy.iter().all(|b| *b == 0) {
-                Ok(G2Affine::zero())
-            } else {
-                Err(())
-            }
+        let x = P::BaseField::read_slice(&data[..size], true)?;
+        let y = P::BaseField::read_slice(&data[size..], false)?;
+
+        Ok(GroupAffine::new(x, y, false))
+    }
+}
+
+/// Reads a compressed point, `SIZE` bytes long, of any curve with an encoded base field.
+pub fn read_compressed_affine<P>(
+    data: &[u8],
+    infinity: Infinity,
+    convention: SignConvention,
+) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    if data.len() != P::BaseField::SIZE {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    read_compressed_affine_slice(data, infinity, convention).map(|(affine, _)| affine)
+}
+
+/// Reads a compressed point from the beginning of `data` without copying it.
+///
+/// Returns the point and the number of bytes consumed.
+pub fn read_compressed_affine_slice<P>(
+    data: &[u8],
+    infinity: Infinity,
+    convention: SignConvention,
+) -> Result<(GroupAffine<P>, usize), AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    let size = P::BaseField::SIZE;
+    if data.len() < size {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    let affine = read_compressed_affine_slice_unchecked(&data[..size], infinity, convention)?;
+
+    // decompression guarantees that this is on the curve
+
+    if !affine.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(AffineDecodeError::WrongSubgroup);
+    } else {
+        Ok((affine, size))
+    }
+}
+
+fn read_compressed_affine_slice_unchecked<P>(
+    data: &[u8],
+    infinity: Infinity,
+    convention: SignConvention,
+) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    if data[0] & (1 << 7) == 0 {
+        // Distinguisher bit is set, but this should be uncompressed!
+        return Err(AffineDecodeError::InvalidFlags);
+    }
+
+    if data[0] & (1 << 6) != 0 {
+        if infinity == Infinity::Reject {
+            return Err(AffineDecodeError::UnexpectedInfinity);
+        }
+
+        // This is the point at infinity, which means that if we mask away
+        // the first two bits, the entire representation should consist
+        // of zeroes.
+        if data[0] & 0x3f == 0 && data[1..].iter().all(|b| *b == 0) {
+            Ok(GroupAffine::zero())
         } else {
-            if copy[0] & (1 << 5) != 0 {
-                // The bit indicating the y-coordinate should be lexicographically
-                // largest is set, but this is an uncompressed element.
-                return Err(());
-            }
+            return Err(AffineDecodeError::InvalidFlags);
+        }
+    } else {
+        // Determine the sign of the intended y coordinate.
+        let sign = data[0] & (1 << 5) != 0;
+
+        let x = P::BaseField::read_slice(data, true)?;
+        get_point_from_x(x, sign, convention)
+    }
+}
+
+/// Largest field encoding, that of `Fq2`, supported by `read_compressed_affine_ct`.
+const MAX_CT_FIELD_SIZE: usize = 96;
+
+/// Constant-time variant of `read_compressed_affine` for the lexicographic sign convention.
+///
+/// Flag handling, the canonicity of `x`, the square root and the choice between `y` and `-y`
+/// do not branch on the input. The square root is only constant-time for fields of
+/// characteristic 3 mod 4, such as the BLS12-381 base fields, see `EncodedField::sqrt_ct`.
+/// Whether the encoding was accepted is still reported through the returned `Result`.
+///
+/// Fields encoded in more than 96 bytes fail with `InvalidLength`.
+pub fn read_compressed_affine_ct<P>(
+    data: &[u8],
+    infinity: Infinity,
+) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    let size = P::BaseField::SIZE;
+    if data.len() != size || size > MAX_CT_FIELD_SIZE {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    let flags = data[0];
+    let compressed = Choice::from((flags >> 7) & 1);
+    let allow_infinity = Choice::from((infinity == Infinity::Allow) as u8);
+    let infinity = Choice::from((flags >> 6) & 1);
+    let greatest = Choice::from((flags >> 5) & 1);
 
-            // Unset the three most significant bits.
-            copy[0] &= 0x1f;
+    // The point at infinity must be allowed, must not set the greatest flag and must consist
+    // of zeroes.
+    let all_zero = data[1..]
+        .iter()
+        .fold(data[0] & 0x1f, |acc, b| acc | b)
+        .ct_eq(&0);
+    let valid_infinity = allow_infinity & !greatest & all_zero;
 
-            let mut cursor = Cursor::new(&copy as &[u8]);
-            let x_c1 = read_fq(&mut cursor)?;
-            let x_c0 = read_fq(&mut cursor)?;
-            let y_c1 = read_fq(&mut cursor)?;
-            let y_c0 = read_fq(&mut cursor)?;
+    let (x, canonical) = P::BaseField::read_slice_ct(data, true)?;
 
-            Ok(G2Affine::new(
-                Fq2::new(x_c0, x_c1),
-                Fq2::new(y_c0, y_c1),
-                false,
-            ))
+    // Compute x^3 + b
+    let mut x3b = x;
+    x3b.square_in_place();
+    x3b.mul_assign(&x);
+    x3b.add_assign(&P::COEFF_B);
+
+    let (y, has_sqrt) = x3b.sqrt_ct();
+
+    let mut y_bytes = [0u8; MAX_CT_FIELD_SIZE];
+    let mut negy_bytes = [0u8; MAX_CT_FIELD_SIZE];
+    y.write(&mut y_bytes[..size]);
+    y.neg().write(&mut negy_bytes[..size]);
+
+    // Pick y if (y < -y) ^ greatest, -y otherwise.
+    let pick_y = ct_lt_be(&y_bytes[..size], &negy_bytes[..size]) ^ greatest;
+
+    // The point at infinity is represented with x = 0 and y = 1.
+    let mut one_bytes = [0u8; MAX_CT_FIELD_SIZE];
+    P::BaseField::one().write(&mut one_bytes[..size]);
+
+    let mut x_bytes = [0u8; MAX_CT_FIELD_SIZE];
+    x.write(&mut x_bytes[..size]);
+
+    for i in 0..size {
+        let y = u8::conditional_select(&negy_bytes[i], &y_bytes[i], pick_y);
+        y_bytes[i] = u8::conditional_select(&y, &one_bytes[i], infinity);
+        x_bytes[i] = u8::conditional_select(&x_bytes[i], &0, infinity);
+    }
+
+    // both are canonical, as written by `EncodedField::write`
+    let (x, _) = P::BaseField::read_slice_ct(&x_bytes[..size], false)?;
+    let (y, _) = P::BaseField::read_slice_ct(&y_bytes[..size], false)?;
+    let affine = GroupAffine::new(x, y, bool::from(infinity));
+
+    // The reason of a failure is only determined once the point has been computed.
+    if !bool::from(compressed) {
+        return Err(AffineDecodeError::InvalidFlags);
+    } else if bool::from(infinity & !allow_infinity) {
+        return Err(AffineDecodeError::UnexpectedInfinity);
+    } else if bool::from(infinity & !valid_infinity) {
+        return Err(AffineDecodeError::InvalidFlags);
+    } else if bool::from(!infinity & !canonical) {
+        return Err(AffineDecodeError::NonCanonicalField);
+    } else if bool::from(!infinity & !has_sqrt) {
+        return Err(AffineDecodeError::NotOnCurve);
+    } else if !affine.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(AffineDecodeError::WrongSubgroup);
+    } else {
+        Ok(affine)
+    }
+}
+
+/// Constant-time lexicographic `a < b` of two big-endian encodings of the same length.
+fn ct_lt_be(a: &[u8], b: &[u8]) -> Choice {
+    let mut lt = Choice::from(0);
+    let mut eq = Choice::from(1);
+    for (a, b) in a.iter().zip(b.iter()) {
+        lt |= eq & a.ct_lt(b);
+        eq &= a.ct_eq(b);
+    }
+    lt
+}
+
+/// Writes an uncompressed point into exactly `2 * SIZE` bytes of `buf`.
+pub fn write_affine<P>(point: &GroupAffine<P>, buf: &mut [u8])
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    let size = P::BaseField::SIZE;
+    for b in buf[..2 * size].iter_mut() {
+        *b = 0;
+    }
+
+    if point.is_zero() {
+        // Set the second-most significant bit to indicate this point
+        // is at infinity.
+        buf[0] |= 1 << 6;
+    } else {
+        point.x.write(&mut buf[..size]);
+        point.y.write(&mut buf[size..2 * size]);
+    }
+}
+
+/// Writes a compressed point into exactly `SIZE` bytes of `buf`.
+pub fn write_compressed_affine<P>(
+    point: &GroupAffine<P>,
+    buf: &mut [u8],
+    convention: SignConvention,
+) where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
+    let size = P::BaseField::SIZE;
+    for b in buf[..size].iter_mut() {
+        *b = 0;
+    }
+
+    if point.is_zero() {
+        // Set the second-most significant bit to indicate this point
+        // is at infinity.
+        buf[0] |= 1 << 6;
+    } else {
+        point.x.write(&mut buf[..size]);
+
+        // Set the third most significant bit if the sign of the correct
+        // y-coordinate is set.
+        if convention.sign(&point.y) {
+            buf[0] |= 1 << 5;
         }
     }
+
+    // Set highest bit to distinguish this as a compressed element.
+    buf[0] |= 1 << 7;
 }
 
-fn get_point_from_x<P: SWModelParameters>(
+pub(crate) fn get_point_from_x<P>(
     x: P::BaseField,
-    greatest: bool,
-) -> Result<GroupAffine<P>, ()> {
+    sign: bool,
+    convention: SignConvention,
+) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: EncodedField,
+{
     // Compute x^3 + b
     let mut x3b = x;
     x3b.square_in_place();
@@ -179,138 +761,571 @@ fn get_point_from_x<P: SWModelParameters>(
         .map(|y| {
             let negy = y.neg();
 
-            GroupAffine::new(x, if (y < negy) ^ greatest { y } else { negy }, false)
+            GroupAffine::new(x, if convention.sign(&y) == sign { y } else { negy }, false)
         })
-        .ok_or_else(|| ())
+        .ok_or_else(|| AffineDecodeError::NotOnCurve)
 }
 
-struct G1Compressed {
-    data: [u8; 48],
-}
+#[cfg(all(test, feature = "spend-vk"))]
+mod tests {
+    use super::{
+        read_affine, read_compressed_affine, read_compressed_g1affine, read_compressed_g1affine_ct,
+        read_compressed_g1affine_slice, read_compressed_g2affine, read_compressed_g2affine_ct,
+        read_compressed_g2affine_slice, read_g1affine, read_g1affine_slice, read_g2affine,
+        read_g2affine_slice, write_compressed_g1affine, write_compressed_g2affine, write_g1affine,
+        write_g2affine, AffineDecodeError, Canonicity, Infinity,
+    };
+    use super::{
+        read_fq, read_fq2, validate_g1, validate_g2, write_compressed_affine, SignConvention,
+    };
+    use crate::zcash;
+    use algebra::{
+        bls12_381::{g1, g2, Fq, Fq2, G1Affine, G2Affine},
+        fields::Field,
+        prelude::{One, Zero},
+    };
+    use hex_literal::hex;
 
-impl G1Compressed {
-    fn new(data: [u8; 48]) -> Self {
-        G1Compressed { data }
+    #[test]
+    fn test_readg1affine() {
+        let t1 = hex!("0db882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2e19ab445dac720bb1f2b0cd3336f7a1acc62bf1b3a321826264dc7e469281e23b218394d598689da04e136878ff9a7897");
+        let _value = read_g1affine(t1).unwrap();
     }
 
-    fn into_affine(&self) -> Result<G1Affine, ()> {
-        let affine = self.into_affine_unchecked()?;
+    #[test]
+    fn test_readg2affine() {
+        let t2 = hex!("0a416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed8838478104376a98072766c29959358e9cde6a4985618f65ea257e8f288974f4aedde52e5dac2fb7ae5d30eab7cd828a2c8b15f15b16f139f2c33ef33d63befe404e696c97077d17ea42f4ff9d82ec456aaf43914a3d07968111a3a348f157e64c0278a");
+        let _value = read_g2affine(t2).unwrap();
+    }
 
-        // decompression guarantees that this is on the curve
+    #[test]
+    fn test_writeg1affine() {
+        let t1 = hex!("0db882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2e19ab445dac720bb1f2b0cd3336f7a1acc62bf1b3a321826264dc7e469281e23b218394d598689da04e136878ff9a7897");
+        let value = read_g1affine(t1).unwrap();
+        assert_eq!(&write_g1affine(&value)[..], &t1[..]);
 
-        if !affine.is_in_correct_subgroup_assuming_on_curve() {
-            return Err(());
-        } else {
-            Ok(affine)
+        let infinity = write_g1affine(&G1Affine::zero());
+        assert_eq!(infinity[0], 0x40);
+        assert!(infinity[1..].iter().all(|b| *b == 0));
+        assert!(read_g1affine(infinity).unwrap().is_zero());
+    }
+
+    #[test]
+    fn test_writeg2affine() {
+        let t2 = hex!("0a416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed8838478104376a98072766c29959358e9cde6a4985618f65ea257e8f288974f4aedde52e5dac2fb7ae5d30eab7cd828a2c8b15f15b16f139f2c33ef33d63befe404e696c97077d17ea42f4ff9d82ec456aaf43914a3d07968111a3a348f157e64c0278a");
+        let value = read_g2affine(t2).unwrap();
+        assert_eq!(&write_g2affine(&value)[..], &t2[..]);
+
+        let infinity = write_g2affine(&G2Affine::zero());
+        assert_eq!(infinity[0], 0x40);
+        assert!(infinity[1..].iter().all(|b| *b == 0));
+        assert!(read_g2affine(infinity).unwrap().is_zero());
+    }
+
+    #[test]
+    fn test_writecompressedg1affine() {
+        let vk = zcash::spend_vk();
+        for point in vk.gamma_abc_g1.iter().chain(Some(&vk.alpha_g1)) {
+            let compressed = write_compressed_g1affine(point);
+            assert_eq!(compressed[0] & 0x80, 0x80);
+            assert_eq!(&read_compressed_g1affine(compressed).unwrap(), point);
         }
+
+        let infinity = write_compressed_g1affine(&G1Affine::zero());
+        assert_eq!(infinity[0], 0xc0);
+        assert!(infinity[1..].iter().all(|b| *b == 0));
+        assert!(read_compressed_g1affine(infinity).unwrap().is_zero());
     }
 
-    fn into_affine_unchecked(&self) -> Result<G1Affine, ()> {
-        // Create a copy of this representation.
-        let mut copy = self.data;
+    #[test]
+    fn test_writecompressedg2affine() {
+        let vk = zcash::spend_vk();
+        for point in &[vk.beta_g2, vk.gamma_g2, vk.delta_g2] {
+            let compressed = write_compressed_g2affine(point);
+            assert_eq!(compressed[0] & 0x80, 0x80);
+            assert_eq!(&read_compressed_g2affine(compressed).unwrap(), point);
+        }
+
+        let infinity = write_compressed_g2affine(&G2Affine::zero());
+        assert_eq!(infinity[0], 0xc0);
+        assert!(infinity[1..].iter().all(|b| *b == 0));
+        assert!(read_compressed_g2affine(infinity).unwrap().is_zero());
+    }
+
+    #[test]
+    fn test_readcompressedaffine_ct() {
+        let vk = zcash::spend_vk();
+        for point in vk.gamma_abc_g1.iter().chain(Some(&vk.alpha_g1)) {
+            let compressed = write_compressed_g1affine(point);
+            assert_eq!(&read_compressed_g1affine_ct(compressed).unwrap(), point);
+
+            // flipping the sign flag selects the negated point
+            let mut flipped = compressed;
+            flipped[0] ^= 1 << 5;
+            assert_eq!(read_compressed_g1affine_ct(flipped).unwrap(), -*point);
+
+            // uncompressed flag is rejected
+            let mut uncompressed = compressed;
+            uncompressed[0] &= 0x7f;
+            assert!(read_compressed_g1affine_ct(uncompressed).is_err());
+        }
 
-        if copy[0] & (1 << 7) == 0 {
-            // Distinguisher bit is set, but this should be uncompressed!
-            return Err(());
+        for point in &[vk.beta_g2, vk.gamma_g2, vk.delta_g2] {
+            let compressed = write_compressed_g2affine(point);
+            assert_eq!(&read_compressed_g2affine_ct(compressed).unwrap(), point);
         }
 
-        if copy[0] & (1 << 6) != 0 {
-            // This is the point at infinity, which means that if we mask away
-            // the first two bits, the entire representation should consist
-            // of zeroes.
-            copy[0] &= 0x3f;
+        let infinity = write_compressed_g1affine(&G1Affine::zero());
+        assert!(read_compressed_g1affine_ct(infinity).unwrap().is_zero());
+
+        let mut infinity_greatest = infinity;
+        infinity_greatest[0] |= 1 << 5;
+        assert!(read_compressed_g1affine_ct(infinity_greatest).is_err());
+
+        let infinity = write_compressed_g2affine(&G2Affine::zero());
+        assert!(read_compressed_g2affine_ct(infinity).unwrap().is_zero());
+
+        // x = p is rejected as by the variable-time reader
+        let mut non_canonical = MODULUS;
+        non_canonical[0] |= 1 << 7;
+        assert_eq!(
+            read_compressed_g1affine_ct(non_canonical).err(),
+            Some(AffineDecodeError::NonCanonicalField)
+        );
+        assert_eq!(
+            read_compressed_g1affine(non_canonical).err(),
+            Some(AffineDecodeError::NonCanonicalField)
+        );
+
+        // x^3 + 4 is not a square for x = 1
+        let mut not_on_curve = [0u8; 48];
+        not_on_curve[0] = 1 << 7;
+        not_on_curve[47] = 1;
+        assert_eq!(
+            read_compressed_g1affine_ct(not_on_curve).err(),
+            Some(AffineDecodeError::NotOnCurve)
+        );
+    }
+
+    #[test]
+    fn test_sqrt_ct() {
+        use super::EncodedField;
+        use algebra::fields::SquareRootField;
 
-            if copy.iter().all(|b| *b == 0) {
-                Ok(G1Affine::zero())
-            } else {
-                return Err(());
+        for i in 0..32u64 {
+            let a = Fq::from(i);
+            let (y, is_square) = a.sqrt_ct();
+            assert_eq!(bool::from(is_square), a.sqrt().is_some(), "{}", i);
+            if bool::from(is_square) {
+                assert_eq!(y.square(), a);
             }
-        } else {
-            // Determine if the intended y coordinate must be greater
-            // lexicographically.
-            let greatest = copy[0] & (1 << 5) != 0;
 
-            // Unset the three most significant bits.
-            copy[0] &= 0x1f;
+            let a = Fq2::new(Fq::from(i), Fq::from(3 * i + 1));
+            let (y, is_square) = a.sqrt_ct();
+            assert_eq!(bool::from(is_square), a.sqrt().is_some(), "{}", i);
+            if bool::from(is_square) {
+                assert_eq!(y.square(), a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reject_infinity() {
+        let infinity = write_g1affine(&G1Affine::zero());
+        assert!(read_affine::<g1::Parameters>(&infinity, Infinity::Allow).is_ok());
+        assert!(read_affine::<g1::Parameters>(&infinity, Infinity::Reject).is_err());
+
+        let infinity = write_g2affine(&G2Affine::zero());
+        assert!(read_affine::<g2::Parameters>(&infinity, Infinity::Allow).is_ok());
+        assert!(read_affine::<g2::Parameters>(&infinity, Infinity::Reject).is_err());
+
+        let infinity = write_compressed_g1affine(&G1Affine::zero());
+        assert!(read_compressed_affine::<g1::Parameters>(&infinity, Infinity::Allow, LEX).is_ok());
+        assert!(
+            read_compressed_affine::<g1::Parameters>(&infinity, Infinity::Reject, LEX).is_err()
+        );
 
-            let mut cursor = Cursor::new(&copy as &[u8]);
-            let x = read_fq(&mut cursor)?;
-            get_point_from_x(x, greatest)
+        let infinity = write_compressed_g2affine(&G2Affine::zero());
+        assert!(read_compressed_affine::<g2::Parameters>(&infinity, Infinity::Allow, LEX).is_ok());
+        assert!(
+            read_compressed_affine::<g2::Parameters>(&infinity, Infinity::Reject, LEX).is_err()
+        );
+
+        // non-infinity points are unaffected by the mode
+        let vk = zcash::spend_vk();
+        let alpha = write_g1affine(&vk.alpha_g1);
+        assert!(read_affine::<g1::Parameters>(&alpha, Infinity::Reject).is_ok());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // modulus of the base field, big-endian
+        let modulus = hex!("1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab");
+        let vk = zcash::spend_vk();
+        let alpha = write_g1affine(&vk.alpha_g1);
+
+        // a coordinate equal to the modulus
+        let mut data = alpha;
+        data[..48].copy_from_slice(&modulus);
+        assert_eq!(
+            read_g1affine(data),
+            Err(AffineDecodeError::NonCanonicalField)
+        );
+
+        let mut data = [0u8; 48];
+        data.copy_from_slice(&modulus);
+        data[0] |= 0x80;
+        assert_eq!(
+            read_compressed_g1affine(data),
+            Err(AffineDecodeError::NonCanonicalField)
+        );
+
+        // alpha with `y + p` in place of `y`, which would reduce to the same point
+        let mut data = alpha;
+        let mut carry = 0u16;
+        for (byte, m) in data[48..].iter_mut().rev().zip(modulus.iter().rev()) {
+            let sum = *byte as u16 + *m as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
         }
+        assert_eq!(carry, 0);
+        assert_eq!(
+            read_g1affine(data),
+            Err(AffineDecodeError::NonCanonicalField)
+        );
+
+        let mut data = alpha;
+        data[95] ^= 1;
+        assert_eq!(read_g1affine(data), Err(AffineDecodeError::NotOnCurve));
     }
-}
 
-struct G2Compressed {
-    data: [u8; 96],
-}
+    #[test]
+    fn test_read_slice() {
+        let vk = zcash::spend_vk();
+
+        // trailing bytes are left for the caller
+        let mut buf = [0xffu8; 100];
+        buf[..96].copy_from_slice(&write_g1affine(&vk.alpha_g1));
+        assert_eq!(read_g1affine_slice(&buf).unwrap(), (vk.alpha_g1, 96));
+        assert_eq!(
+            read_g1affine_slice(&buf[..95]),
+            Err(AffineDecodeError::InvalidLength)
+        );
+
+        let mut buf = [0xffu8; 200];
+        buf[..192].copy_from_slice(&write_g2affine(&vk.beta_g2));
+        assert_eq!(read_g2affine_slice(&buf).unwrap(), (vk.beta_g2, 192));
+        assert_eq!(
+            read_g2affine_slice(&buf[..191]),
+            Err(AffineDecodeError::InvalidLength)
+        );
 
-impl G2Compressed {
-    fn new(data: [u8; 96]) -> Self {
-        G2Compressed { data }
+        let mut buf = [0xffu8; 50];
+        buf[..48].copy_from_slice(&write_compressed_g1affine(&vk.alpha_g1));
+        assert_eq!(
+            read_compressed_g1affine_slice(&buf).unwrap(),
+            (vk.alpha_g1, 48)
+        );
+        assert_eq!(
+            read_compressed_g1affine_slice(&buf[..47]),
+            Err(AffineDecodeError::InvalidLength)
+        );
+
+        let mut buf = [0xffu8; 100];
+        buf[..96].copy_from_slice(&write_compressed_g2affine(&vk.beta_g2));
+        assert_eq!(
+            read_compressed_g2affine_slice(&buf).unwrap(),
+            (vk.beta_g2, 96)
+        );
+        assert_eq!(
+            read_compressed_g2affine_slice(&buf[..95]),
+            Err(AffineDecodeError::InvalidLength)
+        );
     }
 
-    fn into_affine(&self) -> Result<G2Affine, ()> {
-        let affine = self.into_affine_unchecked()?;
+    // The three most significant bits of the first byte carry the compression, infinity and
+    // "y is lexicographically largest" flags. The expectations below follow the readers of the
+    // pairing crate, for both a valid payload and an all-zero payload.
 
-        // decompression guarantees that this is on the curve
+    #[test]
+    fn test_uncompressed_g1_flags() {
+        let point = zcash::spend_vk().alpha_g1;
+        let encoded = write_g1affine(&point);
 
-        if !affine.is_in_correct_subgroup_assuming_on_curve() {
-            return Err(());
-        } else {
-            Ok(affine)
+        for flags in (0..8u8).map(|f| f << 5) {
+            let mut data = encoded;
+            data[0] = (data[0] & 0x1f) | flags;
+            let expected = match flags {
+                0x00 => Ok(point),
+                _ => Err(AffineDecodeError::InvalidFlags),
+            };
+            assert_eq!(read_g1affine(data), expected, "flags {:#x}", flags);
+
+            let mut zero = [0u8; 96];
+            zero[0] = flags;
+            let expected = match flags {
+                0x00 => Err(AffineDecodeError::NotOnCurve),
+                0x40 => Ok(G1Affine::zero()),
+                _ => Err(AffineDecodeError::InvalidFlags),
+            };
+            assert_eq!(read_g1affine(zero), expected, "flags {:#x}", flags);
         }
     }
 
-    fn into_affine_unchecked(&self) -> Result<G2Affine, ()> {
-        // Create a copy of this representation.
-        let mut copy = self.data;
+    #[test]
+    fn test_uncompressed_g2_flags() {
+        let point = zcash::spend_vk().beta_g2;
+        let encoded = write_g2affine(&point);
+
+        for flags in (0..8u8).map(|f| f << 5) {
+            let mut data = encoded;
+            data[0] = (data[0] & 0x1f) | flags;
+            let expected = match flags {
+                0x00 => Ok(point),
+                _ => Err(AffineDecodeError::InvalidFlags),
+            };
+            assert_eq!(read_g2affine(data), expected, "flags {:#x}", flags);
 
-        if copy[0] & (1 << 7) == 0 {
-            // Distinguisher bit is set, but this should be uncompressed!
-            return Err(());
+            let mut zero = [0u8; 192];
+            zero[0] = flags;
+            let expected = match flags {
+                0x00 => Err(AffineDecodeError::NotOnCurve),
+                0x40 => Ok(G2Affine::zero()),
+                _ => Err(AffineDecodeError::InvalidFlags),
+            };
+            assert_eq!(read_g2affine(zero), expected, "flags {:#x}", flags);
         }
+    }
+
+    #[test]
+    fn test_compressed_g1_flags() {
+        let point = zcash::spend_vk().alpha_g1;
+        let encoded = write_compressed_g1affine(&point);
+        let greatest = encoded[0] & 0x20 != 0;
 
-        if copy[0] & (1 << 6) != 0 {
-            // This is the point at infinity, which means that if we mask away
-            // the first two bits, the entire representation should consist
-            // of zeroes.
-            copy[0] &= 0x3f;
+        for flags in (0..8u8).map(|f| f << 5) {
+            let mut data = encoded;
+            data[0] = (data[0] & 0x1f) | flags;
+            let expected = match flags {
+                0x80 if greatest => Ok(-point),
+                0x80 => Ok(point),
+                0xa0 if greatest => Ok(point),
+                0xa0 => Ok(-point),
+                _ => Err(AffineDecodeError::InvalidFlags),
+            };
+            assert_eq!(
+                read_compressed_g1affine(data),
+                expected,
+                "flags {:#x}",
+                flags
+            );
 
-            if copy.iter().all(|b| *b == 0) {
-                Ok(G2Affine::zero())
-            } else {
-                return Err(());
+            let mut zero = [0u8; 48];
+            zero[0] = flags;
+            let result = read_compressed_g1affine(zero);
+            match flags {
+                // x = 0 is on the curve, but it is a point of order 3
+                0x80 | 0xa0 => assert!(result.is_err(), "flags {:#x}", flags),
+                0xc0 => assert_eq!(result, Ok(G1Affine::zero())),
+                _ => assert_eq!(result, Err(AffineDecodeError::InvalidFlags)),
             }
-        } else {
-            // Determine if the intended y coordinate must be greater
-            // lexicographically.
-            let greatest = copy[0] & (1 << 5) != 0;
+        }
+    }
+
+    #[test]
+    fn test_compressed_g2_flags() {
+        let point = zcash::spend_vk().beta_g2;
+        let encoded = write_compressed_g2affine(&point);
+        let greatest = encoded[0] & 0x20 != 0;
 
-            // Unset the three most significant bits.
-            copy[0] &= 0x1f;
+        for flags in (0..8u8).map(|f| f << 5) {
+            let mut data = encoded;
+            data[0] = (data[0] & 0x1f) | flags;
+            let expected = match flags {
+                0x80 if greatest => Ok(-point),
+                0x80 => Ok(point),
+                0xa0 if greatest => Ok(point),
+                0xa0 => Ok(-point),
+                _ => Err(AffineDecodeError::InvalidFlags),
+            };
+            assert_eq!(
+                read_compressed_g2affine(data),
+                expected,
+                "flags {:#x}",
+                flags
+            );
 
-            let mut cursor = Cursor::new(&copy as &[u8]);
-            let x_c1 = read_fq(&mut cursor)?;
-            let x_c0 = read_fq(&mut cursor)?;
-            get_point_from_x(Fq2::new(x_c0, x_c1), greatest)
+            let mut zero = [0u8; 96];
+            zero[0] = flags;
+            let result = read_compressed_g2affine(zero);
+            match flags {
+                0x80 | 0xa0 => assert!(result.is_err(), "flags {:#x}", flags),
+                0xc0 => assert_eq!(result, Ok(G2Affine::zero())),
+                _ => assert_eq!(result, Err(AffineDecodeError::InvalidFlags)),
+            }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{read_g1affine, read_g2affine};
-    use hex_literal::hex;
+    const LEX: SignConvention = SignConvention::Lexicographic;
+
+    // BLS12-381 base field modulus
+    const MODULUS: [u8; 48] = hex!("1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab");
 
     #[test]
-    fn test_readg1affine() {
-        let t1 = hex!("0db882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2e19ab445dac720bb1f2b0cd3336f7a1acc62bf1b3a321826264dc7e469281e23b218394d598689da04e136878ff9a7897");
-        let _value = read_g1affine(t1).unwrap();
+    fn test_read_fq() {
+        let mut one = [0u8; 48];
+        one[47] = 1;
+        assert_eq!(read_fq(&one, Canonicity::Strict), Ok(Fq::one()));
+
+        let mut below = MODULUS;
+        below[47] -= 1;
+        assert_eq!(read_fq(&below, Canonicity::Strict), Ok(-Fq::one()));
+
+        assert_eq!(
+            read_fq(&MODULUS, Canonicity::Strict),
+            Err(AffineDecodeError::NonCanonicalField)
+        );
+        assert_eq!(read_fq(&MODULUS, Canonicity::Lenient), Ok(Fq::zero()));
+
+        let mut above = MODULUS;
+        above[47] += 1;
+        assert_eq!(
+            read_fq(&above, Canonicity::Strict),
+            Err(AffineDecodeError::NonCanonicalField)
+        );
+        assert_eq!(read_fq(&above, Canonicity::Lenient), Ok(Fq::one()));
+
+        assert_eq!(
+            read_fq(&[0xff; 48], Canonicity::Strict),
+            Err(AffineDecodeError::NonCanonicalField)
+        );
+        assert!(read_fq(&[0xff; 48], Canonicity::Lenient).is_ok());
     }
 
     #[test]
-    fn test_readg2affine() {
-        let t2 = hex!("0a416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed8838478104376a98072766c29959358e9cde6a4985618f65ea257e8f288974f4aedde52e5dac2fb7ae5d30eab7cd828a2c8b15f15b16f139f2c33ef33d63befe404e696c97077d17ea42f4ff9d82ec456aaf43914a3d07968111a3a348f157e64c0278a");
-        let _value = read_g2affine(t2).unwrap();
+    fn test_mask_flags_needs_free_bits() {
+        use super::EncodedField;
+        use algebra::bls12_381::Fr;
+
+        // the 381 bit modulus leaves three free bits in 48 bytes
+        let mut data = [0u8; 48];
+        data[0] = 0xe0;
+        data[47] = 1;
+        assert_eq!(Fq::read_slice(&data, true), Ok(Fq::one()));
+
+        // the 255 bit modulus leaves a single free bit in 32 bytes
+        let mut data = [0u8; 32];
+        data[31] = 1;
+        assert_eq!(Fr::read_slice(&data, false), Ok(Fr::one()));
+        assert_eq!(
+            Fr::read_slice(&data, true),
+            Err(AffineDecodeError::InvalidFlags)
+        );
+    }
+
+    #[test]
+    fn test_read_fq2() {
+        let mut data = [0u8; 96];
+        data[47] = 1;
+        assert_eq!(
+            read_fq2(&data, Canonicity::Strict),
+            Ok(Fq2::new(Fq::zero(), Fq::one()))
+        );
+
+        data[48..].copy_from_slice(&MODULUS);
+        assert_eq!(
+            read_fq2(&data, Canonicity::Strict),
+            Err(AffineDecodeError::NonCanonicalField)
+        );
+        assert_eq!(
+            read_fq2(&data, Canonicity::Lenient),
+            Ok(Fq2::new(Fq::zero(), Fq::one()))
+        );
+    }
+
+    /// Decodes a big-endian integer into little-endian limbs with arithmetic only, so it can
+    /// not depend on the byte order of the host.
+    fn reference_limbs(data: &[u8; 48]) -> [u64; 6] {
+        let mut limbs = [0u64; 6];
+        for (i, byte) in data.iter().rev().enumerate() {
+            limbs[i / 8] |= u64::from(*byte) << (8 * (i % 8));
+        }
+        limbs
+    }
+
+    #[test]
+    fn test_read_fq_limb_order() {
+        use algebra::PrimeField;
+
+        for seed in 0..16u8 {
+            let mut data = [0u8; 48];
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte = (i as u8).wrapping_mul(37).wrapping_add(seed);
+            }
+            // keep the value below the modulus
+            data[0] = 0;
+
+            let fq = read_fq(&data, Canonicity::Strict).unwrap();
+            assert_eq!(fq.into_repr().as_ref(), &reference_limbs(&data)[..]);
+
+            let mut written = [0u8; 48];
+            super::write_fp(&fq, &mut written);
+            assert_eq!(written[..], data[..]);
+        }
+    }
+
+    #[test]
+    fn test_sgn0_convention() {
+        let vk = zcash::spend_vk();
+
+        for point in vk.gamma_abc_g1.iter().chain(Some(&vk.alpha_g1)) {
+            let mut data = [0u8; 48];
+            write_compressed_affine(point, &mut data, SignConvention::Sgn0);
+            let decoded = read_compressed_affine::<g1::Parameters>(
+                &data,
+                Infinity::Allow,
+                SignConvention::Sgn0,
+            );
+            assert_eq!(decoded.as_ref(), Ok(point));
+
+            // the flag is the parity of y
+            let uncompressed = write_g1affine(point);
+            assert_eq!(data[0] & 0x20 != 0, uncompressed[95] & 1 == 1);
+        }
+
+        for point in &[vk.beta_g2, vk.gamma_g2, vk.delta_g2] {
+            let mut data = [0u8; 96];
+            write_compressed_affine(point, &mut data, SignConvention::Sgn0);
+            let decoded = read_compressed_affine::<g2::Parameters>(
+                &data,
+                Infinity::Allow,
+                SignConvention::Sgn0,
+            );
+            assert_eq!(decoded.as_ref(), Ok(point));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let vk = zcash::spend_vk();
+        assert_eq!(validate_g1(&vk.alpha_g1), Ok(()));
+        assert_eq!(validate_g2(&vk.beta_g2), Ok(()));
+        assert_eq!(validate_g1(&G1Affine::zero()), Ok(()));
+        assert_eq!(validate_g2(&G2Affine::zero()), Ok(()));
+
+        let mut off_curve = vk.alpha_g1;
+        off_curve.y = off_curve.y.double();
+        assert_eq!(validate_g1(&off_curve), Err(AffineDecodeError::NotOnCurve));
+
+        let mut off_curve = vk.beta_g2;
+        off_curve.x = off_curve.x.double();
+        assert_eq!(validate_g2(&off_curve), Err(AffineDecodeError::NotOnCurve));
+
+        // (0, 2) satisfies y^2 = x^3 + 4, but it is a point of order 3
+        let wrong_subgroup = G1Affine::new(Fq::zero(), Fq::one().double(), false);
+        assert_eq!(
+            validate_g1(&wrong_subgroup),
+            Err(AffineDecodeError::WrongSubgroup)
+        );
     }
 }
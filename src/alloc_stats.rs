@@ -0,0 +1,325 @@
+//! Allocation accounting for sizing embedded and wasm environments.
+//!
+//! Install a `CountingAllocator` as the global allocator and wrap verifications in
+//! `CountingAllocator::measure`, or report them to an `AllocationObserver`:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator<System> = CountingAllocator::new(System);
+//!
+//! let (result, stats) = ALLOCATOR.measure(|| sapling::accept_sapling(...));
+//! ALLOCATOR.measure_block(bundles.len(), |i| sapling::accept_sapling(...), &mut observer)?;
+//! ```
+//!
+//! Measurements may be nested or run on several threads at once, each keeps its own peak.
+//! Counters are global, so allocations of other threads running at the same time are included.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// Number of measurements that can run at the same time, nested or on different threads.
+pub const MAX_MEASUREMENTS: usize = 8;
+
+/// Allocations made during a measured call.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AllocationStats {
+    /// Total bytes allocated, including memory freed again.
+    pub allocated: usize,
+    /// Greatest number of bytes held at once, above what was held when the call started.
+    ///
+    /// Beyond `MAX_MEASUREMENTS` concurrent measurements this is `allocated`, an upper bound.
+    pub peak: usize,
+}
+
+/// What a measurement reported to an `AllocationObserver` covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationScope {
+    /// Verification of the bundle at this index of the block.
+    Bundle(usize),
+    /// Verification of a whole block.
+    Block,
+}
+
+/// Receives the allocations of measured verifications.
+pub trait AllocationObserver {
+    fn report(&mut self, scope: AllocationScope, stats: AllocationStats);
+}
+
+/// Ignores measurements.
+impl AllocationObserver for () {
+    fn report(&mut self, _scope: AllocationScope, _stats: AllocationStats) {}
+}
+
+impl<F: FnMut(AllocationScope, AllocationStats)> AllocationObserver for F {
+    fn report(&mut self, scope: AllocationScope, stats: AllocationStats) {
+        self(scope, stats)
+    }
+}
+
+/// Peak of one running measurement.
+struct Slot {
+    active: AtomicBool,
+    peak: AtomicUsize,
+}
+
+const FREE_SLOT: Slot = Slot {
+    active: AtomicBool::new(false),
+    peak: AtomicUsize::new(0),
+};
+
+/// Frees the slot when the measurement ends, even if the measured call panics.
+struct SlotGuard<'a>(&'a Slot);
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        self.0.active.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Global allocator wrapper counting the bytes allocated through it.
+pub struct CountingAllocator<A> {
+    inner: A,
+    allocated: AtomicUsize,
+    current: AtomicUsize,
+    slots: [Slot; MAX_MEASUREMENTS],
+}
+
+impl<A> CountingAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        CountingAllocator {
+            inner,
+            allocated: AtomicUsize::new(0),
+            current: AtomicUsize::new(0),
+            slots: [FREE_SLOT; MAX_MEASUREMENTS],
+        }
+    }
+
+    /// Runs `f` and returns its result with the allocations it made.
+    pub fn measure<R, F: FnOnce() -> R>(&self, f: F) -> (R, AllocationStats) {
+        let guard = self.claim_slot();
+        let start = self.current.load(Ordering::SeqCst);
+        let allocated = self.allocated.load(Ordering::SeqCst);
+        if let Some(guard) = &guard {
+            guard.0.peak.store(start, Ordering::SeqCst);
+        }
+
+        let result = f();
+
+        let allocated = self.allocated.load(Ordering::SeqCst) - allocated;
+        let peak = match &guard {
+            Some(guard) => guard.0.peak.load(Ordering::SeqCst).saturating_sub(start),
+            None => allocated,
+        };
+        (result, AllocationStats { allocated, peak })
+    }
+
+    /// Runs `f`, reporting its allocations to `observer` as `scope`.
+    pub fn observe<R, F, O>(&self, scope: AllocationScope, observer: &mut O, f: F) -> R
+    where
+        F: FnOnce() -> R,
+        O: AllocationObserver + ?Sized,
+    {
+        let (result, stats) = self.measure(f);
+        observer.report(scope, stats);
+        result
+    }
+
+    /// Runs `verify` for the bundles `0..bundles` of a block, stopping at the first error.
+    ///
+    /// Reports every bundle that was verified and then the whole block to `observer`.
+    pub fn measure_block<E, F, O>(
+        &self,
+        bundles: usize,
+        mut verify: F,
+        observer: &mut O,
+    ) -> Result<(), E>
+    where
+        F: FnMut(usize) -> Result<(), E>,
+        O: AllocationObserver + ?Sized,
+    {
+        let (result, stats) = self.measure(|| {
+            for index in 0..bundles {
+                self.observe(AllocationScope::Bundle(index), observer, || verify(index))?;
+            }
+            Ok(())
+        });
+        observer.report(AllocationScope::Block, stats);
+        result
+    }
+
+    fn claim_slot(&self) -> Option<SlotGuard> {
+        self.slots
+            .iter()
+            .find(|slot| {
+                slot.active
+                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+            })
+            .map(SlotGuard)
+    }
+
+    fn record_alloc(&self, size: usize) {
+        self.allocated.fetch_add(size, Ordering::SeqCst);
+        let current = self.current.fetch_add(size, Ordering::SeqCst) + size;
+        for slot in self.slots.iter() {
+            if slot.active.load(Ordering::SeqCst) {
+                slot.peak.fetch_max(current, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn record_dealloc(&self, size: usize) {
+        self.current.fetch_sub(size, Ordering::SeqCst);
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            self.record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        self.record_dealloc(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            self.record_dealloc(layout.size());
+            self.record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{AllocationScope, AllocationStats, CountingAllocator, MAX_MEASUREMENTS};
+    use core::alloc::{GlobalAlloc, Layout};
+    use std::{alloc::System, vec::Vec};
+
+    #[test]
+    fn test_measure() {
+        let allocator = CountingAllocator::new(System);
+        let layout = Layout::from_size_align(100, 8).unwrap();
+
+        let held = unsafe { allocator.alloc(layout) };
+        let (_, stats) = allocator.measure(|| unsafe {
+            let a = allocator.alloc(layout);
+            let b = allocator.alloc(layout);
+            allocator.dealloc(a, layout);
+            allocator.dealloc(b, layout);
+            let c = allocator.alloc_zeroed(layout);
+            allocator.dealloc(c, layout);
+        });
+        unsafe { allocator.dealloc(held, layout) };
+
+        assert_eq!(
+            stats,
+            AllocationStats {
+                allocated: 300,
+                peak: 200,
+            }
+        );
+    }
+
+    #[test]
+    fn test_nested_measurements() {
+        let allocator = CountingAllocator::new(System);
+        let layout = Layout::from_size_align(100, 8).unwrap();
+
+        let (inner, outer) = allocator.measure(|| unsafe {
+            let a = allocator.alloc(layout);
+            let (_, inner) = allocator.measure(|| {
+                let b = allocator.alloc(layout);
+                allocator.dealloc(b, layout);
+            });
+            allocator.dealloc(a, layout);
+            inner
+        });
+
+        // the inner measurement does not reset the peak of the outer one
+        assert_eq!(
+            inner,
+            AllocationStats {
+                allocated: 100,
+                peak: 100,
+            }
+        );
+        assert_eq!(
+            outer,
+            AllocationStats {
+                allocated: 200,
+                peak: 200,
+            }
+        );
+
+        // without a free slot the peak falls back to the allocated bytes
+        fn nest(allocator: &CountingAllocator<System>, depth: usize) -> AllocationStats {
+            if depth > 0 {
+                return allocator.measure(|| nest(allocator, depth - 1)).0;
+            }
+
+            let layout = Layout::from_size_align(100, 8).unwrap();
+            let (_, stats) = allocator.measure(|| unsafe {
+                let a = allocator.alloc(layout);
+                allocator.dealloc(a, layout);
+                let b = allocator.alloc(layout);
+                allocator.dealloc(b, layout);
+            });
+            stats
+        }
+        assert_eq!(nest(&allocator, MAX_MEASUREMENTS - 1).peak, 100);
+        assert_eq!(nest(&allocator, MAX_MEASUREMENTS).peak, 200);
+    }
+
+    #[test]
+    fn test_measure_block() {
+        let allocator = CountingAllocator::new(System);
+        let layout = Layout::from_size_align(100, 8).unwrap();
+
+        let mut reports = Vec::new();
+        let mut observer =
+            |scope: AllocationScope, stats: AllocationStats| reports.push((scope, stats.allocated));
+        let result = allocator.measure_block(
+            3,
+            |index| unsafe {
+                let a = allocator.alloc(layout);
+                allocator.dealloc(a, layout);
+                if index == 1 {
+                    Err(index)
+                } else {
+                    Ok(())
+                }
+            },
+            &mut observer,
+        );
+
+        assert_eq!(result, Err(1));
+        assert_eq!(
+            reports,
+            [
+                (AllocationScope::Bundle(0), 100),
+                (AllocationScope::Bundle(1), 100),
+                (AllocationScope::Block, 200),
+            ]
+        );
+    }
+}
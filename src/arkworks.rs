@@ -0,0 +1,421 @@
+//! Conversions between the zcash/bellman encodings and the arkworks canonical serialization.
+//!
+//! arkworks serializes short weierstrass points as little-endian field elements with the flags
+//! stored in the two most significant bits of the last byte: bit 7 is set when the y-coordinate
+//! is lexicographically largest and bit 6 marks the point at infinity. Only compressed points
+//! carry the sign flag, and the point at infinity is written as `x = 0, y = 1` when
+//! uncompressed. Extension field elements are written `c0` first. Proofs are the concatenation
+//! of the compressed `a`, `b` and `c`.
+//!
+//! Note that `ark-bls12-381` 0.4 switched to the zcash encoding for BLS12-381 points, so these
+//! conversions are only needed for artifacts produced by older arkworks (and zexe) releases.
+
+use crate::affine::{self, AffineDecodeError, EncodedField, Infinity, SignConvention};
+use algebra::{
+    bls12_381::{g1, g2},
+    curves::models::short_weierstrass_jacobian::GroupAffine,
+    fields::{
+        Fp2, Fp256, Fp256Parameters, Fp2Parameters, Fp384, Fp384Parameters, FpParameters,
+        PrimeField,
+    },
+    prelude::{One, Zero},
+    SWModelParameters,
+};
+use core::ops::Neg;
+
+/// Set when the y-coordinate is lexicographically largest.
+const Y_IS_LARGEST: u8 = 1 << 7;
+/// Set for the point at infinity.
+const INFINITY: u8 = 1 << 6;
+
+/// Field which elements can be (de)serialized in the arkworks little-endian layout.
+pub trait ArkEncodedField: EncodedField {
+    /// Reads the field element from exactly `SIZE` bytes, ignoring the flag bits.
+    fn read_le(data: &[u8]) -> Result<Self, AffineDecodeError>;
+
+    /// Writes the field element into exactly `SIZE` bytes.
+    fn write_le(&self, buf: &mut [u8]);
+}
+
+macro_rules! impl_ark_encoded_prime_field {
+    ($field: ident, $params: ident) => {
+        impl<P: $params> ArkEncodedField for $field<P> {
+            fn read_le(data: &[u8]) -> Result<Self, AffineDecodeError> {
+                read_fp_le(data)
+            }
+
+            fn write_le(&self, buf: &mut [u8]) {
+                write_fp_le(self, buf)
+            }
+        }
+    };
+}
+
+impl_ark_encoded_prime_field!(Fp256, Fp256Parameters);
+impl_ark_encoded_prime_field!(Fp384, Fp384Parameters);
+
+impl<P> ArkEncodedField for Fp2<P>
+where
+    P: Fp2Parameters,
+    P::Fp: ArkEncodedField,
+{
+    fn read_le(data: &[u8]) -> Result<Self, AffineDecodeError> {
+        let c0 = P::Fp::read_le(&data[..P::Fp::SIZE])?;
+        let c1 = P::Fp::read_le(&data[P::Fp::SIZE..])?;
+        Ok(Fp2::new(c0, c1))
+    }
+
+    fn write_le(&self, buf: &mut [u8]) {
+        self.c0.write_le(&mut buf[..P::Fp::SIZE]);
+        self.c1.write_le(&mut buf[P::Fp::SIZE..]);
+    }
+}
+
+fn read_fp_le<F: PrimeField>(data: &[u8]) -> Result<F, AffineDecodeError> {
+    let mut repr = F::BigInt::default();
+    for (limb, chunk) in repr.as_mut().iter_mut().zip(data.chunks(8)) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(bytes);
+    }
+
+    if repr >= F::Params::MODULUS {
+        return Err(AffineDecodeError::NonCanonicalField);
+    }
+
+    Ok(F::from_repr(repr))
+}
+
+fn write_fp_le<F: PrimeField>(fp: &F, buf: &mut [u8]) {
+    let repr = fp.into_repr();
+    for (limb, chunk) in repr.as_ref().iter().zip(buf.chunks_mut(8)) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+}
+
+/// Writes a point into exactly `SIZE` bytes of `buf` using the arkworks compressed layout.
+pub fn write_ark_compressed<P>(point: &GroupAffine<P>, buf: &mut [u8])
+where
+    P: SWModelParameters,
+    P::BaseField: ArkEncodedField,
+{
+    let size = P::BaseField::SIZE;
+    if point.is_zero() {
+        P::BaseField::zero().write_le(&mut buf[..size]);
+        buf[size - 1] |= INFINITY;
+    } else {
+        point.x.write_le(&mut buf[..size]);
+        if point.y > point.y.neg() {
+            buf[size - 1] |= Y_IS_LARGEST;
+        }
+    }
+}
+
+/// Writes a point into exactly `2 * SIZE` bytes of `buf` using the arkworks uncompressed layout.
+pub fn write_ark_uncompressed<P>(point: &GroupAffine<P>, buf: &mut [u8])
+where
+    P: SWModelParameters,
+    P::BaseField: ArkEncodedField,
+{
+    let size = P::BaseField::SIZE;
+    if point.is_zero() {
+        P::BaseField::zero().write_le(&mut buf[..size]);
+        P::BaseField::one().write_le(&mut buf[size..2 * size]);
+        buf[2 * size - 1] |= INFINITY;
+    } else {
+        point.x.write_le(&mut buf[..size]);
+        point.y.write_le(&mut buf[size..2 * size]);
+    }
+}
+
+/// Reads a point, `SIZE` bytes long, in the arkworks compressed layout.
+pub fn read_ark_compressed<P>(
+    data: &[u8],
+    infinity: Infinity,
+) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: ArkEncodedField,
+{
+    let size = P::BaseField::SIZE;
+    if data.len() != size {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    let flags = data[size - 1];
+    let mut copy = data.to_vec();
+    copy[size - 1] &= !(Y_IS_LARGEST | INFINITY);
+
+    let affine = if flags & INFINITY != 0 {
+        if infinity == Infinity::Reject {
+            return Err(AffineDecodeError::UnexpectedInfinity);
+        }
+
+        if flags & Y_IS_LARGEST != 0 || copy.iter().any(|b| *b != 0) {
+            return Err(AffineDecodeError::InvalidFlags);
+        }
+
+        GroupAffine::zero()
+    } else {
+        let x = P::BaseField::read_le(&copy)?;
+        let greatest = flags & Y_IS_LARGEST != 0;
+        affine::get_point_from_x(x, greatest, SignConvention::Lexicographic)?
+    };
+
+    // decompression guarantees that this is on the curve
+
+    if !affine.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(AffineDecodeError::WrongSubgroup);
+    }
+
+    Ok(affine)
+}
+
+/// Reads a point, `2 * SIZE` bytes long, in the arkworks uncompressed layout.
+///
+/// Like arkworks itself, the sign flag is ignored for uncompressed points. The point at
+/// infinity must be written as arkworks writes it, `x = 0, y = 1`.
+pub fn read_ark_uncompressed<P>(
+    data: &[u8],
+    infinity: Infinity,
+) -> Result<GroupAffine<P>, AffineDecodeError>
+where
+    P: SWModelParameters,
+    P::BaseField: ArkEncodedField,
+{
+    let size = P::BaseField::SIZE;
+    if data.len() != 2 * size {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    let flags = data[2 * size - 1];
+    let mut copy = data.to_vec();
+    copy[2 * size - 1] &= !(Y_IS_LARGEST | INFINITY);
+
+    if flags & INFINITY != 0 {
+        if infinity == Infinity::Reject {
+            return Err(AffineDecodeError::UnexpectedInfinity);
+        }
+
+        let mut expected = copy.clone();
+        P::BaseField::zero().write_le(&mut expected[..size]);
+        P::BaseField::one().write_le(&mut expected[size..]);
+        if copy != expected {
+            return Err(AffineDecodeError::InvalidFlags);
+        }
+
+        return Ok(GroupAffine::zero());
+    }
+
+    let x = P::BaseField::read_le(&copy[..size])?;
+    let y = P::BaseField::read_le(&copy[size..])?;
+    let affine = GroupAffine::new(x, y, false);
+
+    if !affine.is_on_curve() {
+        return Err(AffineDecodeError::NotOnCurve);
+    } else if !affine.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(AffineDecodeError::WrongSubgroup);
+    }
+
+    Ok(affine)
+}
+
+/// Converts a zcash uncompressed G1 point into the arkworks uncompressed layout.
+pub fn g1_to_ark(data: [u8; 96]) -> Result<[u8; 96], AffineDecodeError> {
+    let point = affine::read_g1affine(data)?;
+    let mut res = [0u8; 96];
+    write_ark_uncompressed(&point, &mut res);
+    Ok(res)
+}
+
+/// Converts an arkworks uncompressed G1 point into the zcash uncompressed layout.
+pub fn g1_from_ark(data: [u8; 96]) -> Result<[u8; 96], AffineDecodeError> {
+    let point = read_ark_uncompressed::<g1::Parameters>(&data, Infinity::Allow)?;
+    Ok(affine::write_g1affine(&point))
+}
+
+/// Converts a zcash uncompressed G2 point into the arkworks uncompressed layout.
+pub fn g2_to_ark(data: [u8; 192]) -> Result<[u8; 192], AffineDecodeError> {
+    let point = affine::read_g2affine(data)?;
+    let mut res = [0u8; 192];
+    write_ark_uncompressed(&point, &mut res);
+    Ok(res)
+}
+
+/// Converts an arkworks uncompressed G2 point into the zcash uncompressed layout.
+pub fn g2_from_ark(data: [u8; 192]) -> Result<[u8; 192], AffineDecodeError> {
+    let point = read_ark_uncompressed::<g2::Parameters>(&data, Infinity::Allow)?;
+    Ok(affine::write_g2affine(&point))
+}
+
+/// Converts a zcash compressed G1 point into the arkworks compressed layout.
+pub fn compressed_g1_to_ark(data: [u8; 48]) -> Result<[u8; 48], AffineDecodeError> {
+    let point = affine::read_compressed_g1affine(data)?;
+    let mut res = [0u8; 48];
+    write_ark_compressed(&point, &mut res);
+    Ok(res)
+}
+
+/// Converts an arkworks compressed G1 point into the zcash compressed layout.
+pub fn compressed_g1_from_ark(data: [u8; 48]) -> Result<[u8; 48], AffineDecodeError> {
+    let point = read_ark_compressed::<g1::Parameters>(&data, Infinity::Allow)?;
+    Ok(affine::write_compressed_g1affine(&point))
+}
+
+/// Converts a zcash compressed G2 point into the arkworks compressed layout.
+pub fn compressed_g2_to_ark(data: [u8; 96]) -> Result<[u8; 96], AffineDecodeError> {
+    let point = affine::read_compressed_g2affine(data)?;
+    let mut res = [0u8; 96];
+    write_ark_compressed(&point, &mut res);
+    Ok(res)
+}
+
+/// Converts an arkworks compressed G2 point into the zcash compressed layout.
+pub fn compressed_g2_from_ark(data: [u8; 96]) -> Result<[u8; 96], AffineDecodeError> {
+    let point = read_ark_compressed::<g2::Parameters>(&data, Infinity::Allow)?;
+    Ok(affine::write_compressed_g2affine(&point))
+}
+
+/// Converts a 192 byte zcash Groth16 proof into the arkworks compressed proof layout.
+pub fn proof_to_ark(proof: [u8; 192]) -> Result<[u8; 192], AffineDecodeError> {
+    let (infinity, convention) = (Infinity::Reject, SignConvention::Lexicographic);
+    let a = affine::read_compressed_affine::<g1::Parameters>(&proof[..48], infinity, convention)?;
+    let b =
+        affine::read_compressed_affine::<g2::Parameters>(&proof[48..144], infinity, convention)?;
+    let c = affine::read_compressed_affine::<g1::Parameters>(&proof[144..], infinity, convention)?;
+
+    let mut res = [0u8; 192];
+    write_ark_compressed(&a, &mut res[..48]);
+    write_ark_compressed(&b, &mut res[48..144]);
+    write_ark_compressed(&c, &mut res[144..]);
+    Ok(res)
+}
+
+/// Converts an arkworks compressed Groth16 proof into the 192 byte zcash proof layout.
+pub fn proof_from_ark(proof: [u8; 192]) -> Result<[u8; 192], AffineDecodeError> {
+    let a = read_ark_compressed::<g1::Parameters>(&proof[..48], Infinity::Reject)?;
+    let b = read_ark_compressed::<g2::Parameters>(&proof[48..144], Infinity::Reject)?;
+    let c = read_ark_compressed::<g1::Parameters>(&proof[144..], Infinity::Reject)?;
+
+    let mut res = [0u8; 192];
+    let convention = SignConvention::Lexicographic;
+    affine::write_compressed_affine(&a, &mut res[..48], convention);
+    affine::write_compressed_affine(&b, &mut res[48..144], convention);
+    affine::write_compressed_affine(&c, &mut res[144..], convention);
+    Ok(res)
+}
+
+#[cfg(all(test, feature = "spend-vk"))]
+mod tests {
+    use super::{
+        compressed_g1_from_ark, compressed_g1_to_ark, compressed_g2_from_ark, compressed_g2_to_ark,
+        g1_from_ark, g1_to_ark, g2_from_ark, g2_to_ark, proof_from_ark, proof_to_ark,
+    };
+    use crate::{affine, zcash};
+    use algebra::{
+        bls12_381::{G1Affine, G2Affine},
+        prelude::Zero,
+        AffineCurve,
+    };
+    use hex_literal::hex;
+
+    // the generators and the negated G1 generator, serialized by ark-bls12-381 0.3
+    const ARK_G1: [u8; 96] = hex!("bbc622db0af03afbef1a7af93fe8556c58ac1b173f3a4ea105b974974f8c68c30faca94f8c63952694d79731a7d3f117e1e7c5462923aa0ce48a88a244c73cd0edb3042ccb18db00f60ad0d595e0f5fce48a1d74ed309ea0f1a0aae381f4b308");
+    const ARK_NEG_G1: [u8; 96] = hex!("bbc622db0af03afbef1a7af93fe8556c58ac1b173f3a4ea105b974974f8c68c30faca94f8c63952694d79731a7d3f117cac239b9d6dc54ad1b75cb0eba386f4e3642accad5b95566c907b51def6a8167f2212ecfc8767daaa845d555681d4d11");
+    const ARK_G2: [u8; 192] = hex!("b8bd21c1c85680d4efbb05a82603ac0b77d1e37a640b51b4023b40fad47ae4c65110c52d27050826910a8ff0b2a24a027e2b045d057dace5575d941312f14c3349507fdcbb61dab51ab62099d0d06b59654f2788a0d3ac7d609f7152602be0130128b808865493e189a2ac3bccc93a922cd16051699a426da7d3bd8caa9bfdad1a352edac6cdc98c116e7d7227d5e50cbe795ff05f07a9aaa11dec5c270d373fab992e57ab927426af63a7857e283ecb998bc22bb0d2ac32cc34a72ea0c40606");
+
+    #[test]
+    fn test_arkworks_serialization() {
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+
+        for (point, ark) in &[(g1, ARK_G1), (-g1, ARK_NEG_G1)] {
+            let zcash = affine::write_g1affine(point);
+            assert_eq!(&g1_to_ark(zcash).unwrap()[..], &ark[..]);
+            assert_eq!(&g1_from_ark(*ark).unwrap()[..], &zcash[..]);
+        }
+
+        let zcash = affine::write_g2affine(&g2);
+        assert_eq!(&g2_to_ark(zcash).unwrap()[..], &ARK_G2[..]);
+        assert_eq!(&g2_from_ark(ARK_G2).unwrap()[..], &zcash[..]);
+
+        // compressed, only the sign of y is flagged, in the top bit of the last byte
+        let mut ark = [0u8; 48];
+        ark.copy_from_slice(&ARK_G1[..48]);
+        let zcash = affine::write_compressed_g1affine(&g1);
+        assert_eq!(&compressed_g1_to_ark(zcash).unwrap()[..], &ark[..]);
+        assert_eq!(&compressed_g1_from_ark(ark).unwrap()[..], &zcash[..]);
+
+        ark[47] |= 0x80;
+        let zcash = affine::write_compressed_g1affine(&-g1);
+        assert_eq!(&compressed_g1_to_ark(zcash).unwrap()[..], &ark[..]);
+        assert_eq!(&compressed_g1_from_ark(ark).unwrap()[..], &zcash[..]);
+
+        let mut ark = [0u8; 96];
+        ark.copy_from_slice(&ARK_G2[..96]);
+        let zcash = affine::write_compressed_g2affine(&g2);
+        assert_eq!(&compressed_g2_to_ark(zcash).unwrap()[..], &ark[..]);
+        assert_eq!(&compressed_g2_from_ark(ark).unwrap()[..], &zcash[..]);
+
+        // the point at infinity is x = 0, y = 1 when uncompressed
+        let mut ark = [0u8; 96];
+        ark[48] = 1;
+        ark[95] = 0x40;
+        let zcash = affine::write_g1affine(&G1Affine::zero());
+        assert_eq!(&g1_to_ark(zcash).unwrap()[..], &ark[..]);
+        assert_eq!(&g1_from_ark(ark).unwrap()[..], &zcash[..]);
+
+        let mut ark = [0u8; 192];
+        ark[96] = 1;
+        ark[191] = 0x40;
+        let zcash = affine::write_g2affine(&G2Affine::zero());
+        assert_eq!(&g2_to_ark(zcash).unwrap()[..], &ark[..]);
+        assert_eq!(&g2_from_ark(ark).unwrap()[..], &zcash[..]);
+
+        let mut ark = [0u8; 48];
+        ark[47] = 0x40;
+        let zcash = affine::write_compressed_g1affine(&G1Affine::zero());
+        assert_eq!(&compressed_g1_to_ark(zcash).unwrap()[..], &ark[..]);
+        assert_eq!(&compressed_g1_from_ark(ark).unwrap()[..], &zcash[..]);
+    }
+
+    #[test]
+    fn test_points_roundtrip() {
+        let vk = zcash::spend_vk();
+
+        for point in vk.gamma_abc_g1.iter().chain(Some(&vk.alpha_g1)) {
+            let zcash = affine::write_g1affine(point);
+            let ark = g1_to_ark(zcash).unwrap();
+            assert_eq!(&g1_from_ark(ark).unwrap()[..], &zcash[..]);
+
+            let zcash = affine::write_compressed_g1affine(point);
+            let ark = compressed_g1_to_ark(zcash).unwrap();
+            assert_eq!(&compressed_g1_from_ark(ark).unwrap()[..], &zcash[..]);
+
+            // the x-coordinate is the same integer, written in the opposite byte order
+            let mut x = [0u8; 48];
+            x.copy_from_slice(&ark);
+            x[47] &= 0x3f;
+            x.reverse();
+            assert_eq!(&x[1..], &zcash[1..]);
+            assert_eq!(x[0], zcash[0] & 0x1f);
+        }
+
+        for point in &[vk.beta_g2, vk.gamma_g2, vk.delta_g2] {
+            let zcash = affine::write_g2affine(point);
+            let ark = g2_to_ark(zcash).unwrap();
+            assert_eq!(&g2_from_ark(ark).unwrap()[..], &zcash[..]);
+
+            let zcash = affine::write_compressed_g2affine(point);
+            let ark = compressed_g2_to_ark(zcash).unwrap();
+            assert_eq!(&compressed_g2_from_ark(ark).unwrap()[..], &zcash[..]);
+        }
+    }
+
+    #[test]
+    fn test_proof_roundtrip() {
+        let proof = hex!("8e6c30876e36a18d8d935238815c8d9205a4f1f523ff76b51f614bff1064d1c5fa0a27ec0c43c8a6c2714e7234d32e9a8934a3e9c0f74f1fdac2ddf6be3b13bc933b0478cae556a2d387cc23b05e8b0bd53d9e838ad2d2cb31daccefe256087511b044dfae665f0af0fa968edeea4cbb437a8099724159471adf7946eec434cccc1129f4d1e31d7f3f8be524226c65f28897d3604c14efb64bea6a889b2705617432927229dfa382e78c0ace31cc158fbf3ec1597242955e45af1ee5cfaffd78");
+        let ark = proof_to_ark(proof).unwrap();
+        assert_eq!(&proof_from_ark(ark).unwrap()[..], &proof[..]);
+    }
+}
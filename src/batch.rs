@@ -0,0 +1,150 @@
+use crate::{
+    accept_sapling_final, check_output, check_spend, proof, CvSum, Error,
+    Groth16PreparedVerifyingKey, Groth16Proof, Sapling, VerificationPolicy,
+};
+use algebra::bls12_381::{Fr, G1Projective};
+use alloc::vec::Vec;
+use rand_core::{CryptoRng, RngCore};
+
+/// Verifier of the proofs of many bundles, e.g. of a whole block, with a single combined
+/// pairing check.
+///
+/// Every queued proof is weighted by a random scalar, see `proof::verify_proofs_combined`.
+/// A failed `verify` does not tell which proof is invalid, callers that need to know fall
+/// back to verifying the bundles one by one.
+pub struct BatchVerifier<'a> {
+    spend_vk: &'a Groth16PreparedVerifyingKey,
+    output_vk: &'a Groth16PreparedVerifyingKey,
+    items: Vec<(&'a Groth16PreparedVerifyingKey, Groth16Proof, G1Projective)>,
+}
+
+impl<'a> BatchVerifier<'a> {
+    pub fn new(
+        spend_vk: &'a Groth16PreparedVerifyingKey,
+        output_vk: &'a Groth16PreparedVerifyingKey,
+    ) -> Self {
+        BatchVerifier {
+            spend_vk,
+            output_vk,
+            items: Vec::new(),
+        }
+    }
+
+    /// Queues a spend proof with its public input.
+    pub fn queue_spend(
+        &mut self,
+        zkproof: Groth16Proof,
+        public_input: &[Fr; 7],
+    ) -> Result<(), Error> {
+        let prepared_inputs = proof::prepare_inputs(self.spend_vk, public_input)?;
+        self.items.push((self.spend_vk, zkproof, prepared_inputs));
+        Ok(())
+    }
+
+    /// Queues an output proof with its public input.
+    pub fn queue_output(
+        &mut self,
+        zkproof: Groth16Proof,
+        public_input: &[Fr; 5],
+    ) -> Result<(), Error> {
+        let prepared_inputs = proof::prepare_inputs(self.output_vk, public_input)?;
+        self.items.push((self.output_vk, zkproof, prepared_inputs));
+        Ok(())
+    }
+
+    /// Runs every check of `accept_sapling` on `sapling` except for the proofs, which are
+    /// queued.
+    ///
+    /// Nothing is queued if the bundle is rejected.
+    pub fn queue_sapling(
+        &mut self,
+        policy: &VerificationPolicy,
+        sighash: &[u8; 32],
+        sapling: &Sapling,
+    ) -> Result<(), Error> {
+        let mut total = CvSum::new();
+        let mut spend_proofs = Vec::with_capacity(sapling.spends.len());
+        for spend in sapling.spends.iter() {
+            spend_proofs.push(check_spend(policy, sighash, &mut total, spend, &mut ())?);
+        }
+
+        let mut output_proofs = Vec::with_capacity(sapling.outputs.len());
+        for output in sapling.outputs.iter() {
+            output_proofs.push(check_output(policy, &mut total, output, &mut ())?);
+        }
+
+        accept_sapling_final(policy, sighash, total, sapling, &mut ())?;
+
+        let mut items = Vec::with_capacity(spend_proofs.len() + output_proofs.len());
+        for (zkproof, public_input) in spend_proofs {
+            let prepared_inputs = proof::prepare_inputs(self.spend_vk, &public_input)?;
+            items.push((self.spend_vk, zkproof, prepared_inputs));
+        }
+
+        for (zkproof, public_input) in output_proofs {
+            let prepared_inputs = proof::prepare_inputs(self.output_vk, &public_input)?;
+            items.push((self.output_vk, zkproof, prepared_inputs));
+        }
+
+        self.items.extend(items);
+        Ok(())
+    }
+
+    /// Number of queued proofs.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Verifies every queued proof. `rng` provides the weights of the combined check.
+    pub fn verify<R: RngCore + CryptoRng>(self, rng: &mut R) -> Result<(), Error> {
+        if proof::verify_proofs_combined(&self.items, rng)? {
+            Ok(())
+        } else {
+            Err(Error::CombinedProofsInvalid)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::BatchVerifier;
+    use crate::{
+        tests::{mainnet_sapling, test_rng},
+        zcash, Error, Groth16PreparedVerifyingKey, VerificationPolicy,
+    };
+
+    #[test]
+    fn test_batch_verifier() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+        let mut rng = test_rng();
+
+        let mut batch = BatchVerifier::new(&spend_vk, &output_vk);
+        assert!(batch.is_empty());
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch.verify(&mut rng), Ok(()));
+
+        // a rejected bundle leaves the batch untouched
+        let mut batch = BatchVerifier::new(&spend_vk, &output_vk);
+        let mut invalid = sapling.clone();
+        invalid.binding_sig[40] ^= 1;
+        assert_eq!(
+            batch.queue_sapling(&policy, &sighash, &invalid),
+            Err(Error::BindingSigInvalid)
+        );
+        assert!(batch.is_empty());
+
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        sapling.outputs[0].zkproof[0] ^= 0x20;
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        assert_eq!(batch.verify(&mut rng), Err(Error::CombinedProofsInvalid));
+    }
+}
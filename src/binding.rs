@@ -0,0 +1,237 @@
+use crate::{compute_value_balance, require_non_small_order_point, Point};
+use algebra::{
+    jubjub,
+    prelude::{Group, Zero},
+};
+use core::ops::Neg;
+use zexe_redjubjub::{read_point, FixedGenerators};
+
+/// Opening of a value commitment: `cv = value * G_v + randomness * G_r`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueCommitmentOpening {
+    pub value: u64,
+    pub randomness: jubjub::Fr,
+}
+
+impl ValueCommitmentOpening {
+    /// Computes the value commitment this opening opens.
+    pub fn commitment(&self) -> Point {
+        let value: Point = FixedGenerators::ValueCommitmentValue
+            .point()
+            .mul(&self.value.into())
+            .into();
+        let randomness: Point = FixedGenerators::ValueCommitmentRandomness
+            .point()
+            .mul(&self.randomness)
+            .into();
+        value + &randomness
+    }
+}
+
+/// Checks that `cv` commits to `value` with randomness `rcv`.
+///
+/// Lets the holder of the opening show a third party which value a commitment hides.
+pub fn verify_value_commitment_opening(cv: &[u8; 32], value: u64, rcv: jubjub::Fr) -> bool {
+    let opening = ValueCommitmentOpening {
+        value,
+        randomness: rcv,
+    };
+    read_point(&cv[..]) == Some(opening.commitment())
+}
+
+/// Inconsistency found by `audit_binding_key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKeyMismatch {
+    /// Spend value commitment at this index is invalid or of small order.
+    InvalidSpendCommitment(usize),
+    /// Output value commitment at this index is invalid or of small order.
+    InvalidOutputCommitment(usize),
+    /// Number of openings differs from the number of commitments.
+    OpeningCount,
+    /// Opening at this index does not open the spend value commitment.
+    SpendOpening(usize),
+    /// Opening at this index does not open the output value commitment.
+    OutputOpening(usize),
+    /// Opened values do not add up to the balancing value.
+    ValueBalance,
+    /// The balancing value can not be committed to.
+    InvalidBalancingValue,
+    /// Accumulated key differs from the one derived from the openings.
+    BindingKey,
+}
+
+/// Computes bvk by accumulating value commitments, the same way `accept_sapling` does.
+pub fn accumulate_binding_key(
+    spend_commitments: &[[u8; 32]],
+    output_commitments: &[[u8; 32]],
+    balancing_value: i64,
+) -> Result<Point, BindingKeyMismatch> {
+    let mut total = Point::zero();
+    for (index, cv) in spend_commitments.iter().enumerate() {
+        let cv = require_non_small_order_point(cv)
+            .map_err(|_| BindingKeyMismatch::InvalidSpendCommitment(index))?;
+        total += &cv;
+    }
+
+    for (index, cv) in output_commitments.iter().enumerate() {
+        let cv = require_non_small_order_point(cv)
+            .map_err(|_| BindingKeyMismatch::InvalidOutputCommitment(index))?;
+        total -= &cv;
+    }
+
+    let value_balance = compute_value_balance(balancing_value)
+        .map_err(|_| BindingKeyMismatch::InvalidBalancingValue)?;
+
+    Ok(total + &value_balance.neg())
+}
+
+/// Computes bvk from the commitment randomness alone.
+///
+/// Equal to `accumulate_binding_key` only if the opened values balance.
+pub fn binding_key_from_openings(
+    spends: &[ValueCommitmentOpening],
+    outputs: &[ValueCommitmentOpening],
+) -> Point {
+    let mut randomness = jubjub::Fr::zero();
+    for opening in spends {
+        randomness += &opening.randomness;
+    }
+
+    for opening in outputs {
+        randomness -= &opening.randomness;
+    }
+
+    FixedGenerators::ValueCommitmentRandomness
+        .point()
+        .mul(&randomness)
+        .into()
+}
+
+/// Recomputes bvk from the value commitments and from their openings and checks that both
+/// agree.
+///
+/// Returns the binding verification key on success.
+pub fn audit_binding_key(
+    spend_commitments: &[[u8; 32]],
+    output_commitments: &[[u8; 32]],
+    spend_openings: &[ValueCommitmentOpening],
+    output_openings: &[ValueCommitmentOpening],
+    balancing_value: i64,
+) -> Result<Point, BindingKeyMismatch> {
+    if spend_commitments.len() != spend_openings.len()
+        || output_commitments.len() != output_openings.len()
+    {
+        return Err(BindingKeyMismatch::OpeningCount);
+    }
+
+    let accumulated =
+        accumulate_binding_key(spend_commitments, output_commitments, balancing_value)?;
+
+    for (index, (cv, opening)) in spend_commitments.iter().zip(spend_openings).enumerate() {
+        if require_non_small_order_point(cv) != Ok(opening.commitment()) {
+            return Err(BindingKeyMismatch::SpendOpening(index));
+        }
+    }
+
+    for (index, (cv, opening)) in output_commitments.iter().zip(output_openings).enumerate() {
+        if require_non_small_order_point(cv) != Ok(opening.commitment()) {
+            return Err(BindingKeyMismatch::OutputOpening(index));
+        }
+    }
+
+    let spent: i128 = spend_openings.iter().map(|o| o.value as i128).sum();
+    let created: i128 = output_openings.iter().map(|o| o.value as i128).sum();
+    if spent - created != balancing_value as i128 {
+        return Err(BindingKeyMismatch::ValueBalance);
+    }
+
+    if accumulated != binding_key_from_openings(spend_openings, output_openings) {
+        return Err(BindingKeyMismatch::BindingKey);
+    }
+
+    Ok(accumulated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        audit_binding_key, verify_value_commitment_opening, BindingKeyMismatch,
+        ValueCommitmentOpening,
+    };
+    use algebra::jubjub;
+    use zexe_redjubjub::write_point;
+
+    fn opening(value: u64, randomness: u64) -> ValueCommitmentOpening {
+        ValueCommitmentOpening {
+            value,
+            randomness: jubjub::Fr::from(randomness),
+        }
+    }
+
+    fn encode(opening: &ValueCommitmentOpening) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        write_point(&opening.commitment(), &mut result[..]).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_audit_binding_key() {
+        let spends = [opening(100, 7), opening(50, 11)];
+        let outputs = [opening(120, 13)];
+        let spend_cvs = [encode(&spends[0]), encode(&spends[1])];
+        let output_cvs = [encode(&outputs[0])];
+
+        assert!(audit_binding_key(&spend_cvs, &output_cvs, &spends, &outputs, 30).is_ok());
+
+        assert_eq!(
+            audit_binding_key(&spend_cvs, &output_cvs, &spends, &outputs, 31),
+            Err(BindingKeyMismatch::ValueBalance)
+        );
+        assert_eq!(
+            audit_binding_key(&spend_cvs, &output_cvs, &spends[..1], &outputs, 30),
+            Err(BindingKeyMismatch::OpeningCount)
+        );
+
+        let wrong = [opening(120, 14)];
+        assert_eq!(
+            audit_binding_key(&spend_cvs, &output_cvs, &spends, &wrong, 30),
+            Err(BindingKeyMismatch::OutputOpening(0))
+        );
+
+        let mut invalid = spend_cvs;
+        invalid[1] = [0u8; 32];
+        invalid[1][0] = 2;
+        assert_eq!(
+            audit_binding_key(&invalid, &output_cvs, &spends, &outputs, 30),
+            Err(BindingKeyMismatch::InvalidSpendCommitment(1))
+        );
+    }
+
+    #[test]
+    fn test_verify_value_commitment_opening() {
+        let cv = encode(&opening(100, 7));
+        assert!(verify_value_commitment_opening(
+            &cv,
+            100,
+            jubjub::Fr::from(7u64)
+        ));
+        assert!(!verify_value_commitment_opening(
+            &cv,
+            101,
+            jubjub::Fr::from(7u64)
+        ));
+        assert!(!verify_value_commitment_opening(
+            &cv,
+            100,
+            jubjub::Fr::from(8u64)
+        ));
+
+        let mut invalid = [0u8; 32];
+        invalid[0] = 2;
+        assert!(!verify_value_commitment_opening(
+            &invalid,
+            100,
+            jubjub::Fr::from(7u64)
+        ));
+    }
+}
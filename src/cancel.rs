@@ -0,0 +1,64 @@
+use crate::Error;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Lets a caller abandon a long verification, e.g. of a block that was orphaned meanwhile.
+///
+/// Verifiers poll the token between descriptions and before their final pairing check.
+pub trait Cancellation {
+    fn is_cancelled(&self) -> bool;
+}
+
+/// Never cancels.
+impl Cancellation for () {
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// Cancels once the flag is set, typically from another thread.
+impl Cancellation for AtomicBool {
+    fn is_cancelled(&self) -> bool {
+        self.load(Ordering::Relaxed)
+    }
+}
+
+/// Reason a cancellable verification did not accept its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    /// The input is invalid.
+    Invalid(Error),
+    /// The verification was cancelled before a decision was reached.
+    Cancelled,
+}
+
+impl From<Error> for BatchError {
+    fn from(err: Error) -> Self {
+        BatchError::Invalid(err)
+    }
+}
+
+/// Fails with `BatchError::Cancelled` once `cancel` is cancelled.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub(crate) fn check_cancelled<C: Cancellation + ?Sized>(cancel: &C) -> Result<(), BatchError> {
+    if cancel.is_cancelled() {
+        Err(BatchError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "verify-proofs", feature = "verify-sigs"))]
+mod tests {
+    use super::{check_cancelled, BatchError};
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_check_cancelled() {
+        assert_eq!(check_cancelled(&()), Ok(()));
+
+        let flag = AtomicBool::new(false);
+        assert_eq!(check_cancelled(&flag), Ok(()));
+        flag.store(true, Ordering::Relaxed);
+        assert_eq!(check_cancelled(&flag), Err(BatchError::Cancelled));
+    }
+}
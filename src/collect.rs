@@ -0,0 +1,282 @@
+use crate::{
+    accept_sapling_final, check_output, check_spend, require, transcript::record, verify_proof,
+    CvSum, Error, Groth16PreparedVerifyingKey, Sapling, Transcript, VerificationPolicy,
+};
+use alloc::vec::Vec;
+
+/// Part of a bundle a `Failure` refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    /// The spend description at this index.
+    Spend(usize),
+    /// The output description at this index.
+    Output(usize),
+    /// The balancing value or the binding signature.
+    Bundle,
+}
+
+/// `Failure::check` of a rejection no check label was recorded for.
+pub const UNKNOWN_CHECK: &str = "unknown";
+
+/// A rejected check found by `accept_sapling_collect_all` or `accept_sapling_located`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Failure {
+    pub component: Component,
+    /// Transcript label of the failed check, e.g. `"spend.zkproof.verified"`, or
+    /// `UNKNOWN_CHECK`.
+    pub check: &'static str,
+    pub error: Error,
+}
+
+/// Same as `accept_sapling`, but keeps going after a failure and returns every invalid part
+/// of the bundle.
+///
+/// Each description stops at its first failed check. The binding signature is only checked
+/// if every value commitment is valid, since it can not hold otherwise.
+pub fn accept_sapling_collect_all(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+) -> Result<(), Vec<Failure>> {
+    let mut failures = Vec::new();
+    let mut total = CvSum::new();
+    let mut commitments_valid = true;
+
+    for (index, spend) in sapling.spends.iter().enumerate() {
+        let mut capture = FirstFailure::default();
+        let result = check_spend(policy, sighash, &mut total, spend, &mut capture).and_then(
+            |(zkproof, public_input)| {
+                let error = Error::SpendProofInvalid { index };
+                let result = verify_proof(spend_vk, &zkproof, &public_input)
+                    .and_then(|is_verification_ok| require(is_verification_ok, error));
+                record(&mut capture, "spend.zkproof.verified", result)
+            },
+        );
+        if let Err(error) = result {
+            commitments_valid &= error != Error::InvalidSpendValueCommitment;
+            failures.push(capture.failure(Component::Spend(index), error));
+        }
+    }
+
+    for (index, output) in sapling.outputs.iter().enumerate() {
+        let mut capture = FirstFailure::default();
+        let result = check_output(policy, &mut total, output, &mut capture).and_then(
+            |(zkproof, public_input)| {
+                let error = Error::OutputProofInvalid { index };
+                let result = verify_proof(output_vk, &zkproof, &public_input)
+                    .and_then(|is_verification_ok| require(is_verification_ok, error));
+                record(&mut capture, "output.zkproof.verified", result)
+            },
+        );
+        if let Err(error) = result {
+            commitments_valid &= error != Error::InvalidOutputValueCommitment;
+            failures.push(capture.failure(Component::Output(index), error));
+        }
+    }
+
+    if commitments_valid {
+        let mut capture = FirstFailure::default();
+        if let Err(error) = accept_sapling_final(policy, sighash, total, sapling, &mut capture) {
+            failures.push(capture.failure(Component::Bundle, error));
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+/// Same as `accept_sapling`, additionally reporting which part of the bundle failed.
+///
+/// The checks run in the same order as in `accept_sapling`, so both reject the same bundles
+/// with the same `Error`.
+pub fn accept_sapling_located(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+) -> Result<(), Failure> {
+    let mut total = CvSum::new();
+    let mut spend_proofs = Vec::with_capacity(sapling.spends.len());
+    for (index, spend) in sapling.spends.iter().enumerate() {
+        let mut capture = FirstFailure::default();
+        let result = check_spend(policy, sighash, &mut total, spend, &mut capture);
+        spend_proofs.push(result.map_err(|err| capture.failure(Component::Spend(index), err))?);
+    }
+
+    let mut output_proofs = Vec::with_capacity(sapling.outputs.len());
+    for (index, output) in sapling.outputs.iter().enumerate() {
+        let mut capture = FirstFailure::default();
+        let result = check_output(policy, &mut total, output, &mut capture);
+        output_proofs.push(result.map_err(|err| capture.failure(Component::Output(index), err))?);
+    }
+
+    let mut capture = FirstFailure::default();
+    accept_sapling_final(policy, sighash, total, sapling, &mut capture)
+        .map_err(|err| capture.failure(Component::Bundle, err))?;
+
+    for (index, (zkproof, public_input)) in spend_proofs.iter().enumerate() {
+        let error = Error::SpendProofInvalid { index };
+        let result = verify_proof(spend_vk, zkproof, public_input)
+            .and_then(|is_verification_ok| require(is_verification_ok, error));
+        let check = "spend.zkproof.verified";
+        result.map_err(|error| Failure {
+            component: Component::Spend(index),
+            check,
+            error,
+        })?;
+    }
+
+    for (index, (zkproof, public_input)) in output_proofs.iter().enumerate() {
+        let error = Error::OutputProofInvalid { index };
+        let result = verify_proof(output_vk, zkproof, public_input)
+            .and_then(|is_verification_ok| require(is_verification_ok, error));
+        let check = "output.zkproof.verified";
+        result.map_err(|error| Failure {
+            component: Component::Output(index),
+            check,
+            error,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Transcript remembering the label of the first failed check.
+#[derive(Default)]
+struct FirstFailure {
+    check: Option<&'static str>,
+}
+
+impl FirstFailure {
+    fn failure(&self, component: Component, error: Error) -> Failure {
+        Failure {
+            component,
+            check: self.check.unwrap_or(UNKNOWN_CHECK),
+            error,
+        }
+    }
+}
+
+impl Transcript for FirstFailure {
+    fn append(&mut self, _label: &'static str, _data: &[u8]) {}
+
+    fn decision(&mut self, label: &'static str, passed: bool) {
+        if !passed && self.check.is_none() {
+            self.check = Some(label);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::{
+        accept_sapling_collect_all, accept_sapling_located, Component, Failure, FirstFailure,
+        UNKNOWN_CHECK,
+    };
+    use crate::{
+        tests::mainnet_sapling, zcash, Error, Groth16PreparedVerifyingKey, VerificationPolicy,
+    };
+    use alloc::vec;
+
+    #[test]
+    fn test_collect_all() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        let collect =
+            |sapling| accept_sapling_collect_all(&policy, &spend_vk, &output_vk, &sighash, sapling);
+        assert_eq!(collect(&sapling), Ok(()));
+
+        // break the output proof and the binding signature
+        sapling.outputs[0].zkproof[0] ^= 0x20;
+        sapling.binding_sig[40] ^= 1;
+        assert_eq!(
+            collect(&sapling),
+            Err(vec![
+                Failure {
+                    component: Component::Output(0),
+                    check: "output.zkproof.verified",
+                    error: Error::OutputProofInvalid { index: 0 },
+                },
+                Failure {
+                    component: Component::Bundle,
+                    check: "binding_sig.verified",
+                    error: Error::BindingSigInvalid,
+                },
+            ])
+        );
+
+        // an invalid value commitment makes the binding signature meaningless
+        sapling.spends[0].value_commitment = [0u8; 32];
+        sapling.spends[0].value_commitment[0] = 1;
+        assert_eq!(
+            collect(&sapling),
+            Err(vec![
+                Failure {
+                    component: Component::Spend(0),
+                    check: "spend.value_commitment.valid",
+                    error: Error::InvalidSpendValueCommitment,
+                },
+                Failure {
+                    component: Component::Output(0),
+                    check: "output.zkproof.verified",
+                    error: Error::OutputProofInvalid { index: 0 },
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_located() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        let located =
+            |sapling| accept_sapling_located(&policy, &spend_vk, &output_vk, &sighash, sapling);
+        assert_eq!(located(&sapling), Ok(()));
+
+        sapling.outputs[0].zkproof[0] ^= 0x20;
+        assert_eq!(
+            located(&sapling),
+            Err(Failure {
+                component: Component::Output(0),
+                check: "output.zkproof.verified",
+                error: Error::OutputProofInvalid { index: 0 },
+            })
+        );
+
+        // the cheaper checks run first
+        sapling.spends[0].anchor = [0xff; 32];
+        assert_eq!(
+            located(&sapling),
+            Err(Failure {
+                component: Component::Spend(0),
+                check: "spend.anchor.valid",
+                error: Error::InvalidAnchor,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unrecorded_failure() {
+        let capture = FirstFailure::default();
+        assert_eq!(
+            capture.failure(Component::Bundle, Error::BindingSigInvalid),
+            Failure {
+                component: Component::Bundle,
+                check: UNKNOWN_CHECK,
+                error: Error::BindingSigInvalid,
+            }
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    accept_output_with_transcript, accept_spend_with_transcript, derive_binding_verification_key,
+    read_signature, verify_binding_sig, CvSum, Error, Groth16PreparedVerifyingKey,
+    SaplingOutputDescription, SaplingRef, SaplingSpendDescription, VerificationPolicy,
+};
+
+/// Verifier of a bundle whose descriptions arrive one at a time, e.g. while streaming a
+/// transaction.
+///
+/// Mirrors the verification context of librustzcash: every spend is passed to `check_spend`,
+/// every output to `check_output`, and `final_check` is called once with the remaining fields
+/// of the bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaplingVerificationContext {
+    policy: VerificationPolicy,
+    total: CvSum,
+    spends: usize,
+    outputs: usize,
+}
+
+impl SaplingVerificationContext {
+    pub fn new(policy: VerificationPolicy) -> Self {
+        SaplingVerificationContext {
+            policy,
+            total: CvSum::new(),
+            spends: 0,
+            outputs: 0,
+        }
+    }
+
+    /// Fully verifies the next spend of the bundle and accumulates its value commitment.
+    pub fn check_spend(
+        &mut self,
+        spend_vk: &Groth16PreparedVerifyingKey,
+        sighash: &[u8; 32],
+        spend: &SaplingSpendDescription,
+    ) -> Result<(), Error> {
+        let index = self.spends;
+        self.spends += 1;
+        accept_spend_with_transcript(
+            &self.policy,
+            spend_vk,
+            sighash,
+            &mut self.total,
+            spend,
+            index,
+            &mut (),
+        )
+    }
+
+    /// Fully verifies the next output of the bundle and accumulates its value commitment.
+    pub fn check_output(
+        &mut self,
+        output_vk: &Groth16PreparedVerifyingKey,
+        output: &SaplingOutputDescription,
+    ) -> Result<(), Error> {
+        let index = self.outputs;
+        self.outputs += 1;
+        accept_output_with_transcript(
+            &self.policy,
+            output_vk,
+            &mut self.total,
+            output,
+            index,
+            &mut (),
+        )
+    }
+
+    /// Checks the balancing value and the binding signature against every description passed
+    /// so far.
+    ///
+    /// The bundle is valid only if every previous `check_spend` and `check_output` call
+    /// succeeded.
+    pub fn final_check(
+        &self,
+        balancing_value: i64,
+        sighash: &[u8; 32],
+        binding_sig: &[u8; 64],
+    ) -> Result<(), Error> {
+        let (binding_verification_key, data_to_be_signed) =
+            derive_binding_verification_key(sighash, self.total, balancing_value, &mut ())?;
+        let binding_sig =
+            read_signature(&self.policy, binding_sig).map_err(|_| Error::MalformedBindingSig)?;
+        verify_binding_sig(
+            &binding_verification_key,
+            &data_to_be_signed,
+            &binding_sig,
+            &mut (),
+        )
+    }
+}
+
+/// Same as `accept_sapling` for a bundle given as its parts, without requiring `Vec`s.
+///
+/// Each description is fully verified before the next one is taken from its iterator, so an
+/// invalid bundle may be rejected with a different error than by `accept_sapling`.
+#[allow(clippy::too_many_arguments)]
+pub fn accept_sapling_iter<'a, S, O>(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    spends: S,
+    outputs: O,
+    balancing_value: i64,
+    binding_sig: &[u8; 64],
+) -> Result<(), Error>
+where
+    S: IntoIterator<Item = &'a SaplingSpendDescription>,
+    O: IntoIterator<Item = &'a SaplingOutputDescription>,
+{
+    let mut ctx = SaplingVerificationContext::new(*policy);
+    for spend in spends {
+        ctx.check_spend(spend_vk, sighash, spend)?;
+    }
+
+    for output in outputs {
+        ctx.check_output(output_vk, output)?;
+    }
+
+    ctx.final_check(balancing_value, sighash, binding_sig)
+}
+
+/// Same as `accept_sapling` for a bundle borrowed from the encoded transaction.
+///
+/// Only one description at a time is copied, onto the stack. Each description is fully
+/// verified before the next one, so an invalid bundle may be rejected with a different error
+/// than by `accept_sapling`.
+pub fn accept_sapling_ref(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &SaplingRef,
+) -> Result<(), Error> {
+    let mut ctx = SaplingVerificationContext::new(*policy);
+    for spend in sapling.spends() {
+        ctx.check_spend(spend_vk, sighash, &spend.to_description())?;
+    }
+
+    for output in sapling.outputs() {
+        ctx.check_output(output_vk, &output.to_description())?;
+    }
+
+    ctx.final_check(sapling.balancing_value, sighash, &sapling.binding_sig())
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::{accept_sapling_iter, accept_sapling_ref, SaplingVerificationContext};
+    use crate::{
+        tests::mainnet_sapling, zcash, Error, Groth16PreparedVerifyingKey, VerificationPolicy,
+    };
+
+    #[test]
+    fn test_verification_context() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+
+        let mut ctx = SaplingVerificationContext::new(VerificationPolicy::strict());
+        ctx.check_spend(&spend_vk, &sighash, &sapling.spends[0])
+            .unwrap();
+        ctx.check_output(&output_vk, &sapling.outputs[0]).unwrap();
+        assert_eq!(
+            ctx.final_check(sapling.balancing_value, &sighash, &sapling.binding_sig),
+            Ok(())
+        );
+        assert_eq!(
+            ctx.final_check(sapling.balancing_value + 1, &sighash, &sapling.binding_sig),
+            Err(Error::BindingSigInvalid)
+        );
+
+        let output = sapling.outputs[0].clone();
+        sapling.outputs[0].zkproof[0] ^= 0x20;
+        let mut ctx = SaplingVerificationContext::new(VerificationPolicy::strict());
+        ctx.check_spend(&spend_vk, &sighash, &sapling.spends[0])
+            .unwrap();
+        assert_eq!(
+            ctx.check_output(&output_vk, &sapling.outputs[0]),
+            Err(Error::OutputProofInvalid { index: 0 })
+        );
+
+        // the index counts the outputs checked before, whether they were accepted or not
+        ctx.check_output(&output_vk, &output).unwrap();
+        assert_eq!(
+            ctx.check_output(&output_vk, &sapling.outputs[0]),
+            Err(Error::OutputProofInvalid { index: 2 })
+        );
+    }
+
+    #[test]
+    fn test_accept_sapling_iter() {
+        let (sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        let accept = |spends: &[_], balancing_value| {
+            accept_sapling_iter(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                spends,
+                core::iter::once(&sapling.outputs[0]),
+                balancing_value,
+                &sapling.binding_sig,
+            )
+        };
+        assert_eq!(accept(&sapling.spends[..], sapling.balancing_value), Ok(()));
+        assert_eq!(
+            accept(&sapling.spends[..], sapling.balancing_value - 1),
+            Err(Error::BindingSigInvalid)
+        );
+        assert_eq!(
+            accept(&sapling.spends[..0], sapling.balancing_value),
+            Err(Error::BindingSigInvalid)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "parse")]
+    fn test_accept_sapling_ref() {
+        use crate::SaplingRef;
+        use hex_literal::hex;
+
+        let (sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        // a v4 transaction without transparent parts or JoinSplits
+        let mut tx = hex!("0400008085202f8900000000000000000000").to_vec();
+        let bytes = sapling.to_bytes();
+        tx.extend_from_slice(&bytes[..bytes.len() - 64]);
+        tx.push(0);
+        tx.extend_from_slice(&sapling.binding_sig);
+
+        let sapling_ref = SaplingRef::read(&tx).unwrap();
+        assert_eq!(
+            accept_sapling_ref(&policy, &spend_vk, &output_vk, &sighash, &sapling_ref),
+            Ok(())
+        );
+        assert_eq!(
+            accept_sapling_ref(&policy, &spend_vk, &output_vk, &[0u8; 32], &sapling_ref),
+            Err(Error::SpendAuthSigInvalid)
+        );
+    }
+}
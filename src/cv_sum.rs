@@ -0,0 +1,98 @@
+use crate::{is_canonical_point_encoding, Point};
+use algebra::prelude::Zero;
+use zexe_redjubjub::{read_point, write_point};
+
+/// Sum of spend value commitments minus output value commitments.
+///
+/// Partial sums over disjoint sets of descriptions can be accumulated independently, e.g. on
+/// several threads or machines, and merged before the binding signature is checked with
+/// `accept_binding_sig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CvSum {
+    point: Point,
+}
+
+impl CvSum {
+    /// The empty sum.
+    pub fn new() -> Self {
+        CvSum {
+            point: Point::zero(),
+        }
+    }
+
+    /// Adds the value commitment of a spend.
+    pub fn add(&mut self, value_commitment: &Point) {
+        self.point += value_commitment;
+    }
+
+    /// Subtracts the value commitment of an output.
+    pub fn sub(&mut self, value_commitment: &Point) {
+        self.point -= value_commitment;
+    }
+
+    /// Adds a partial sum accumulated elsewhere.
+    pub fn merge(&mut self, other: &CvSum) {
+        self.point += &other.point;
+    }
+
+    pub fn point(&self) -> Point {
+        self.point
+    }
+
+    /// Canonical 32-byte encoding of the sum.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        write_point(&self.point, &mut result[..]).expect("point is 32 bytes");
+        result
+    }
+
+    /// Reads a sum written by `to_bytes`, rejecting non-canonical encodings.
+    pub fn from_bytes(data: &[u8; 32]) -> Result<Self, ()> {
+        if !is_canonical_point_encoding(&data[..]) {
+            return Err(());
+        }
+
+        read_point(&data[..]).map(|point| CvSum { point }).ok_or(())
+    }
+}
+
+impl Default for CvSum {
+    fn default() -> Self {
+        CvSum::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CvSum;
+    use crate::Point;
+    use zexe_redjubjub::FixedGenerators;
+
+    #[test]
+    fn test_merge_and_serialize() {
+        let a: Point = FixedGenerators::ValueCommitmentValue.point().into();
+        let b: Point = FixedGenerators::ValueCommitmentRandomness.point().into();
+
+        let mut sum = CvSum::new();
+        sum.add(&a);
+        sum.sub(&b);
+
+        let mut left = CvSum::new();
+        left.add(&a);
+        let mut right = CvSum::new();
+        right.sub(&b);
+        left.merge(&right);
+        assert_eq!(left, sum);
+
+        assert_eq!(CvSum::from_bytes(&sum.to_bytes()), Ok(sum));
+        assert_eq!(
+            CvSum::from_bytes(&CvSum::new().to_bytes()),
+            Ok(CvSum::new())
+        );
+
+        // y = 2 is not on the curve
+        let mut invalid = [0u8; 32];
+        invalid[0] = 2;
+        assert_eq!(CvSum::from_bytes(&invalid), Err(()));
+    }
+}
@@ -1,5 +1,9 @@
 use crate::Vec;
-use core::fmt;
+use core::{
+    convert::{TryFrom, TryInto},
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 ///
 #[derive(Clone)]
@@ -74,6 +78,91 @@ pub struct SaplingOutputDescription {
     pub zkproof: [u8; 192],
 }
 
+/// Marginal fee per logical action of ZIP 317, in zatoshi.
+pub const MARGINAL_FEE: u64 = 5_000;
+
+impl Sapling {
+    /// Size of the Sapling fields in a v4 transaction: `valueBalance`, both description
+    /// vectors with their compact size prefixes and `bindingSig` if there are descriptions.
+    pub fn serialized_size(&self) -> usize {
+        let mut size = 8
+            + compact_size_len(self.spends.len())
+            + self.spends.len() * SaplingSpendDescription::SIZE
+            + compact_size_len(self.outputs.len())
+            + self.outputs.len() * SaplingOutputDescription::SIZE;
+        if !self.spends.is_empty() || !self.outputs.is_empty() {
+            size += 64;
+        }
+        size
+    }
+
+    /// Number of ZIP 317 logical actions contributed by the bundle, i.e. the larger of the
+    /// number of spends and outputs.
+    ///
+    /// The conventional fee of a transaction is `MARGINAL_FEE` times the sum of the logical
+    /// actions of all its parts, but at least two actions.
+    pub fn tx_weight_contribution(&self) -> usize {
+        self.spends.len().max(self.outputs.len())
+    }
+
+    /// Appends the Sapling fields in their v4 transaction encoding, `serialized_size` bytes.
+    ///
+    /// A v4 transaction with JoinSplits places them between the outputs and `bindingSig`, the
+    /// last 64 bytes.
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.reserve(self.serialized_size());
+        out.extend_from_slice(&self.balancing_value.to_le_bytes());
+        write_compact_size(out, self.spends.len());
+        for spend in self.spends.iter() {
+            spend.write(out);
+        }
+
+        write_compact_size(out, self.outputs.len());
+        for output in self.outputs.iter() {
+            output.write(out);
+        }
+
+        if !self.spends.is_empty() || !self.outputs.is_empty() {
+            out.extend_from_slice(&self.binding_sig);
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        self.write(&mut result);
+        result
+    }
+}
+
+/// Length of a Bitcoin style compact size encoding of `n`.
+fn compact_size_len(n: usize) -> usize {
+    match n as u64 {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// Appends the Bitcoin style compact size encoding of `n`.
+fn write_compact_size(out: &mut Vec<u8>, n: usize) {
+    match n as u64 {
+        n @ 0..=0xfc => out.push(n as u8),
+        n @ 0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        n @ 0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        n => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
 impl Default for Sapling {
     fn default() -> Self {
         Sapling {
@@ -94,6 +183,17 @@ impl PartialEq<Sapling> for Sapling {
     }
 }
 
+impl Eq for Sapling {}
+
+impl Hash for Sapling {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.balancing_value.hash(state);
+        self.spends.hash(state);
+        self.outputs.hash(state);
+        self.binding_sig[..].hash(state);
+    }
+}
+
 impl fmt::Debug for Sapling {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // TODO: remove allocations
@@ -106,6 +206,36 @@ impl fmt::Debug for Sapling {
     }
 }
 
+impl SaplingSpendDescription {
+    /// Size of the encoded description.
+    pub const SIZE: usize = 32 * 4 + 192 + 64;
+
+    /// Reads a description from the beginning of `data`, in the order of fields of a v4
+    /// transaction.
+    ///
+    /// Returns the description and the number of bytes consumed.
+    pub fn from_slice(data: &[u8]) -> Result<(Self, usize), ()> {
+        if data.len() < Self::SIZE {
+            return Err(());
+        }
+
+        let mut result = SaplingSpendDescription::default();
+        let mut reader = SliceReader::new(data);
+        reader.read_spend(&mut result);
+        Ok((result, reader.offset))
+    }
+
+    /// Appends the description in the encoding read by `from_slice`.
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.value_commitment);
+        out.extend_from_slice(&self.anchor);
+        out.extend_from_slice(&self.nullifier);
+        out.extend_from_slice(&self.randomized_key);
+        out.extend_from_slice(&self.zkproof);
+        out.extend_from_slice(&self.spend_auth_sig);
+    }
+}
+
 impl Default for SaplingSpendDescription {
     fn default() -> Self {
         SaplingSpendDescription {
@@ -130,6 +260,19 @@ impl PartialEq<SaplingSpendDescription> for SaplingSpendDescription {
     }
 }
 
+impl Eq for SaplingSpendDescription {}
+
+impl Hash for SaplingSpendDescription {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value_commitment.hash(state);
+        self.anchor.hash(state);
+        self.nullifier.hash(state);
+        self.randomized_key.hash(state);
+        self.zkproof[..].hash(state);
+        self.spend_auth_sig[..].hash(state);
+    }
+}
+
 impl fmt::Debug for SaplingSpendDescription {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // TODO: remove allocations
@@ -144,6 +287,36 @@ impl fmt::Debug for SaplingSpendDescription {
     }
 }
 
+impl SaplingOutputDescription {
+    /// Size of the encoded description.
+    pub const SIZE: usize = 32 * 3 + 580 + 80 + 192;
+
+    /// Reads a description from the beginning of `data`, in the order of fields of a v4
+    /// transaction.
+    ///
+    /// Returns the description and the number of bytes consumed.
+    pub fn from_slice(data: &[u8]) -> Result<(Self, usize), ()> {
+        if data.len() < Self::SIZE {
+            return Err(());
+        }
+
+        let mut result = SaplingOutputDescription::default();
+        let mut reader = SliceReader::new(data);
+        reader.read_output(&mut result);
+        Ok((result, reader.offset))
+    }
+
+    /// Appends the description in the encoding read by `from_slice`.
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.value_commitment);
+        out.extend_from_slice(&self.note_commitment);
+        out.extend_from_slice(&self.ephemeral_key);
+        out.extend_from_slice(&self.enc_cipher_text);
+        out.extend_from_slice(&self.out_cipher_text);
+        out.extend_from_slice(&self.zkproof);
+    }
+}
+
 impl Default for SaplingOutputDescription {
     fn default() -> Self {
         SaplingOutputDescription {
@@ -168,6 +341,19 @@ impl PartialEq<SaplingOutputDescription> for SaplingOutputDescription {
     }
 }
 
+impl Eq for SaplingOutputDescription {}
+
+impl Hash for SaplingOutputDescription {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value_commitment.hash(state);
+        self.note_commitment.hash(state);
+        self.ephemeral_key.hash(state);
+        self.enc_cipher_text[..].hash(state);
+        self.out_cipher_text[..].hash(state);
+        self.zkproof[..].hash(state);
+    }
+}
+
 impl fmt::Debug for SaplingOutputDescription {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // TODO: remove allocations
@@ -181,3 +367,520 @@ impl fmt::Debug for SaplingOutputDescription {
             .finish()
     }
 }
+
+/// Sapling bundle of a v5 transaction, as specified by ZIP 225.
+///
+/// All spends share a single anchor. Proofs and signatures are encoded in separate arrays
+/// after the descriptions but are kept next to their description here.
+#[derive(Clone)]
+pub struct SaplingBundleV5 {
+    pub balancing_value: i64,
+    pub anchor: [u8; 32],
+    pub spends: Vec<SaplingSpendDescriptionV5>,
+    pub outputs: Vec<SaplingOutputDescription>,
+    pub binding_sig: [u8; 64],
+}
+
+/// Spend description of a v5 transaction, without the anchor.
+#[derive(Clone)]
+pub struct SaplingSpendDescriptionV5 {
+    pub value_commitment: [u8; 32],
+    pub nullifier: [u8; 32],
+    pub randomized_key: [u8; 32],
+    pub zkproof: [u8; 192],
+    pub spend_auth_sig: [u8; 64],
+}
+
+impl SaplingBundleV5 {
+    /// Returns the bundle in the v4 layout, with the shared anchor copied into every spend.
+    pub fn to_sapling(&self) -> Sapling {
+        Sapling {
+            balancing_value: self.balancing_value,
+            spends: self
+                .spends
+                .iter()
+                .map(|spend| spend.with_anchor(self.anchor))
+                .collect(),
+            outputs: self.outputs.clone(),
+            binding_sig: self.binding_sig,
+        }
+    }
+}
+
+/// The spends of a v4 bundle do not share an anchor, which the v5 encoding requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifferentAnchors;
+
+impl TryFrom<&Sapling> for SaplingBundleV5 {
+    type Error = DifferentAnchors;
+
+    /// Moves the anchor of the spends to the bundle. Without spends the anchor is all zeros.
+    fn try_from(sapling: &Sapling) -> Result<Self, Self::Error> {
+        let anchor = sapling
+            .spends
+            .first()
+            .map_or([0u8; 32], |spend| spend.anchor);
+        if sapling.spends.iter().any(|spend| spend.anchor != anchor) {
+            return Err(DifferentAnchors);
+        }
+
+        Ok(SaplingBundleV5 {
+            balancing_value: sapling.balancing_value,
+            anchor,
+            spends: sapling
+                .spends
+                .iter()
+                .map(SaplingSpendDescriptionV5::from)
+                .collect(),
+            outputs: sapling.outputs.clone(),
+            binding_sig: sapling.binding_sig,
+        })
+    }
+}
+
+impl From<&SaplingSpendDescription> for SaplingSpendDescriptionV5 {
+    /// Drops the anchor of `spend`.
+    fn from(spend: &SaplingSpendDescription) -> Self {
+        SaplingSpendDescriptionV5 {
+            value_commitment: spend.value_commitment,
+            nullifier: spend.nullifier,
+            randomized_key: spend.randomized_key,
+            zkproof: spend.zkproof,
+            spend_auth_sig: spend.spend_auth_sig,
+        }
+    }
+}
+
+impl SaplingSpendDescriptionV5 {
+    /// Returns the v4 description spending from `anchor`.
+    pub fn with_anchor(&self, anchor: [u8; 32]) -> SaplingSpendDescription {
+        SaplingSpendDescription {
+            value_commitment: self.value_commitment,
+            anchor,
+            nullifier: self.nullifier,
+            randomized_key: self.randomized_key,
+            zkproof: self.zkproof,
+            spend_auth_sig: self.spend_auth_sig,
+        }
+    }
+}
+
+impl Default for SaplingBundleV5 {
+    fn default() -> Self {
+        SaplingBundleV5 {
+            balancing_value: Default::default(),
+            anchor: Default::default(),
+            spends: Default::default(),
+            outputs: Default::default(),
+            binding_sig: [0; 64],
+        }
+    }
+}
+
+impl PartialEq<SaplingBundleV5> for SaplingBundleV5 {
+    fn eq(&self, other: &SaplingBundleV5) -> bool {
+        self.balancing_value == other.balancing_value
+            && self.anchor == other.anchor
+            && self.spends == other.spends
+            && self.outputs == other.outputs
+            && self.binding_sig.as_ref() == other.binding_sig.as_ref()
+    }
+}
+
+impl Eq for SaplingBundleV5 {}
+
+impl Hash for SaplingBundleV5 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.balancing_value.hash(state);
+        self.anchor.hash(state);
+        self.spends.hash(state);
+        self.outputs.hash(state);
+        self.binding_sig[..].hash(state);
+    }
+}
+
+impl fmt::Debug for SaplingBundleV5 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SaplingBundleV5")
+            .field("balancing_value", &self.balancing_value)
+            .field("anchor", &self.anchor)
+            .field("spends", &self.spends)
+            .field("outputs", &self.outputs)
+            .field("binding_sig", &&self.binding_sig[..])
+            .finish()
+    }
+}
+
+impl Default for SaplingSpendDescriptionV5 {
+    fn default() -> Self {
+        SaplingSpendDescriptionV5 {
+            value_commitment: Default::default(),
+            nullifier: Default::default(),
+            randomized_key: Default::default(),
+            zkproof: [0; 192],
+            spend_auth_sig: [0; 64],
+        }
+    }
+}
+
+impl PartialEq<SaplingSpendDescriptionV5> for SaplingSpendDescriptionV5 {
+    fn eq(&self, other: &SaplingSpendDescriptionV5) -> bool {
+        self.value_commitment == other.value_commitment
+            && self.nullifier == other.nullifier
+            && self.randomized_key == other.randomized_key
+            && self.zkproof.as_ref() == other.zkproof.as_ref()
+            && self.spend_auth_sig.as_ref() == other.spend_auth_sig.as_ref()
+    }
+}
+
+impl Eq for SaplingSpendDescriptionV5 {}
+
+impl Hash for SaplingSpendDescriptionV5 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value_commitment.hash(state);
+        self.nullifier.hash(state);
+        self.randomized_key.hash(state);
+        self.zkproof[..].hash(state);
+        self.spend_auth_sig[..].hash(state);
+    }
+}
+
+impl fmt::Debug for SaplingSpendDescriptionV5 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SaplingSpendDescriptionV5")
+            .field("value_commitment", &self.value_commitment)
+            .field("nullifier", &self.nullifier)
+            .field("randomized_key", &self.randomized_key)
+            .field("zkproof", &&self.zkproof[..])
+            .field("spend_auth_sig", &&self.spend_auth_sig[..])
+            .finish()
+    }
+}
+
+/// Sapling fields of a transaction, borrowed from the encoded transaction.
+///
+/// Read with `SaplingRef::read` and verified with `accept_sapling_ref` without copying the
+/// bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaplingRef<'a> {
+    pub balancing_value: i64,
+    /// Encoded spend descriptions, `SaplingSpendDescription::SIZE` bytes each.
+    pub(crate) spends: &'a [u8],
+    /// Encoded output descriptions, `SaplingOutputDescription::SIZE` bytes each.
+    pub(crate) outputs: &'a [u8],
+    /// Empty if there are no descriptions.
+    pub(crate) binding_sig: &'a [u8],
+}
+
+/// Spend description borrowed from an encoded transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendDescriptionRef<'a> {
+    data: &'a [u8],
+}
+
+/// Output description borrowed from an encoded transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputDescriptionRef<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SaplingRef<'a> {
+    pub fn spends(&self) -> impl Iterator<Item = SpendDescriptionRef<'a>> + 'a {
+        let spends = self.spends.chunks_exact(SaplingSpendDescription::SIZE);
+        spends.map(|data| SpendDescriptionRef { data })
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = OutputDescriptionRef<'a>> + 'a {
+        let outputs = self.outputs.chunks_exact(SaplingOutputDescription::SIZE);
+        outputs.map(|data| OutputDescriptionRef { data })
+    }
+
+    /// The binding signature, all zeros if there are no descriptions.
+    pub fn binding_sig(&self) -> [u8; 64] {
+        let mut result = [0u8; 64];
+        if !self.binding_sig.is_empty() {
+            result.copy_from_slice(self.binding_sig);
+        }
+        result
+    }
+
+    /// Copies the bundle.
+    pub fn to_sapling(&self) -> Sapling {
+        Sapling {
+            balancing_value: self.balancing_value,
+            spends: self.spends().map(|spend| spend.to_description()).collect(),
+            outputs: self
+                .outputs()
+                .map(|output| output.to_description())
+                .collect(),
+            binding_sig: self.binding_sig(),
+        }
+    }
+}
+
+impl<'a> SpendDescriptionRef<'a> {
+    pub fn value_commitment(&self) -> &'a [u8; 32] {
+        array_32(&self.data[..32])
+    }
+
+    pub fn anchor(&self) -> &'a [u8; 32] {
+        array_32(&self.data[32..64])
+    }
+
+    pub fn nullifier(&self) -> &'a [u8; 32] {
+        array_32(&self.data[64..96])
+    }
+
+    pub fn randomized_key(&self) -> &'a [u8; 32] {
+        array_32(&self.data[96..128])
+    }
+
+    /// The 192 byte proof.
+    pub fn zkproof(&self) -> &'a [u8] {
+        &self.data[128..320]
+    }
+
+    /// The 64 byte spend authorization signature.
+    pub fn spend_auth_sig(&self) -> &'a [u8] {
+        &self.data[320..]
+    }
+
+    /// Copies the description, e.g. onto the stack for verification.
+    pub fn to_description(&self) -> SaplingSpendDescription {
+        let mut result = SaplingSpendDescription::default();
+        SliceReader::new(self.data).read_spend(&mut result);
+        result
+    }
+}
+
+impl<'a> OutputDescriptionRef<'a> {
+    pub fn value_commitment(&self) -> &'a [u8; 32] {
+        array_32(&self.data[..32])
+    }
+
+    pub fn note_commitment(&self) -> &'a [u8; 32] {
+        array_32(&self.data[32..64])
+    }
+
+    pub fn ephemeral_key(&self) -> &'a [u8; 32] {
+        array_32(&self.data[64..96])
+    }
+
+    /// The 580 byte note ciphertext.
+    pub fn enc_cipher_text(&self) -> &'a [u8] {
+        &self.data[96..676]
+    }
+
+    /// The 80 byte outgoing ciphertext.
+    pub fn out_cipher_text(&self) -> &'a [u8] {
+        &self.data[676..756]
+    }
+
+    /// The 192 byte proof.
+    pub fn zkproof(&self) -> &'a [u8] {
+        &self.data[756..]
+    }
+
+    /// Copies the description, e.g. onto the stack for verification.
+    pub fn to_description(&self) -> SaplingOutputDescription {
+        let mut result = SaplingOutputDescription::default();
+        SliceReader::new(self.data).read_output(&mut result);
+        result
+    }
+}
+
+fn array_32(data: &[u8]) -> &[u8; 32] {
+    data.try_into().expect("field is 32 bytes")
+}
+
+/// Copies consecutive fields out of a slice whose length was already checked.
+struct SliceReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        SliceReader { data, offset: 0 }
+    }
+
+    fn read(&mut self, out: &mut [u8]) {
+        out.copy_from_slice(&self.data[self.offset..self.offset + out.len()]);
+        self.offset += out.len();
+    }
+
+    fn read_spend(&mut self, result: &mut SaplingSpendDescription) {
+        self.read(&mut result.value_commitment);
+        self.read(&mut result.anchor);
+        self.read(&mut result.nullifier);
+        self.read(&mut result.randomized_key);
+        self.read(&mut result.zkproof);
+        self.read(&mut result.spend_auth_sig);
+    }
+
+    fn read_output(&mut self, result: &mut SaplingOutputDescription) {
+        self.read(&mut result.value_commitment);
+        self.read(&mut result.note_commitment);
+        self.read(&mut result.ephemeral_key);
+        self.read(&mut result.enc_cipher_text);
+        self.read(&mut result.out_cipher_text);
+        self.read(&mut result.zkproof);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        compact_size_len, write_compact_size, DifferentAnchors, Sapling, SaplingBundleV5,
+        SaplingOutputDescription, SaplingSpendDescription,
+    };
+    use alloc::{vec, vec::Vec};
+    use core::{
+        convert::TryFrom,
+        hash::{Hash, Hasher},
+    };
+
+    struct Recorder(Vec<u8>);
+
+    impl Hasher for Recorder {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+
+    fn hash_bytes<T: Hash>(value: &T) -> Vec<u8> {
+        let mut recorder = Recorder(Vec::new());
+        value.hash(&mut recorder);
+        recorder.0
+    }
+
+    #[test]
+    fn test_eq_and_hash() {
+        let sapling = Sapling {
+            balancing_value: 10,
+            spends: vec![SaplingSpendDescription::default()],
+            outputs: vec![SaplingOutputDescription::default()],
+            binding_sig: [1; 64],
+        };
+
+        let copy = sapling.clone();
+        assert_eq!(sapling, copy);
+        assert_eq!(hash_bytes(&sapling), hash_bytes(&copy));
+
+        let mut other = sapling.clone();
+        other.outputs[0].enc_cipher_text[579] = 1;
+        assert_ne!(sapling, other);
+        assert_ne!(hash_bytes(&sapling), hash_bytes(&other));
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+
+        let (spend, len) = SaplingSpendDescription::from_slice(&data).unwrap();
+        assert_eq!(len, 384);
+        assert_eq!(spend.value_commitment[0], 0);
+        assert_eq!(spend.anchor[0], 32);
+        assert_eq!(spend.zkproof[0], 128);
+        assert_eq!(spend.spend_auth_sig[63], 127);
+
+        let (output, len) = SaplingOutputDescription::from_slice(&data).unwrap();
+        assert_eq!(len, 948);
+        assert_eq!(output.enc_cipher_text[0], 96);
+        assert_eq!(output.zkproof[191], 179);
+
+        assert!(SaplingSpendDescription::from_slice(&data[..383]).is_err());
+        assert!(SaplingOutputDescription::from_slice(&data[..947]).is_err());
+    }
+
+    #[test]
+    fn test_serialized_size() {
+        let mut sapling = Sapling::default();
+        assert_eq!(sapling.serialized_size(), 8 + 1 + 1);
+        assert_eq!(sapling.tx_weight_contribution(), 0);
+
+        sapling.spends = vec![SaplingSpendDescription::default(); 2];
+        sapling.outputs = vec![SaplingOutputDescription::default(); 3];
+        assert_eq!(
+            sapling.serialized_size(),
+            8 + 1 + 2 * 384 + 1 + 3 * 948 + 64
+        );
+        assert_eq!(sapling.tx_weight_contribution(), 3);
+
+        assert_eq!(compact_size_len(0xfc), 1);
+        assert_eq!(compact_size_len(0xfd), 3);
+        assert_eq!(compact_size_len(0x1_0000), 5);
+    }
+
+    #[test]
+    fn test_write() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let (spend, _) = SaplingSpendDescription::from_slice(&data).unwrap();
+        let (output, _) = SaplingOutputDescription::from_slice(&data).unwrap();
+
+        let mut encoded = Vec::new();
+        spend.write(&mut encoded);
+        assert_eq!(encoded[..], data[..SaplingSpendDescription::SIZE]);
+
+        let sapling = Sapling {
+            balancing_value: -1,
+            spends: vec![spend],
+            outputs: vec![output.clone(), output],
+            binding_sig: [3; 64],
+        };
+        let bytes = sapling.to_bytes();
+        assert_eq!(bytes.len(), sapling.serialized_size());
+        assert_eq!(
+            bytes[..9],
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 1][..]
+        );
+        assert_eq!(bytes[9 + 384], 2);
+        assert_eq!(bytes[bytes.len() - 64..], [3; 64][..]);
+
+        let mut compact = Vec::new();
+        write_compact_size(&mut compact, 0xfd);
+        write_compact_size(&mut compact, 0x1_0000);
+        assert_eq!(compact, [0xfd, 0xfd, 0x00, 0xfe, 0x00, 0x00, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_v5_conversion() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let (spend, _) = SaplingSpendDescription::from_slice(&data).unwrap();
+        let (output, _) = SaplingOutputDescription::from_slice(&data).unwrap();
+        let sapling = Sapling {
+            balancing_value: 7,
+            spends: vec![spend.clone(), spend],
+            outputs: vec![output],
+            binding_sig: [3; 64],
+        };
+
+        // v4 -> v5 -> v4
+        let bundle = SaplingBundleV5::try_from(&sapling).unwrap();
+        assert_eq!(bundle.anchor, sapling.spends[0].anchor);
+        assert_eq!(bundle.spends.len(), 2);
+        assert_eq!(bundle.to_sapling(), sapling);
+
+        // v5 -> v4 -> v5
+        let mut bundle = bundle;
+        bundle.anchor = [5; 32];
+        assert_eq!(SaplingBundleV5::try_from(&bundle.to_sapling()), Ok(bundle));
+
+        let mut different = sapling.clone();
+        different.spends[1].anchor[0] ^= 1;
+        assert_eq!(SaplingBundleV5::try_from(&different), Err(DifferentAnchors));
+
+        // outputs only
+        let outputs_only = Sapling {
+            spends: vec![],
+            ..sapling
+        };
+        let bundle = SaplingBundleV5::try_from(&outputs_only).unwrap();
+        assert_eq!(bundle.anchor, [0; 32]);
+        assert_eq!(bundle.to_sapling(), outputs_only);
+    }
+}
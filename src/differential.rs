@@ -0,0 +1,195 @@
+//! Differential checks against bellman, the Groth16 verifier used by zcashd and
+//! librustzcash.
+//!
+//! Every case is run through both implementations, which must make the same accept or reject
+//! decision. The corpus is built from mainnet descriptions and mutations of them.
+
+use crate::{
+    affine::{write_g1affine, write_g2affine},
+    proof::verify_sapling_proof,
+    Groth16PreparedVerifyingKey, Groth16VerifyingKey,
+};
+use algebra::{
+    bls12_381::{self, Fr},
+    PrimeField,
+};
+use alloc::vec::Vec;
+use bellman::groth16::{self, PreparedVerifyingKey};
+use zkcrypto_bls12_381::{Bls12, G1Affine, G2Affine, Scalar};
+
+/// Converts a verifying key of this crate to a prepared bellman key.
+///
+/// bellman does not use `beta_g1` and `delta_g1` to verify, they are left at the identity.
+fn bellman_vk(vk: &Groth16VerifyingKey) -> PreparedVerifyingKey<Bls12> {
+    groth16::prepare_verifying_key(&groth16::VerifyingKey {
+        alpha_g1: bellman_g1(&vk.alpha_g1),
+        beta_g1: G1Affine::identity(),
+        beta_g2: bellman_g2(&vk.beta_g2),
+        gamma_g2: bellman_g2(&vk.gamma_g2),
+        delta_g1: G1Affine::identity(),
+        delta_g2: bellman_g2(&vk.delta_g2),
+        ic: vk.gamma_abc_g1.iter().map(bellman_g1).collect(),
+    })
+}
+
+fn bellman_g1(point: &bls12_381::G1Affine) -> G1Affine {
+    G1Affine::from_uncompressed(&write_g1affine(point)).unwrap()
+}
+
+fn bellman_g2(point: &bls12_381::G2Affine) -> G2Affine {
+    G2Affine::from_uncompressed(&write_g2affine(point)).unwrap()
+}
+
+fn bellman_scalar(element: &Fr) -> Scalar {
+    let mut bytes = [0u8; 32];
+    for (limb, chunk) in element.into_repr().0.iter().zip(bytes.chunks_mut(8)) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    Scalar::from_bytes(&bytes).unwrap()
+}
+
+/// Does bellman accept the encoded proof?
+fn bellman_accepts(pvk: &PreparedVerifyingKey<Bls12>, proof: &[u8; 192], inputs: &[Fr]) -> bool {
+    let proof = match groth16::Proof::<Bls12>::read(&proof[..]) {
+        Ok(proof) => proof,
+        Err(_) => return false,
+    };
+    let inputs: Vec<Scalar> = inputs.iter().map(bellman_scalar).collect();
+    groth16::verify_proof(pvk, &proof, &inputs).is_ok()
+}
+
+/// Runs the proof through both verifiers, failing on any disagreement. Returns the decision.
+fn assert_same_decision(
+    pvk: &Groth16PreparedVerifyingKey,
+    bellman_pvk: &PreparedVerifyingKey<Bls12>,
+    proof: &[u8; 192],
+    inputs: &[Fr],
+) -> bool {
+    let accepted = verify_sapling_proof(pvk, proof, inputs) == Ok(true);
+    assert_eq!(
+        accepted,
+        bellman_accepts(bellman_pvk, proof, inputs),
+        "decisions differ for proof {:?}",
+        &proof[..]
+    );
+    accepted
+}
+
+/// Mutations of a valid proof: single bit flips in every byte, including the flag bits of
+/// every point, swapped `a` and `c`, and each point replaced by the identity.
+fn proof_mutations(proof: &[u8; 192]) -> Vec<[u8; 192]> {
+    let mut result = Vec::new();
+    for index in 0..proof.len() {
+        for bit in &[0x01, 0x20, 0x40, 0x80] {
+            let mut mutated = *proof;
+            mutated[index] ^= bit;
+            result.push(mutated);
+        }
+    }
+
+    let mut swapped = *proof;
+    swapped[..48].copy_from_slice(&proof[144..]);
+    swapped[144..].copy_from_slice(&proof[..48]);
+    result.push(swapped);
+
+    for (start, len) in &[(0, 48), (48, 96), (144, 48)] {
+        let mut identity = *proof;
+        identity[*start..*start + *len].copy_from_slice(&[0u8; 96][..*len]);
+        identity[*start] = 0xc0;
+        result.push(identity);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_same_decision, bellman_scalar, bellman_vk, proof_mutations};
+    use crate::{
+        multipack::pack_bytes, output_public_inputs, spend_public_inputs, tests::mainnet_sapling,
+        zcash, Groth16PreparedVerifyingKey, VerificationPolicy,
+    };
+    use algebra::bls12_381::Fr;
+    use alloc::{vec, vec::Vec};
+    use bellman::gadgets::multipack;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+    use zkcrypto_bls12_381::Scalar;
+
+    #[test]
+    fn test_spend_proofs() {
+        let vk = zcash::spend_vk();
+        let bellman_pvk = bellman_vk(&vk);
+        let pvk: Groth16PreparedVerifyingKey = vk.into();
+        let (sapling, _) = mainnet_sapling();
+        let spend = &sapling.spends[0];
+        let inputs = spend_public_inputs(&VerificationPolicy::strict(), spend).unwrap();
+
+        assert!(assert_same_decision(
+            &pvk,
+            &bellman_pvk,
+            &spend.zkproof,
+            &inputs
+        ));
+        for proof in proof_mutations(&spend.zkproof) {
+            assert_same_decision(&pvk, &bellman_pvk, &proof, &inputs);
+        }
+
+        for index in 0..inputs.len() {
+            let mut mutated = inputs;
+            mutated[index] += &Fr::from(1u64);
+            assert!(!assert_same_decision(
+                &pvk,
+                &bellman_pvk,
+                &spend.zkproof,
+                &mutated
+            ));
+        }
+    }
+
+    #[test]
+    fn test_output_proofs() {
+        let vk = zcash::output_vk();
+        let bellman_pvk = bellman_vk(&vk);
+        let pvk: Groth16PreparedVerifyingKey = vk.into();
+        let (sapling, _) = mainnet_sapling();
+        let output = &sapling.outputs[0];
+        let inputs = output_public_inputs(&VerificationPolicy::strict(), output).unwrap();
+
+        assert!(assert_same_decision(
+            &pvk,
+            &bellman_pvk,
+            &output.zkproof,
+            &inputs
+        ));
+        for proof in proof_mutations(&output.zkproof) {
+            assert_same_decision(&pvk, &bellman_pvk, &proof, &inputs);
+        }
+
+        // proofs of one circuit never verify against the other
+        let spend = &sapling.spends[0];
+        assert!(!assert_same_decision(
+            &pvk,
+            &bellman_pvk,
+            &spend.zkproof,
+            &inputs
+        ));
+    }
+
+    #[test]
+    fn test_multipacking() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        for len in 0..100 {
+            let mut bytes = vec![0u8; len];
+            rng.fill_bytes(&mut bytes);
+
+            let bits = multipack::bytes_to_bits_le(&bytes);
+            let expected: Vec<Scalar> = multipack::compute_multipacking(&bits);
+            let packed: Vec<_> = pack_bytes::<Fr>(&bytes)
+                .iter()
+                .map(bellman_scalar)
+                .collect();
+            assert_eq!(packed, expected);
+        }
+    }
+}
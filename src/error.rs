@@ -0,0 +1,74 @@
+use core::fmt;
+
+/// Reason for rejecting a Sapling bundle or one of its descriptions.
+///
+/// `accept_sapling_located` and `accept_sapling_collect_all` additionally report which
+/// description failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The value commitment of a spend is not an allowed point encoding or is of small order.
+    InvalidSpendValueCommitment,
+    /// The anchor of a spend is not an allowed encoding of a field element.
+    InvalidAnchor,
+    /// The randomized key of a spend is not an allowed point encoding or is of small order.
+    InvalidRandomizedKey,
+    /// A spend authorization signature is not an allowed encoding.
+    MalformedSpendAuthSig,
+    /// A spend authorization signature does not verify.
+    SpendAuthSigInvalid,
+    /// The value commitment of an output is not an allowed point encoding or is of small order.
+    InvalidOutputValueCommitment,
+    /// The note commitment of an output is not an allowed encoding of a field element.
+    InvalidNoteCommitment,
+    /// The ephemeral key of an output is not an allowed point encoding or is of small order.
+    InvalidEphemeralKey,
+    /// The proof of a spend failed to decode.
+    MalformedSpendProof,
+    /// The proof of an output failed to decode.
+    MalformedOutputProof,
+    /// The number of public inputs does not match the verifying key, which belongs to another
+    /// circuit.
+    InvalidPublicInputs,
+    /// The proof of the spend at `index` in the bundle does not verify.
+    SpendProofInvalid { index: usize },
+    /// The proof of the output at `index` in the bundle does not verify.
+    OutputProofInvalid { index: usize },
+    /// At least one proof of a combined check does not verify.
+    CombinedProofsInvalid,
+    /// At least one signature of a combined check does not verify.
+    CombinedSignaturesInvalid,
+    /// The balancing value is out of range.
+    InvalidBalancingValue,
+    /// The binding signature is not an allowed encoding.
+    MalformedBindingSig,
+    /// The binding signature does not verify.
+    BindingSigInvalid,
+    /// `VerificationJobs::finish` did not receive exactly one result per job.
+    InvalidJobResults,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidSpendValueCommitment => write!(f, "invalid spend value commitment"),
+            Error::InvalidAnchor => write!(f, "invalid anchor"),
+            Error::InvalidRandomizedKey => write!(f, "invalid randomized key"),
+            Error::MalformedSpendAuthSig => write!(f, "malformed spend authorization signature"),
+            Error::SpendAuthSigInvalid => write!(f, "invalid spend authorization signature"),
+            Error::InvalidOutputValueCommitment => write!(f, "invalid output value commitment"),
+            Error::InvalidNoteCommitment => write!(f, "invalid note commitment"),
+            Error::InvalidEphemeralKey => write!(f, "invalid ephemeral key"),
+            Error::MalformedSpendProof => write!(f, "malformed spend proof"),
+            Error::MalformedOutputProof => write!(f, "malformed output proof"),
+            Error::InvalidPublicInputs => write!(f, "public inputs do not match verifying key"),
+            Error::SpendProofInvalid { index } => write!(f, "invalid proof of spend {}", index),
+            Error::OutputProofInvalid { index } => write!(f, "invalid proof of output {}", index),
+            Error::CombinedProofsInvalid => write!(f, "invalid proofs"),
+            Error::CombinedSignaturesInvalid => write!(f, "invalid signatures"),
+            Error::InvalidBalancingValue => write!(f, "invalid balancing value"),
+            Error::MalformedBindingSig => write!(f, "malformed binding signature"),
+            Error::BindingSigInvalid => write!(f, "invalid binding signature"),
+            Error::InvalidJobResults => write!(f, "job results do not match jobs"),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+use crate::Failure;
+#[cfg(feature = "verify-proofs")]
+use crate::{
+    affine::AffineDecodeError,
+    proof::{ProofDecodeError, VerifyProofError},
+};
+use crate::{BatchError, Error};
+
+/// Numeric rejection reason that keeps its meaning across versions of this crate.
+///
+/// Codes are grouped in ranges:
+///
+/// * `1..=99`: a consensus check of a bundle failed
+/// * `100..=199`: a point or proof failed to decode
+/// * `200..=299`: the verification did not reach a decision
+/// * `300..=999`: reserved for future groups
+/// * `1000..=u16::MAX`: never assigned, free for application specific codes
+///
+/// `0` is never an error. A code is never reassigned once released; new reasons get new codes
+/// in the range of their group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ErrorCode(u16);
+
+impl ErrorCode {
+    /// The bundle is invalid for an unspecified reason.
+    pub const INVALID: ErrorCode = ErrorCode(1);
+    pub const SPEND_VALUE_COMMITMENT: ErrorCode = ErrorCode(2);
+    pub const SPEND_ANCHOR: ErrorCode = ErrorCode(3);
+    pub const SPEND_RANDOMIZED_KEY: ErrorCode = ErrorCode(4);
+    pub const SPEND_AUTH_SIG_ENCODING: ErrorCode = ErrorCode(5);
+    pub const SPEND_AUTH_SIG: ErrorCode = ErrorCode(6);
+    pub const SPEND_PROOF_ENCODING: ErrorCode = ErrorCode(7);
+    pub const SPEND_PROOF: ErrorCode = ErrorCode(8);
+    pub const OUTPUT_VALUE_COMMITMENT: ErrorCode = ErrorCode(9);
+    pub const OUTPUT_NOTE_COMMITMENT: ErrorCode = ErrorCode(10);
+    pub const OUTPUT_EPHEMERAL_KEY: ErrorCode = ErrorCode(11);
+    pub const OUTPUT_PROOF_ENCODING: ErrorCode = ErrorCode(12);
+    pub const OUTPUT_PROOF: ErrorCode = ErrorCode(13);
+    pub const BALANCING_VALUE: ErrorCode = ErrorCode(14);
+    pub const BINDING_SIG_ENCODING: ErrorCode = ErrorCode(15);
+    pub const BINDING_SIG: ErrorCode = ErrorCode(16);
+    /// A proof of a spend or output does not verify.
+    pub const PROOF: ErrorCode = ErrorCode(19);
+    /// A spend authorization or binding signature does not verify.
+    pub const SIGNATURE: ErrorCode = ErrorCode(20);
+
+    pub const POINT_LENGTH: ErrorCode = ErrorCode(100);
+    pub const POINT_FLAGS: ErrorCode = ErrorCode(101);
+    pub const POINT_NOT_ON_CURVE: ErrorCode = ErrorCode(102);
+    pub const POINT_WRONG_SUBGROUP: ErrorCode = ErrorCode(103);
+    pub const POINT_NON_CANONICAL_FIELD: ErrorCode = ErrorCode(104);
+    pub const POINT_UNEXPECTED_INFINITY: ErrorCode = ErrorCode(105);
+    pub const PROOF_LENGTH: ErrorCode = ErrorCode(106);
+
+    pub const CANCELLED: ErrorCode = ErrorCode(200);
+    /// The number of public inputs does not match the verifying key.
+    pub const PUBLIC_INPUT_COUNT: ErrorCode = ErrorCode(201);
+    /// The number of verification job results does not match the jobs.
+    pub const JOB_RESULT_COUNT: ErrorCode = ErrorCode(202);
+
+    /// Returns `None` for `0`, which is not an error.
+    pub fn new(value: u16) -> Option<Self> {
+        if value == 0 {
+            return None;
+        }
+
+        Some(ErrorCode(value))
+    }
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the code of the check recorded under `label` in a `Transcript`.
+    pub fn from_check(label: &str) -> Option<Self> {
+        Some(match label {
+            "spend.value_commitment.valid" => Self::SPEND_VALUE_COMMITMENT,
+            "spend.anchor.valid" => Self::SPEND_ANCHOR,
+            "spend.randomized_key.valid" => Self::SPEND_RANDOMIZED_KEY,
+            "spend.spend_auth_sig.valid" => Self::SPEND_AUTH_SIG_ENCODING,
+            "spend.spend_auth_sig.verified" => Self::SPEND_AUTH_SIG,
+            "spend.zkproof.valid" => Self::SPEND_PROOF_ENCODING,
+            "spend.zkproof.verified" => Self::SPEND_PROOF,
+            "output.value_commitment.valid" => Self::OUTPUT_VALUE_COMMITMENT,
+            "output.note_commitment.valid" => Self::OUTPUT_NOTE_COMMITMENT,
+            "output.ephemeral_key.valid" => Self::OUTPUT_EPHEMERAL_KEY,
+            "output.zkproof.valid" => Self::OUTPUT_PROOF_ENCODING,
+            "output.zkproof.verified" => Self::OUTPUT_PROOF,
+            "balancing_value.valid" => Self::BALANCING_VALUE,
+            "binding_sig.valid" => Self::BINDING_SIG_ENCODING,
+            "binding_sig.verified" => Self::BINDING_SIG,
+            _ => return None,
+        })
+    }
+}
+
+impl From<Error> for ErrorCode {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::InvalidSpendValueCommitment => ErrorCode::SPEND_VALUE_COMMITMENT,
+            Error::InvalidAnchor => ErrorCode::SPEND_ANCHOR,
+            Error::InvalidRandomizedKey => ErrorCode::SPEND_RANDOMIZED_KEY,
+            Error::MalformedSpendAuthSig => ErrorCode::SPEND_AUTH_SIG_ENCODING,
+            Error::SpendAuthSigInvalid => ErrorCode::SPEND_AUTH_SIG,
+            Error::InvalidOutputValueCommitment => ErrorCode::OUTPUT_VALUE_COMMITMENT,
+            Error::InvalidNoteCommitment => ErrorCode::OUTPUT_NOTE_COMMITMENT,
+            Error::InvalidEphemeralKey => ErrorCode::OUTPUT_EPHEMERAL_KEY,
+            Error::MalformedSpendProof => ErrorCode::SPEND_PROOF_ENCODING,
+            Error::MalformedOutputProof => ErrorCode::OUTPUT_PROOF_ENCODING,
+            Error::InvalidPublicInputs => ErrorCode::PUBLIC_INPUT_COUNT,
+            Error::SpendProofInvalid { .. } => ErrorCode::SPEND_PROOF,
+            Error::OutputProofInvalid { .. } => ErrorCode::OUTPUT_PROOF,
+            Error::CombinedProofsInvalid => ErrorCode::PROOF,
+            Error::CombinedSignaturesInvalid => ErrorCode::SIGNATURE,
+            Error::InvalidBalancingValue => ErrorCode::BALANCING_VALUE,
+            Error::MalformedBindingSig => ErrorCode::BINDING_SIG_ENCODING,
+            Error::BindingSigInvalid => ErrorCode::BINDING_SIG,
+            Error::InvalidJobResults => ErrorCode::JOB_RESULT_COUNT,
+        }
+    }
+}
+
+impl From<BatchError> for ErrorCode {
+    fn from(err: BatchError) -> Self {
+        match err {
+            BatchError::Invalid(err) => err.into(),
+            BatchError::Cancelled => ErrorCode::CANCELLED,
+        }
+    }
+}
+
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+impl From<Failure> for ErrorCode {
+    fn from(failure: Failure) -> Self {
+        ErrorCode::from_check(failure.check).unwrap_or(ErrorCode::INVALID)
+    }
+}
+
+#[cfg(feature = "verify-proofs")]
+impl From<AffineDecodeError> for ErrorCode {
+    fn from(err: AffineDecodeError) -> Self {
+        match err {
+            AffineDecodeError::InvalidLength => ErrorCode::POINT_LENGTH,
+            AffineDecodeError::InvalidFlags => ErrorCode::POINT_FLAGS,
+            AffineDecodeError::NotOnCurve => ErrorCode::POINT_NOT_ON_CURVE,
+            AffineDecodeError::WrongSubgroup => ErrorCode::POINT_WRONG_SUBGROUP,
+            AffineDecodeError::NonCanonicalField => ErrorCode::POINT_NON_CANONICAL_FIELD,
+            AffineDecodeError::UnexpectedInfinity => ErrorCode::POINT_UNEXPECTED_INFINITY,
+        }
+    }
+}
+
+#[cfg(feature = "verify-proofs")]
+impl From<ProofDecodeError> for ErrorCode {
+    fn from(err: ProofDecodeError) -> Self {
+        match err {
+            ProofDecodeError::InvalidLength => ErrorCode::PROOF_LENGTH,
+            ProofDecodeError::A(err) | ProofDecodeError::B(err) | ProofDecodeError::C(err) => {
+                err.into()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "verify-proofs")]
+impl From<VerifyProofError> for ErrorCode {
+    fn from(err: VerifyProofError) -> Self {
+        match err {
+            VerifyProofError::Decode(err) => err.into(),
+            VerifyProofError::InvalidInputs => ErrorCode::PUBLIC_INPUT_COUNT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorCode;
+    use crate::{BatchError, Error};
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        assert_eq!(ErrorCode::new(0), None);
+        assert_eq!(ErrorCode::new(16), Some(ErrorCode::BINDING_SIG));
+        let err = BatchError::Invalid(Error::BindingSigInvalid);
+        assert_eq!(ErrorCode::from(err), ErrorCode::BINDING_SIG);
+        assert_eq!(ErrorCode::from(BatchError::Cancelled).value(), 200);
+        assert_eq!(
+            ErrorCode::from_check("spend.zkproof.verified").map(|c| c.value()),
+            Some(8)
+        );
+        assert_eq!(
+            ErrorCode::from_check("binding_sig.verified").map(|c| c.value()),
+            Some(16)
+        );
+        assert_eq!(ErrorCode::from_check("spend.anchor"), None);
+    }
+
+    #[test]
+    fn test_errors_match_checks() {
+        let cases = [
+            (
+                Error::InvalidSpendValueCommitment,
+                "spend.value_commitment.valid",
+            ),
+            (Error::MalformedSpendProof, "spend.zkproof.valid"),
+            (
+                Error::InvalidOutputValueCommitment,
+                "output.value_commitment.valid",
+            ),
+            (Error::MalformedOutputProof, "output.zkproof.valid"),
+        ];
+        for (err, check) in cases.iter() {
+            assert_eq!(Some(ErrorCode::from(*err)), ErrorCode::from_check(check));
+        }
+    }
+
+    #[cfg(feature = "verify-proofs")]
+    #[test]
+    fn test_decode_error_codes() {
+        use crate::{affine::AffineDecodeError, proof::ProofDecodeError};
+
+        let err = ProofDecodeError::B(AffineDecodeError::WrongSubgroup);
+        assert_eq!(ErrorCode::from(err).value(), 103);
+        assert_eq!(
+            ErrorCode::from(ProofDecodeError::InvalidLength).value(),
+            106
+        );
+    }
+
+    #[cfg(feature = "mutation")]
+    #[test]
+    fn test_every_check_has_a_code() {
+        use crate::mutation::MutationKind;
+
+        for kind in MutationKind::ALL.iter() {
+            assert!(ErrorCode::from_check(kind.failing_check()).is_some());
+        }
+    }
+}
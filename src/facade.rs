@@ -0,0 +1,172 @@
+//! Entry points whose signatures only use types owned by this crate.
+//!
+//! The rest of the API exposes zexe types such as `PreparedVerifyingKey<Bls12_381>` and
+//! `Point<JubJubParameters>`. Code written against this module keeps compiling when the
+//! curve backend is replaced.
+
+use crate::{
+    affine::AffineDecodeError, is_canonical_point_encoding, read_canonical_fr, zcash, CvSum, Error,
+    Groth16PreparedVerifyingKey, Point, Sapling, VerificationPolicy,
+};
+use zexe_redjubjub::{read_point, write_point};
+
+/// Prepared Groth16 verifying key of a Sapling circuit.
+pub struct VerifyingKey {
+    prepared: Groth16PreparedVerifyingKey,
+}
+
+impl VerifyingKey {
+    /// Reads a key in the layout of `zcash::read_vk`.
+    pub fn read(data: &[u8]) -> Result<Self, AffineDecodeError> {
+        Ok(VerifyingKey {
+            prepared: zcash::read_vk(data)?.into(),
+        })
+    }
+
+    /// Reads a key in the layout of `zcash::read_compressed_vk`.
+    pub fn read_compressed(data: &[u8]) -> Result<Self, AffineDecodeError> {
+        Ok(VerifyingKey {
+            prepared: zcash::read_compressed_vk(data)?.into(),
+        })
+    }
+
+    /// Returns the key of the Zcash Sapling spend circuit.
+    #[cfg(feature = "spend-vk")]
+    pub fn sapling_spend() -> Self {
+        VerifyingKey {
+            prepared: zcash::spend_vk().into(),
+        }
+    }
+
+    /// Returns the key of the Zcash Sapling output circuit.
+    #[cfg(feature = "output-vk")]
+    pub fn sapling_output() -> Self {
+        VerifyingKey {
+            prepared: zcash::output_vk().into(),
+        }
+    }
+}
+
+/// Point on the Jubjub curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JubjubPoint {
+    point: Point,
+}
+
+impl JubjubPoint {
+    /// Reads a point, accepting non-canonical encodings only if `policy` allows them.
+    pub fn from_bytes(policy: &VerificationPolicy, bytes: &[u8; 32]) -> Option<Self> {
+        if policy.canonical_point_encodings && !is_canonical_point_encoding(&bytes[..]) {
+            return None;
+        }
+
+        read_point(&bytes[..]).map(|point| JubjubPoint { point })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        write_point(&self.point, &mut result[..]).expect("point is 32 bytes");
+        result
+    }
+}
+
+impl From<CvSum> for JubjubPoint {
+    fn from(sum: CvSum) -> Self {
+        JubjubPoint { point: sum.point() }
+    }
+}
+
+/// Canonical little-endian encoding of an element of the BLS12-381 scalar field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldBytes {
+    bytes: [u8; 32],
+}
+
+impl FieldBytes {
+    /// Returns `None` if `bytes` encodes a value not below the field modulus.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        read_canonical_fr(bytes).ok()?;
+        Some(FieldBytes { bytes: *bytes })
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+}
+
+/// Same as `crate::accept_sapling` with crate-owned verifying keys.
+pub fn accept_sapling(
+    policy: &VerificationPolicy,
+    spend_vk: &VerifyingKey,
+    output_vk: &VerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+) -> Result<(), Error> {
+    crate::accept_sapling(
+        policy,
+        &spend_vk.prepared,
+        &output_vk.prepared,
+        sighash,
+        sapling,
+    )
+}
+
+/// Same as `crate::nullifier_to_field_elements`, returning the encoded elements.
+pub fn nullifier_to_field_bytes(nullifier: &[u8; 32]) -> [FieldBytes; 2] {
+    use algebra::PrimeField;
+
+    let elements = crate::nullifier_to_field_elements(nullifier);
+    let mut result = [FieldBytes { bytes: [0u8; 32] }; 2];
+    for (element, field_bytes) in elements.iter().zip(result.iter_mut()) {
+        let limbs = element.into_repr().0;
+        for (limb, chunk) in limbs.iter().zip(field_bytes.bytes.chunks_mut(8)) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+    }
+    result
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::{accept_sapling, nullifier_to_field_bytes, FieldBytes, JubjubPoint, VerifyingKey};
+    use crate::{tests::mainnet_sapling, zcash, VerificationPolicy};
+
+    #[test]
+    fn test_accept_sapling() {
+        let (sapling, sighash) = mainnet_sapling();
+        let spend_vk = VerifyingKey::sapling_spend();
+        let output_vk = VerifyingKey::read_compressed(&zcash::OUTPUT_VK_COMPRESSED_BYTES).unwrap();
+        let policy = VerificationPolicy::strict();
+
+        assert!(accept_sapling(&policy, &spend_vk, &output_vk, &sighash, &sapling).is_ok());
+        assert!(accept_sapling(&policy, &output_vk, &spend_vk, &sighash, &sapling).is_err());
+    }
+
+    #[test]
+    fn test_jubjub_point() {
+        let (sapling, _) = mainnet_sapling();
+        let cv = sapling.spends[0].value_commitment;
+        let point = JubjubPoint::from_bytes(&VerificationPolicy::strict(), &cv).unwrap();
+        assert_eq!(point.to_bytes(), cv);
+
+        let mut invalid = [0u8; 32];
+        invalid[0] = 2;
+        assert_eq!(
+            JubjubPoint::from_bytes(&VerificationPolicy::strict(), &invalid),
+            None
+        );
+    }
+
+    #[test]
+    fn test_field_bytes() {
+        assert_eq!(FieldBytes::from_bytes(&[0xff; 32]), None);
+
+        let mut nullifier = [0u8; 32];
+        nullifier[0] = 5;
+        nullifier[31] = 0xc0;
+        let [low, high] = nullifier_to_field_bytes(&nullifier);
+        assert_eq!(low.as_bytes()[0], 5);
+        assert_eq!(high.as_bytes()[0], 3);
+        assert_eq!(FieldBytes::from_bytes(low.as_bytes()), Some(low));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::Sapling;
+use alloc::{vec, vec::Vec};
+
+/// Maximal number of bit positions set per nullifier, one per 8 bytes of the nullifier.
+pub const MAX_FILTER_HASHES: usize = 4;
+
+/// Bloom filter over nullifiers for cheap double-spend prechecks.
+///
+/// `may_contain` has no false negatives, so a `false` answer is final. A `true` answer must be
+/// confirmed against the authoritative nullifier set.
+///
+/// Nullifiers are outputs of a PRF, so the bit positions are taken directly from their bytes
+/// instead of hashing them again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullifierFilter {
+    words: Vec<u64>,
+    hashes: usize,
+}
+
+impl NullifierFilter {
+    /// Creates an empty filter of `bits` bits, rounded up to a multiple of 64, setting
+    /// `hashes` bits per nullifier.
+    ///
+    /// Fails if `bits` is zero or `hashes` is not in `1..=MAX_FILTER_HASHES`.
+    pub fn new(bits: usize, hashes: usize) -> Result<Self, ()> {
+        if bits == 0 || hashes == 0 || hashes > MAX_FILTER_HASHES {
+            return Err(());
+        }
+
+        Ok(NullifierFilter {
+            words: vec![0; (bits + 63) / 64],
+            hashes,
+        })
+    }
+
+    /// Creates an empty filter sized for `count` nullifiers with a false positive rate of
+    /// about 0.25%.
+    pub fn with_capacity(count: usize) -> Self {
+        NullifierFilter::new(count.max(1) * 16, MAX_FILTER_HASHES).expect("parameters are valid")
+    }
+
+    pub fn insert(&mut self, nullifier: &[u8; 32]) {
+        for position in self.positions(nullifier) {
+            self.words[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    /// Inserts the nullifiers of every spend of `sapling`.
+    pub fn insert_bundle(&mut self, sapling: &Sapling) {
+        for spend in &sapling.spends {
+            self.insert(&spend.nullifier);
+        }
+    }
+
+    pub fn may_contain(&self, nullifier: &[u8; 32]) -> bool {
+        self.positions(nullifier)
+            .all(|position| self.words[position / 64] & (1 << (position % 64)) != 0)
+    }
+
+    /// Adds every nullifier of `other`, e.g. to combine the filters of several blocks.
+    ///
+    /// Fails if the filters differ in size or number of hashes.
+    pub fn merge(&mut self, other: &NullifierFilter) -> Result<(), ()> {
+        if self.words.len() != other.words.len() || self.hashes != other.hashes {
+            return Err(());
+        }
+
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= *other;
+        }
+        Ok(())
+    }
+
+    fn positions<'a>(&self, nullifier: &'a [u8; 32]) -> impl Iterator<Item = usize> + 'a {
+        let bits = self.words.len() as u64 * 64;
+        nullifier.chunks(8).take(self.hashes).map(move |chunk| {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            (u64::from_le_bytes(word) % bits) as usize
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NullifierFilter;
+
+    fn nullifier(i: u64) -> [u8; 32] {
+        // spread the bits like a PRF output would
+        let mut result = [0u8; 32];
+        for (j, chunk) in result.chunks_mut(8).enumerate() {
+            let word = (i + 1)
+                .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                .rotate_left(j as u32 * 16);
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        result
+    }
+
+    #[test]
+    fn test_nullifier_filter() {
+        let mut filter = NullifierFilter::with_capacity(100);
+        for i in 0..100 {
+            filter.insert(&nullifier(i));
+        }
+
+        assert!((0..100).all(|i| filter.may_contain(&nullifier(i))));
+        let false_positives = (100..10_100)
+            .filter(|&i| filter.may_contain(&nullifier(i)))
+            .count();
+        assert!(false_positives < 100);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut first = NullifierFilter::new(1024, 3).unwrap();
+        let mut second = NullifierFilter::new(1024, 3).unwrap();
+        first.insert(&nullifier(1));
+        second.insert(&nullifier(2));
+
+        first.merge(&second).unwrap();
+        assert!(first.may_contain(&nullifier(1)));
+        assert!(first.may_contain(&nullifier(2)));
+
+        assert!(first
+            .merge(&NullifierFilter::new(2048, 3).unwrap())
+            .is_err());
+        assert!(first
+            .merge(&NullifierFilter::new(1024, 2).unwrap())
+            .is_err());
+        assert!(NullifierFilter::new(1024, 5).is_err());
+    }
+}
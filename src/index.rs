@@ -0,0 +1,64 @@
+use crate::Sapling;
+
+/// Fields of a bundle that chain indexers store, borrowed from the bundle.
+///
+/// Obtained with `Sapling::extract_index_data`. Nothing is decoded or verified.
+#[derive(Debug, Clone, Copy)]
+pub struct IndexData<'a> {
+    sapling: &'a Sapling,
+}
+
+impl Sapling {
+    pub fn extract_index_data(&self) -> IndexData<'_> {
+        IndexData { sapling: self }
+    }
+}
+
+impl<'a> IndexData<'a> {
+    /// Returns each nullifier with the index of its spend.
+    pub fn nullifiers(&self) -> impl Iterator<Item = (&'a [u8; 32], usize)> + 'a {
+        let spends = self.sapling.spends.iter().enumerate();
+        spends.map(|(index, spend)| (&spend.nullifier, index))
+    }
+
+    /// Returns each note commitment `cmu` with the index of its output.
+    pub fn note_commitments(&self) -> impl Iterator<Item = (&'a [u8; 32], usize)> + 'a {
+        let outputs = self.sapling.outputs.iter().enumerate();
+        outputs.map(|(index, output)| (&output.note_commitment, index))
+    }
+
+    /// Returns each ephemeral key with the index of its output.
+    pub fn ephemeral_keys(&self) -> impl Iterator<Item = (&'a [u8; 32], usize)> + 'a {
+        let outputs = self.sapling.outputs.iter().enumerate();
+        outputs.map(|(index, output)| (&output.ephemeral_key, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Sapling, SaplingOutputDescription, SaplingSpendDescription};
+    use alloc::{vec, vec::Vec};
+
+    #[test]
+    fn test_extract_index_data() {
+        let mut sapling = Sapling {
+            spends: vec![SaplingSpendDescription::default(); 2],
+            outputs: vec![SaplingOutputDescription::default(); 3],
+            ..Sapling::default()
+        };
+        sapling.spends[1].nullifier = [1; 32];
+        sapling.outputs[2].note_commitment = [2; 32];
+        sapling.outputs[0].ephemeral_key = [3; 32];
+
+        let data = sapling.extract_index_data();
+        let nullifiers: Vec<_> = data.nullifiers().collect();
+        assert_eq!(nullifiers, vec![(&[0; 32], 0), (&[1; 32], 1)]);
+
+        let commitments: Vec<_> = data
+            .note_commitments()
+            .map(|(cmu, i)| (cmu[0], i))
+            .collect();
+        assert_eq!(commitments, vec![(0, 0), (0, 1), (2, 2)]);
+        assert_eq!(data.ephemeral_keys().next(), Some((&[3; 32], 0)));
+    }
+}
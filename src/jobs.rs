@@ -0,0 +1,295 @@
+use crate::{
+    check_output, check_spend_commitments, derive_binding_verification_key, read_randomized_key,
+    read_signature, read_spend_proof, require, verify_proof, CvSum, Error,
+    Groth16PreparedVerifyingKey, Groth16Proof, Sapling, VerificationPolicy,
+};
+use algebra::bls12_381::Fr;
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use zexe_redjubjub::{FixedGenerators, PublicKey, Signature};
+
+/// Independent unit of work of a bundle verification.
+///
+/// Jobs can run in any order and on any thread.
+pub enum VerificationJob {
+    /// Spend authorization signature of the spend at `index`.
+    SpendAuthSig {
+        index: usize,
+        key: PublicKey,
+        message: [u8; 64],
+        sig: Signature,
+    },
+    /// Proof of the spend at `index`.
+    SpendProof {
+        index: usize,
+        proof: Groth16Proof,
+        public_input: [Fr; 7],
+    },
+    /// Proof of the output at `index`.
+    OutputProof {
+        index: usize,
+        proof: Groth16Proof,
+        public_input: [Fr; 5],
+    },
+    /// Binding signature of the bundle.
+    BindingSig {
+        key: PublicKey,
+        message: [u8; 64],
+        sig: Signature,
+    },
+}
+
+impl VerificationJob {
+    fn run(
+        &self,
+        spend_vk: &Groth16PreparedVerifyingKey,
+        output_vk: &Groth16PreparedVerifyingKey,
+    ) -> Result<(), Error> {
+        match self {
+            VerificationJob::SpendAuthSig {
+                key, message, sig, ..
+            } => require(
+                key.verify(message, sig, FixedGenerators::SpendingKeyGenerator),
+                Error::SpendAuthSigInvalid,
+            ),
+            VerificationJob::SpendProof {
+                index,
+                proof,
+                public_input,
+            } => require(
+                verify_proof(spend_vk, proof, public_input)?,
+                Error::SpendProofInvalid { index: *index },
+            ),
+            VerificationJob::OutputProof {
+                index,
+                proof,
+                public_input,
+            } => require(
+                verify_proof(output_vk, proof, public_input)?,
+                Error::OutputProofInvalid { index: *index },
+            ),
+            VerificationJob::BindingSig { key, message, sig } => {
+                let is_signature_ok =
+                    key.verify(message, sig, FixedGenerators::ValueCommitmentRandomness);
+                require(is_signature_ok, Error::BindingSigInvalid)
+            }
+        }
+    }
+}
+
+/// Outcome of the job at `position`, as returned by `VerificationJobs::run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobResult {
+    position: usize,
+    result: Result<(), Error>,
+}
+
+impl JobResult {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn result(&self) -> Result<(), Error> {
+        self.result
+    }
+}
+
+/// Bundle decomposed into verification jobs, for embedders scheduling the work themselves.
+///
+/// `new` performs every decoding and encoding check. The expensive signature and proof
+/// checks are left to the jobs, whose results are combined by `finish`.
+pub struct VerificationJobs {
+    jobs: Vec<VerificationJob>,
+}
+
+impl VerificationJobs {
+    pub fn new(
+        policy: &VerificationPolicy,
+        sighash: &[u8; 32],
+        sapling: &Sapling,
+    ) -> Result<Self, Error> {
+        let mut jobs = Vec::with_capacity(2 * sapling.spends.len() + sapling.outputs.len() + 1);
+        let mut total = CvSum::new();
+
+        for (index, spend) in sapling.spends.iter().enumerate() {
+            let (value_commitment, anchor) =
+                check_spend_commitments(policy, &mut total, spend, &mut ())?;
+            let key = read_randomized_key(policy, &spend.randomized_key)
+                .map_err(|_| Error::InvalidRandomizedKey)?;
+            let sig = read_signature(policy, &spend.spend_auth_sig)
+                .map_err(|_| Error::MalformedSpendAuthSig)?;
+            let (proof, public_input) =
+                read_spend_proof(spend, value_commitment, anchor, &key, &mut ())?;
+
+            let mut message = [0u8; 64];
+            message[..32].copy_from_slice(&spend.randomized_key);
+            message[32..].copy_from_slice(sighash);
+            jobs.push(VerificationJob::SpendAuthSig {
+                index,
+                key,
+                message,
+                sig,
+            });
+            jobs.push(VerificationJob::SpendProof {
+                index,
+                proof,
+                public_input,
+            });
+        }
+
+        for (index, output) in sapling.outputs.iter().enumerate() {
+            let (proof, public_input) = check_output(policy, &mut total, output, &mut ())?;
+            jobs.push(VerificationJob::OutputProof {
+                index,
+                proof,
+                public_input,
+            });
+        }
+
+        let (key, message) =
+            derive_binding_verification_key(sighash, total, sapling.balancing_value, &mut ())?;
+        let sig =
+            read_signature(policy, &sapling.binding_sig).map_err(|_| Error::MalformedBindingSig)?;
+        jobs.push(VerificationJob::BindingSig { key, message, sig });
+
+        Ok(VerificationJobs { jobs })
+    }
+
+    pub fn jobs(&self) -> &[VerificationJob] {
+        &self.jobs
+    }
+
+    /// Runs the job at `position` of `jobs`.
+    ///
+    /// Panics if `position` is out of range.
+    pub fn run(
+        &self,
+        position: usize,
+        spend_vk: &Groth16PreparedVerifyingKey,
+        output_vk: &Groth16PreparedVerifyingKey,
+    ) -> JobResult {
+        JobResult {
+            position,
+            result: self.jobs[position].run(spend_vk, output_vk),
+        }
+    }
+
+    /// Accepts the bundle if there is one successful result per job, in any order.
+    ///
+    /// Returns the first error among `results`, or `Error::InvalidJobResults` unless there is
+    /// exactly one result for every position.
+    pub fn finish<I>(&self, results: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = JobResult>,
+    {
+        let mut seen = vec![false; self.jobs.len()];
+        let mut first_error = None;
+        for result in results {
+            match seen.get_mut(result.position) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(Error::InvalidJobResults),
+            }
+            if let Err(err) = result.result {
+                first_error = first_error.or(Some(err));
+            }
+        }
+        if seen.contains(&false) {
+            return Err(Error::InvalidJobResults);
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Same as `accept_sapling`, running the signature and proof checks on the rayon thread pool.
+///
+/// The decoding checks and the accumulation of value commitments run on the calling thread.
+#[cfg(feature = "rayon")]
+pub fn accept_sapling_par(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+) -> Result<(), Error> {
+    let jobs = VerificationJobs::new(policy, sighash, sapling)?;
+    let results: Vec<_> = (0..jobs.jobs().len())
+        .into_par_iter()
+        .map(|position| jobs.run(position, spend_vk, output_vk))
+        .collect();
+    jobs.finish(results)
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::VerificationJobs;
+    use crate::{
+        tests::mainnet_sapling, zcash, Error, Groth16PreparedVerifyingKey, VerificationPolicy,
+    };
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_verification_jobs() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        let jobs = VerificationJobs::new(&policy, &sighash, &sapling).unwrap();
+        assert_eq!(jobs.jobs().len(), 4);
+
+        // run in reverse order, as a scheduler might
+        let results: Vec<_> = (0..4)
+            .rev()
+            .map(|position| jobs.run(position, &spend_vk, &output_vk))
+            .collect();
+        assert_eq!(jobs.finish(results.clone()), Ok(()));
+        assert_eq!(
+            jobs.finish(results[1..].iter().cloned()),
+            Err(Error::InvalidJobResults)
+        );
+        assert_eq!(
+            jobs.finish(results.iter().cloned().chain(Some(results[0]))),
+            Err(Error::InvalidJobResults)
+        );
+
+        // a repeated result does not stand in for a job that never ran
+        let mut repeated = results.clone();
+        repeated[1] = repeated[0];
+        assert_eq!(jobs.finish(repeated), Err(Error::InvalidJobResults));
+
+        sapling.outputs[0].zkproof[0] ^= 0x20;
+        let jobs = VerificationJobs::new(&policy, &sighash, &sapling).unwrap();
+        let results = (0..4).map(|position| jobs.run(position, &spend_vk, &output_vk));
+        assert_eq!(
+            jobs.finish(results),
+            Err(Error::OutputProofInvalid { index: 0 })
+        );
+
+        sapling.spends[0].anchor = [0xff; 32];
+        assert_eq!(
+            VerificationJobs::new(&policy, &sighash, &sapling).err(),
+            Some(Error::InvalidAnchor)
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_accept_sapling_par() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        let par =
+            |sapling| super::accept_sapling_par(&policy, &spend_vk, &output_vk, &sighash, sapling);
+        assert_eq!(par(&sapling), Ok(()));
+
+        sapling.binding_sig[40] ^= 1;
+        assert_eq!(par(&sapling), Err(Error::BindingSigInvalid));
+    }
+}
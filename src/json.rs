@@ -0,0 +1,228 @@
+use crate::{Sapling, SaplingOutputDescription, SaplingSpendDescription};
+use alloc::{string::String, vec::Vec};
+use serde_json::{Map, Value};
+
+/// Reason for rejecting a zcashd `getrawtransaction` JSON object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonError {
+    /// The input is not valid JSON.
+    InvalidJson,
+    /// A required field is missing or has the wrong type.
+    MissingField(&'static str),
+    /// A field is not hex of the expected length.
+    InvalidHex(&'static str),
+    /// The value balance is out of range.
+    InvalidValueBalance,
+}
+
+/// Reads the Sapling components of a transaction returned by zcashd `getrawtransaction`
+/// with `verbose = 1`.
+pub fn sapling_from_rpc_json(json: &str) -> Result<Sapling, JsonError> {
+    let value: Value = serde_json::from_str(json).map_err(|_| JsonError::InvalidJson)?;
+    sapling_from_rpc_value(&value)
+}
+
+/// Same as `sapling_from_rpc_json` for an already parsed object.
+///
+/// zcashd prints `cv`, `anchor`, `nullifier`, `rk`, `cmu` and `ephemeralKey` as uint256
+/// values, which are byte-reversed. They are converted back to their consensus encoding.
+pub fn sapling_from_rpc_value(value: &Value) -> Result<Sapling, JsonError> {
+    let object = value
+        .as_object()
+        .ok_or(JsonError::MissingField("transaction"))?;
+
+    let spends = array(object, "vShieldedSpend")?
+        .iter()
+        .map(read_spend)
+        .collect::<Result<Vec<_>, _>>()?;
+    let outputs = array(object, "vShieldedOutput")?
+        .iter()
+        .map(read_output)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // transactions without shielded components have no binding signature
+    let mut binding_sig = [0u8; 64];
+    if !spends.is_empty() || !outputs.is_empty() {
+        read_hex(object, "bindingSig", &mut binding_sig)?;
+    }
+
+    Ok(Sapling {
+        balancing_value: read_value_balance(object)?,
+        spends,
+        outputs,
+        binding_sig,
+    })
+}
+
+fn read_spend(value: &Value) -> Result<SaplingSpendDescription, JsonError> {
+    let object = value
+        .as_object()
+        .ok_or(JsonError::MissingField("vShieldedSpend"))?;
+
+    let mut spend = SaplingSpendDescription::default();
+    read_uint256(object, "cv", &mut spend.value_commitment)?;
+    read_uint256(object, "anchor", &mut spend.anchor)?;
+    read_uint256(object, "nullifier", &mut spend.nullifier)?;
+    read_uint256(object, "rk", &mut spend.randomized_key)?;
+    read_hex(object, "proof", &mut spend.zkproof)?;
+    read_hex(object, "spendAuthSig", &mut spend.spend_auth_sig)?;
+    Ok(spend)
+}
+
+fn read_output(value: &Value) -> Result<SaplingOutputDescription, JsonError> {
+    let object = value
+        .as_object()
+        .ok_or(JsonError::MissingField("vShieldedOutput"))?;
+
+    let mut output = SaplingOutputDescription::default();
+    read_uint256(object, "cv", &mut output.value_commitment)?;
+    read_uint256(object, "cmu", &mut output.note_commitment)?;
+    read_uint256(object, "ephemeralKey", &mut output.ephemeral_key)?;
+    read_hex(object, "encCiphertext", &mut output.enc_cipher_text)?;
+    read_hex(object, "outCiphertext", &mut output.out_cipher_text)?;
+    read_hex(object, "proof", &mut output.zkproof)?;
+    Ok(output)
+}
+
+fn read_value_balance(object: &Map<String, Value>) -> Result<i64, JsonError> {
+    if let Some(value) = object.get("valueBalanceZat") {
+        return value.as_i64().ok_or(JsonError::InvalidValueBalance);
+    }
+
+    // older nodes only report the balance in ZEC
+    let zec = object
+        .get("valueBalance")
+        .and_then(Value::as_f64)
+        .ok_or(JsonError::MissingField("valueBalance"))?;
+    let zat = zec * 100_000_000.0;
+    if zat.is_nan() || zat <= i64::MIN as f64 || zat >= i64::MAX as f64 {
+        return Err(JsonError::InvalidValueBalance);
+    }
+
+    // round half away from zero, `as` truncates
+    Ok(if zat < 0.0 { zat - 0.5 } else { zat + 0.5 } as i64)
+}
+
+fn array<'a>(
+    object: &'a Map<String, Value>,
+    field: &'static str,
+) -> Result<&'a [Value], JsonError> {
+    object
+        .get(field)
+        .and_then(Value::as_array)
+        .map(|array| &array[..])
+        .ok_or(JsonError::MissingField(field))
+}
+
+fn read_hex(
+    object: &Map<String, Value>,
+    field: &'static str,
+    out: &mut [u8],
+) -> Result<(), JsonError> {
+    let string = object
+        .get(field)
+        .and_then(Value::as_str)
+        .ok_or(JsonError::MissingField(field))?;
+    hex::decode_to_slice(string, out).map_err(|_| JsonError::InvalidHex(field))
+}
+
+fn read_uint256(
+    object: &Map<String, Value>,
+    field: &'static str,
+    out: &mut [u8; 32],
+) -> Result<(), JsonError> {
+    read_hex(object, field, &mut out[..])?;
+    out.reverse();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sapling_from_rpc_json, JsonError};
+    use alloc::{format, string::String};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn ascending() -> [u8; 32] {
+        let mut result = [0u8; 32];
+        for (i, b) in result.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        result
+    }
+
+    fn transaction(value_balance: &str) -> String {
+        let mut reversed = ascending();
+        reversed.reverse();
+        let uint256 = hex(&reversed);
+
+        format!(
+            r#"{{
+                {},
+                "vShieldedSpend": [{{
+                    "cv": "{u}", "anchor": "{u}", "nullifier": "{u}", "rk": "{u}",
+                    "proof": "{}", "spendAuthSig": "{}"
+                }}],
+                "vShieldedOutput": [{{
+                    "cv": "{u}", "cmu": "{u}", "ephemeralKey": "{u}",
+                    "encCiphertext": "{}", "outCiphertext": "{}", "proof": "{}"
+                }}],
+                "bindingSig": "{}"
+            }}"#,
+            value_balance,
+            hex(&[1; 192]),
+            hex(&[2; 64]),
+            hex(&[3; 580]),
+            hex(&[4; 80]),
+            hex(&[5; 192]),
+            hex(&[6; 64]),
+            u = uint256,
+        )
+    }
+
+    #[test]
+    fn test_sapling_from_rpc_json() {
+        let sapling = sapling_from_rpc_json(&transaction(r#""valueBalanceZat": -12345"#)).unwrap();
+        assert_eq!(sapling.balancing_value, -12345);
+        assert_eq!(sapling.spends.len(), 1);
+        assert_eq!(sapling.outputs.len(), 1);
+
+        // uint256 fields are converted back to their consensus byte order
+        let spend = &sapling.spends[0];
+        assert_eq!(spend.value_commitment, ascending());
+        assert_eq!(spend.randomized_key, ascending());
+        assert_eq!(spend.zkproof[..], [1; 192][..]);
+        assert_eq!(sapling.outputs[0].note_commitment, ascending());
+        assert_eq!(sapling.outputs[0].enc_cipher_text[..], [3; 580][..]);
+        assert_eq!(sapling.binding_sig[..], [6; 64][..]);
+    }
+
+    #[test]
+    fn test_value_balance_in_zec() {
+        let sapling = sapling_from_rpc_json(&transaction(r#""valueBalance": 0.0001"#)).unwrap();
+        assert_eq!(sapling.balancing_value, 10_000);
+
+        let sapling = sapling_from_rpc_json(&transaction(r#""valueBalance": -1.23456789"#));
+        assert_eq!(sapling.unwrap().balancing_value, -123_456_789);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert_eq!(
+            sapling_from_rpc_json("{").err(),
+            Some(JsonError::InvalidJson)
+        );
+        assert_eq!(
+            sapling_from_rpc_json(r#"{"valueBalanceZat": 0}"#).err(),
+            Some(JsonError::MissingField("vShieldedSpend"))
+        );
+
+        let json = transaction(r#""valueBalanceZat": 0"#).replace("\"cmu\": \"1f", "\"cmu\": \"zz");
+        assert_eq!(
+            sapling_from_rpc_json(&json).err(),
+            Some(JsonError::InvalidHex("cmu"))
+        );
+    }
+}
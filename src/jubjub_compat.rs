@@ -0,0 +1,110 @@
+//! Conversions between this crate's Jubjub types and the zkcrypto `jubjub` and `redjubjub`
+//! crates.
+//!
+//! Both sides describe the same curve, so every conversion goes through the canonical 32 or
+//! 64 byte encoding.
+
+use crate::Point;
+use core::convert::TryFrom;
+use redjubjub::SigType;
+use zexe_redjubjub::{read_point, write_point, PublicKey, Signature};
+use zkcrypto_jubjub::{AffinePoint, ExtendedPoint};
+
+pub fn point_to_jubjub(point: &Point) -> ExtendedPoint {
+    let mut bytes = [0u8; 32];
+    write_point(point, &mut bytes[..]).expect("point is 32 bytes");
+    let affine = AffinePoint::from_bytes(bytes);
+    assert!(
+        bool::from(affine.is_some()),
+        "points of both crates are on the same curve"
+    );
+    affine.unwrap().into()
+}
+
+pub fn point_from_jubjub(point: &ExtendedPoint) -> Point {
+    let bytes = AffinePoint::from(*point).to_bytes();
+    read_point(&bytes[..]).expect("points of both crates are on the same curve")
+}
+
+/// Converts `key` to a redjubjub verification key.
+///
+/// Fails if redjubjub rejects the key, e.g. because its point is of small order.
+pub fn public_key_to_redjubjub<T: SigType>(
+    key: &PublicKey,
+) -> Result<redjubjub::VerificationKey<T>, ()> {
+    let mut bytes = [0u8; 32];
+    write_point(&key.point, &mut bytes[..]).expect("public key is 32 bytes");
+    redjubjub::VerificationKey::try_from(bytes).map_err(|_| ())
+}
+
+pub fn public_key_from_redjubjub<T: SigType>(key: &redjubjub::VerificationKey<T>) -> PublicKey {
+    let bytes: [u8; 32] = (*key).into();
+    PublicKey::new(read_point(&bytes[..]).expect("verification key is a valid point"))
+}
+
+pub fn signature_to_redjubjub<T: SigType>(sig: &Signature) -> redjubjub::Signature<T> {
+    let mut bytes = [0u8; 64];
+    sig.write(&mut bytes[..]).expect("signature is 64 bytes");
+    redjubjub::Signature::from(bytes)
+}
+
+/// Converts a redjubjub signature.
+///
+/// Neither side validates `R` or `S` until verification, so this never fails.
+pub fn signature_from_redjubjub<T: SigType>(sig: &redjubjub::Signature<T>) -> Signature {
+    let bytes: [u8; 64] = (*sig).into();
+    Signature::read(&bytes[..]).expect("signature is 64 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        point_from_jubjub, point_to_jubjub, public_key_from_redjubjub, public_key_to_redjubjub,
+        signature_from_redjubjub, signature_to_redjubjub,
+    };
+    use crate::Point;
+    use redjubjub::{Binding, SpendAuth};
+    use zexe_redjubjub::{write_point, FixedGenerators, PublicKey, Signature};
+
+    fn generator() -> Point {
+        FixedGenerators::SpendingKeyGenerator.point().into()
+    }
+
+    #[test]
+    fn test_point_roundtrip() {
+        let point = generator();
+        let mut bytes = [0u8; 32];
+        write_point(&point, &mut bytes[..]).unwrap();
+
+        let converted = point_to_jubjub(&point);
+        assert_eq!(
+            zkcrypto_jubjub::AffinePoint::from(converted).to_bytes(),
+            bytes
+        );
+        assert_eq!(point_from_jubjub(&converted), point);
+    }
+
+    #[test]
+    fn test_public_key_roundtrip() {
+        let key = PublicKey::new(generator());
+        let converted = public_key_to_redjubjub::<SpendAuth>(&key).unwrap();
+        assert_eq!(public_key_from_redjubjub(&converted).point, key.point);
+    }
+
+    #[test]
+    fn test_signature_roundtrip() {
+        let mut bytes = [0u8; 64];
+        write_point(&generator(), &mut bytes[..32]).unwrap();
+        bytes[32] = 7;
+        let sig = Signature::read(&bytes[..]).unwrap();
+
+        let converted = signature_to_redjubjub::<Binding>(&sig);
+        assert_eq!(<[u8; 64]>::from(converted), bytes);
+
+        let mut written = [0u8; 64];
+        signature_from_redjubjub(&converted)
+            .write(&mut written[..])
+            .unwrap();
+        assert_eq!(written, bytes);
+    }
+}
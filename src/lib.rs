@@ -1,173 +1,823 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+#[cfg(feature = "verify-proofs")]
+pub mod arkworks;
+#[cfg(feature = "verify-proofs")]
 pub mod zcash;
 
-mod affine;
+#[cfg(feature = "verify-proofs")]
+pub mod affine;
+#[cfg(feature = "alloc-stats")]
+pub mod alloc_stats;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+mod batch;
+#[cfg(feature = "verify-sigs")]
+pub mod binding;
+mod cancel;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+mod collect;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+mod context;
+#[cfg(feature = "verify-sigs")]
+mod cv_sum;
 mod data;
-mod multipack;
-mod proof;
+#[cfg(all(test, feature = "differential"))]
+mod differential;
+mod error;
+mod error_code;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub mod facade;
+mod filter;
+mod index;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+mod jobs;
+#[cfg(feature = "json-rpc")]
+pub mod json;
+#[cfg(feature = "jubjub-compat")]
+pub mod jubjub_compat;
+mod lint;
+#[cfg(feature = "verify-proofs")]
+pub mod multipack;
+#[cfg(feature = "mutation")]
+pub mod mutation;
+#[cfg(feature = "verify-proofs")]
+mod network;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub mod offline;
+#[cfg(feature = "params")]
+pub mod params;
+#[cfg(feature = "parse")]
+mod parse;
+mod policy;
+mod pool;
+#[cfg(feature = "verify-proofs")]
+pub mod prepared;
+mod progress;
+#[cfg(feature = "verify-proofs")]
+pub mod proof;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+mod resumable;
+#[cfg(feature = "batch-sigs")]
+mod sig_batch;
+#[cfg(feature = "test-circuit")]
+pub mod test_circuit;
+mod transcript;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+mod typed;
+#[cfg(feature = "zip321")]
+pub mod zip321;
 
+#[cfg(feature = "verify-proofs")]
+use algebra::Bls12_381;
+#[cfg(feature = "verify-sigs")]
 use algebra::{
-    bls12_381,
-    jubjub::JubJubParameters,
+    bls12_381::Fr,
+    fields::{FpParameters, PrimeField},
+    jubjub::{self, JubJubParameters},
     prelude::{Group, Zero},
-    Bls12_381, FromBytes, ModelParameters,
+    BigInteger, BigInteger256,
 };
 use alloc::vec::Vec;
+#[cfg(feature = "verify-sigs")]
 use core::ops::{Add, Neg};
-use groth16::{verify_proof, PreparedVerifyingKey, VerifyingKey};
+#[cfg(feature = "verify-proofs")]
+use groth16::{PreparedVerifyingKey, Proof, VerifyingKey};
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+use proof::verify_proof;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "verify-sigs")]
 use zexe_redjubjub::{read_point, write_point, FixedGenerators, PublicKey, Signature};
 
-pub use data::{Sapling, SaplingOutputDescription, SaplingSpendDescription};
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub use batch::BatchVerifier;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+use cancel::check_cancelled;
+pub use cancel::{BatchError, Cancellation};
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub use collect::{
+    accept_sapling_collect_all, accept_sapling_located, Component, Failure, UNKNOWN_CHECK,
+};
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub use context::{accept_sapling_iter, accept_sapling_ref, SaplingVerificationContext};
+#[cfg(feature = "verify-sigs")]
+pub use cv_sum::CvSum;
+pub use data::{
+    DifferentAnchors, OutputDescriptionRef, Sapling, SaplingBundleV5, SaplingOutputDescription,
+    SaplingRef, SaplingSpendDescription, SaplingSpendDescriptionV5, SpendDescriptionRef,
+    MARGINAL_FEE,
+};
+pub use error::Error;
+pub use error_code::ErrorCode;
+pub use filter::{NullifierFilter, MAX_FILTER_HASHES};
+pub use index::IndexData;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs", feature = "rayon"))]
+pub use jobs::accept_sapling_par;
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub use jobs::{JobResult, VerificationJob, VerificationJobs};
+pub use lint::{lint_ephemeral_keys, Lint};
+#[cfg(feature = "verify-proofs")]
+pub use network::SaplingNetwork;
+#[cfg(feature = "parse")]
+pub use parse::{ReadError, ReadLimits};
+pub use policy::{
+    VerificationPolicy, BLOSSOM_BRANCH_ID, CANOPY_BRANCH_ID, HEARTWOOD_BRANCH_ID, NU5_BRANCH_ID,
+    NU6_BRANCH_ID, OVERWINTER_BRANCH_ID, SAPLING_BRANCH_ID,
+};
+pub use pool::{classify_transaction, PoolTransfer, TransactionKind};
+pub use progress::{Progress, ProgressStage};
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub use resumable::{ResumableVerifier, ResumeError, RESUMABLE_STATE_SIZE};
+#[cfg(feature = "batch-sigs")]
+pub use sig_batch::SignatureBatch;
+#[cfg(feature = "verify-sigs")]
+use transcript::record;
+pub use transcript::{RecordingTranscript, Transcript};
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub use typed::{TypedSapling, TypedSpendDescription};
 
+#[cfg(feature = "verify-proofs")]
 pub type Groth16VerifyingKey = VerifyingKey<Bls12_381>;
+#[cfg(feature = "verify-proofs")]
 pub type Groth16PreparedVerifyingKey = PreparedVerifyingKey<Bls12_381>;
+#[cfg(feature = "verify-proofs")]
+pub type Groth16Proof = Proof<Bls12_381>;
+#[cfg(feature = "verify-sigs")]
 pub type Point = zexe_redjubjub::Point<JubJubParameters>;
 
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
 pub fn accept_sapling(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+) -> Result<(), Error> {
+    accept_sapling_with_transcript(policy, spend_vk, output_vk, sighash, sapling, &mut ())
+}
+
+/// Same as `accept_sapling` with the verifying keys of `network`.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_sapling_on<N: SaplingNetwork + ?Sized>(
+    network: &N,
+    policy: &VerificationPolicy,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+) -> Result<(), Error> {
+    accept_sapling(
+        policy,
+        network.spend_vk(),
+        network.output_vk(),
+        sighash,
+        sapling,
+    )
+}
+
+/// Same as `accept_sapling` for the bundle of a v5 transaction, whose spends are verified
+/// against its shared anchor.
+///
+/// `sighash` is the ZIP 244 signature digest of the transaction.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_sapling_v5(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    bundle: &SaplingBundleV5,
+) -> Result<(), Error> {
+    accept_sapling(policy, spend_vk, output_vk, sighash, &bundle.to_sapling())
+}
+
+/// Same as `accept_sapling`, recording every input and decision into `transcript`.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_sapling_with_transcript<T: Transcript>(
+    policy: &VerificationPolicy,
     spend_vk: &Groth16PreparedVerifyingKey,
     output_vk: &Groth16PreparedVerifyingKey,
     sighash: &[u8; 32],
     sapling: &Sapling,
-) -> Result<(), ()> {
-    let mut total = Point::zero();
-    for (_, spend) in sapling.spends.iter().enumerate() {
-        accept_spend(spend_vk, sighash, &mut total, spend)?;
+    transcript: &mut T,
+) -> Result<(), Error> {
+    transcript.append("sighash", sighash);
+    transcript.append("balancing_value", &sapling.balancing_value.to_le_bytes());
+
+    // run every cheap check, signatures included, before any pairing so that an invalid
+    // bundle is rejected without paying for its proofs
+    let mut total = CvSum::new();
+    let mut spend_proofs = Vec::with_capacity(sapling.spends.len());
+    for spend in sapling.spends.iter() {
+        spend_proofs.push(check_spend(policy, sighash, &mut total, spend, transcript)?);
+    }
+
+    let mut output_proofs = Vec::with_capacity(sapling.outputs.len());
+    for output in sapling.outputs.iter() {
+        output_proofs.push(check_output(policy, &mut total, output, transcript)?);
     }
 
-    for (_, output) in sapling.outputs.iter().enumerate() {
-        accept_output(output_vk, &mut total, output)?;
+    accept_sapling_final(policy, sighash, total, sapling, transcript)?;
+
+    verify_bundle_proofs(
+        spend_vk,
+        output_vk,
+        &spend_proofs,
+        &output_proofs,
+        transcript,
+    )
+}
+
+/// Same as `accept_sapling` for a bundle whose keys and signatures were parsed when it was
+/// constructed.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_typed_sapling(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &TypedSapling,
+) -> Result<(), Error> {
+    let transcript = &mut ();
+
+    let mut total = CvSum::new();
+    let mut spend_proofs = Vec::with_capacity(sapling.spends.len());
+    for spend in sapling.spends.iter() {
+        let description = spend.description();
+        let (value_commitment, anchor) =
+            check_spend_commitments(policy, &mut total, description, transcript)?;
+        spend_proofs.push(check_spend_authorization(
+            sighash,
+            description,
+            value_commitment,
+            anchor,
+            spend.randomized_key(),
+            spend.spend_auth_sig(),
+            transcript,
+        )?);
+    }
+
+    let mut output_proofs = Vec::with_capacity(sapling.outputs.len());
+    for output in sapling.outputs.iter() {
+        output_proofs.push(check_output(policy, &mut total, output, transcript)?);
+    }
+
+    let (binding_verification_key, data_to_be_signed) =
+        derive_binding_verification_key(sighash, total, sapling.balancing_value, transcript)?;
+    verify_binding_sig(
+        &binding_verification_key,
+        &data_to_be_signed,
+        &sapling.binding_sig,
+        transcript,
+    )?;
+
+    verify_bundle_proofs(
+        spend_vk,
+        output_vk,
+        &spend_proofs,
+        &output_proofs,
+        transcript,
+    )
+}
+
+/// Verifies the proofs collected by `check_spend` and `check_output`, in order.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn verify_bundle_proofs<T: Transcript>(
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    spend_proofs: &[(Groth16Proof, [Fr; 7])],
+    output_proofs: &[(Groth16Proof, [Fr; 5])],
+    transcript: &mut T,
+) -> Result<(), Error> {
+    for (index, (zkproof, public_input)) in spend_proofs.iter().enumerate() {
+        let is_verification_ok = verify_proof(spend_vk, zkproof, public_input)?;
+        let error = Error::SpendProofInvalid { index };
+        record(
+            transcript,
+            "spend.zkproof.verified",
+            require(is_verification_ok, error),
+        )?;
     }
 
-    accept_sapling_final(sighash, total, sapling)
+    for (index, (zkproof, public_input)) in output_proofs.iter().enumerate() {
+        let is_verification_ok = verify_proof(output_vk, zkproof, public_input)?;
+        let error = Error::OutputProofInvalid { index };
+        record(
+            transcript,
+            "output.zkproof.verified",
+            require(is_verification_ok, error),
+        )?;
+    }
+
+    Ok(())
 }
 
+/// Same as `accept_sapling`, verifying all proofs of the bundle together with a single final
+/// exponentiation.
+///
+/// Faster for bundles with several descriptions, but does not tell which proof is invalid.
+/// `rng` provides the weights that keep the combined check sound, see
+/// `proof::verify_proofs_combined`.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_sapling_combined<R: RngCore + CryptoRng>(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+    rng: &mut R,
+) -> Result<(), Error> {
+    accept_sapling_combined_cancellable(policy, spend_vk, output_vk, sighash, sapling, rng, &())
+        .map_err(|err| match err {
+            BatchError::Invalid(err) => err,
+            BatchError::Cancelled => unreachable!("() never cancels"),
+        })
+}
+
+/// Same as `accept_sapling_combined`, giving up with `BatchError::Cancelled` as soon as
+/// `cancel` is cancelled.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_sapling_combined_cancellable<R: RngCore + CryptoRng, C: Cancellation + ?Sized>(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+    rng: &mut R,
+    cancel: &C,
+) -> Result<(), BatchError> {
+    accept_sapling_combined_with_hooks(
+        policy,
+        spend_vk,
+        output_vk,
+        sighash,
+        sapling,
+        rng,
+        cancel,
+        &mut (),
+    )
+}
+
+/// Same as `accept_sapling_combined_cancellable`, also reporting to `progress` after every
+/// description and once the proofs are verified.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+#[allow(clippy::too_many_arguments)]
+pub fn accept_sapling_combined_with_hooks<R, C, P>(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    output_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    sapling: &Sapling,
+    rng: &mut R,
+    cancel: &C,
+    progress: &mut P,
+) -> Result<(), BatchError>
+where
+    R: RngCore + CryptoRng,
+    C: Cancellation + ?Sized,
+    P: Progress + ?Sized,
+{
+    let descriptions = sapling.spends.len() + sapling.outputs.len();
+    let mut proofs = Vec::with_capacity(descriptions);
+    let mut total = CvSum::new();
+    for spend in sapling.spends.iter() {
+        check_cancelled(cancel)?;
+        let (zkproof, public_input) = check_spend(policy, sighash, &mut total, spend, &mut ())?;
+        let prepared_inputs = proof::prepare_inputs(spend_vk, &public_input)?;
+        proofs.push((spend_vk, zkproof, prepared_inputs));
+        progress.report(ProgressStage::Descriptions, proofs.len(), descriptions);
+    }
+
+    for output in sapling.outputs.iter() {
+        check_cancelled(cancel)?;
+        let (zkproof, public_input) = check_output(policy, &mut total, output, &mut ())?;
+        let prepared_inputs = proof::prepare_inputs(output_vk, &public_input)?;
+        proofs.push((output_vk, zkproof, prepared_inputs));
+        progress.report(ProgressStage::Descriptions, proofs.len(), descriptions);
+    }
+
+    accept_sapling_final(policy, sighash, total, sapling, &mut ())?;
+
+    check_cancelled(cancel)?;
+    let is_verification_ok = proof::verify_proofs_combined(&proofs, rng)?;
+    progress.report(ProgressStage::Proofs, proofs.len(), proofs.len());
+    Ok(require(is_verification_ok, Error::CombinedProofsInvalid)?)
+}
+
+/// Checks a single spend and accumulates its value commitment into `total`.
+///
+/// `index` is the position of `spend` in its bundle, reported if its proof does not verify.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
 pub fn accept_spend(
+    policy: &VerificationPolicy,
     spend_vk: &Groth16PreparedVerifyingKey,
     sighash: &[u8; 32],
-    total: &mut Point,
+    total: &mut CvSum,
     spend: &SaplingSpendDescription,
-) -> Result<(), ()> {
-    use algebra::ProjectiveCurve;
+    index: usize,
+) -> Result<(), Error> {
+    accept_spend_with_transcript(policy, spend_vk, sighash, total, spend, index, &mut ())
+}
 
+/// Same as `accept_spend`, recording every input and decision into `transcript`.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_spend_with_transcript<T: Transcript>(
+    policy: &VerificationPolicy,
+    spend_vk: &Groth16PreparedVerifyingKey,
+    sighash: &[u8; 32],
+    total: &mut CvSum,
+    spend: &SaplingSpendDescription,
+    index: usize,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    let (zkproof, public_input) = check_spend(policy, sighash, total, spend, transcript)?;
+
+    // check the proof
+    let is_verification_ok = verify_proof(&spend_vk, &zkproof, &public_input)?;
+    let error = Error::SpendProofInvalid { index };
+    record(
+        transcript,
+        "spend.zkproof.verified",
+        require(is_verification_ok, error),
+    )
+}
+
+/// Performs every check of `accept_spend` except for the proof verification.
+///
+/// Returns the decoded proof and its public input.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn check_spend<T: Transcript>(
+    policy: &VerificationPolicy,
+    sighash: &[u8; 32],
+    total: &mut CvSum,
+    spend: &SaplingSpendDescription,
+    transcript: &mut T,
+) -> Result<(Groth16Proof, [Fr; 7]), Error> {
+    transcript.append("spend.value_commitment", &spend.value_commitment);
+    transcript.append("spend.anchor", &spend.anchor);
+    transcript.append("spend.nullifier", &spend.nullifier);
+    transcript.append("spend.randomized_key", &spend.randomized_key);
+    transcript.append("spend.zkproof", &spend.zkproof);
+    transcript.append("spend.spend_auth_sig", &spend.spend_auth_sig);
+
+    let (value_commitment, anchor) = check_spend_commitments(policy, total, spend, transcript)?;
+
+    let randomized_key = record(
+        transcript,
+        "spend.randomized_key.valid",
+        read_randomized_key(policy, &spend.randomized_key).map_err(|_| Error::InvalidRandomizedKey),
+    )?;
+
+    // deserialize the signature
+    let spend_auth_sig = record(
+        transcript,
+        "spend.spend_auth_sig.valid",
+        read_signature(policy, &spend.spend_auth_sig).map_err(|_| Error::MalformedSpendAuthSig),
+    )?;
+
+    check_spend_authorization(
+        sighash,
+        spend,
+        value_commitment,
+        anchor,
+        &randomized_key,
+        &spend_auth_sig,
+        transcript,
+    )
+}
+
+/// Checks the value commitment and the anchor of a spend and accumulates the former into
+/// `total`.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn check_spend_commitments<T: Transcript>(
+    policy: &VerificationPolicy,
+    total: &mut CvSum,
+    spend: &SaplingSpendDescription,
+    transcript: &mut T,
+) -> Result<(Point, Fr), Error> {
     // deserialize and check value commitment
-    let value_commitment = require_non_small_order_point(&spend.value_commitment)?;
+    let value_commitment = record(
+        transcript,
+        "spend.value_commitment.valid",
+        read_non_small_order_point(policy, &spend.value_commitment)
+            .map_err(|_| Error::InvalidSpendValueCommitment),
+    )?;
 
     // accumulate value commitment
-    *total += &value_commitment;
+    total.add(&value_commitment);
 
     // deserialize the anchor, which should be an element of Fr
-    let anchor = <JubJubParameters as ModelParameters>::BaseField::read(&spend.anchor as &[u8])
-        .map_err(|_| ())?;
+    let anchor = read_fr(policy, &spend.anchor).map_err(|_| Error::InvalidAnchor);
+    let anchor = record(transcript, "spend.anchor.valid", anchor)?;
+
+    Ok((value_commitment, anchor))
+}
 
+/// Verifies the spend authorization signature against the already parsed key and signature.
+///
+/// Returns the decoded proof and its public input.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn check_spend_authorization<T: Transcript>(
+    sighash: &[u8; 32],
+    spend: &SaplingSpendDescription,
+    value_commitment: Point,
+    anchor: Fr,
+    randomized_key: &PublicKey,
+    spend_auth_sig: &Signature,
+    transcript: &mut T,
+) -> Result<(Groth16Proof, [Fr; 7]), Error> {
     // compute the signature's message for randomized key && spend_auth_sig
     let mut data_to_be_signed = [0u8; 64];
     data_to_be_signed[..32].copy_from_slice(&spend.randomized_key);
     data_to_be_signed[32..].copy_from_slice(sighash);
 
-    let randomized_key = PublicKey::read(&spend.randomized_key[..]).map_err(|_| ())?;
-    if is_small_order(&randomized_key.point) {
-        return Err(());
-    }
-
-    // deserialize the signature
-    let spend_auth_sig = Signature::read(&spend.spend_auth_sig[..])
-        .expect("only could fail if length of passed buffer != 64; qed");
-
     // verify the spend_auth_sig
-    if !randomized_key.verify(
+    let is_signature_ok = randomized_key.verify(
         &data_to_be_signed,
-        &spend_auth_sig,
+        spend_auth_sig,
         FixedGenerators::SpendingKeyGenerator,
-    ) {
-        return Err(());
-    }
+    );
+    let error = Error::SpendAuthSigInvalid;
+    record(
+        transcript,
+        "spend.spend_auth_sig.verified",
+        require(is_signature_ok, error),
+    )?;
 
-    // Add the nullifier through multiscalar packing
-    let nullifier = multipack::bytes_to_bits_le(&spend.nullifier);
-    let nullifier = multipack::compute_multipacking::<bls12_381::g1::Parameters>(&nullifier);
-    assert_eq!(nullifier.len(), 2);
+    read_spend_proof(spend, value_commitment, anchor, randomized_key, transcript)
+}
 
-    let randomized_key_xy = randomized_key.point.into_affine();
-    let value_xy = value_commitment.into_affine();
-    let public_input = [
-        randomized_key_xy.x,
-        randomized_key_xy.y,
-        value_xy.x,
-        value_xy.y,
-        anchor,
-        nullifier[0],
-        nullifier[1],
-    ];
+/// Decodes the proof of a spend and computes its public input.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn read_spend_proof<T: Transcript>(
+    spend: &SaplingSpendDescription,
+    value_commitment: Point,
+    anchor: Fr,
+    randomized_key: &PublicKey,
+    transcript: &mut T,
+) -> Result<(Groth16Proof, [Fr; 7]), Error> {
+    let public_input = make_spend_public_input(spend, value_commitment, anchor, randomized_key);
 
     // deserialize the proof
-    // TODO: its currently unimplemented in groth16
-    let zkproof = proof::read_proof(spend.zkproof)?;
+    let zkproof = record(
+        transcript,
+        "spend.zkproof.valid",
+        proof::read_proof(spend.zkproof).map_err(|_| Error::MalformedSpendProof),
+    )?;
 
-    // check the proof
-    let is_verification_ok = verify_proof(&spend_vk, &zkproof, &public_input).map_err(|_| ())?;
-
-    if !is_verification_ok {
-        return Err(());
-    }
-
-    Ok(())
+    Ok((zkproof, public_input))
 }
 
+/// Checks a single output and subtracts its value commitment from `total`.
+///
+/// `index` is the position of `output` in its bundle, reported if its proof does not verify.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
 pub fn accept_output(
+    policy: &VerificationPolicy,
+    output_vk: &Groth16PreparedVerifyingKey,
+    total: &mut CvSum,
+    output: &SaplingOutputDescription,
+    index: usize,
+) -> Result<(), Error> {
+    accept_output_with_transcript(policy, output_vk, total, output, index, &mut ())
+}
+
+/// Same as `accept_output`, recording every input and decision into `transcript`.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn accept_output_with_transcript<T: Transcript>(
+    policy: &VerificationPolicy,
     output_vk: &Groth16PreparedVerifyingKey,
-    total: &mut Point,
+    total: &mut CvSum,
+    output: &SaplingOutputDescription,
+    index: usize,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    let (zkproof, public_input) = check_output(policy, total, output, transcript)?;
+
+    // check the proof
+    let is_verification_ok = verify_proof(&output_vk, &zkproof, &public_input)?;
+    let error = Error::OutputProofInvalid { index };
+    record(
+        transcript,
+        "output.zkproof.verified",
+        require(is_verification_ok, error),
+    )
+}
+
+/// Performs every check of `accept_output` except for the proof verification.
+///
+/// Returns the decoded proof and its public input.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn check_output<T: Transcript>(
+    policy: &VerificationPolicy,
+    total: &mut CvSum,
     output: &SaplingOutputDescription,
-) -> Result<(), ()> {
-    use algebra::curves::ProjectiveCurve;
+    transcript: &mut T,
+) -> Result<(Groth16Proof, [Fr; 5]), Error> {
+    transcript.append("output.value_commitment", &output.value_commitment);
+    transcript.append("output.note_commitment", &output.note_commitment);
+    transcript.append("output.ephemeral_key", &output.ephemeral_key);
+    transcript.append("output.zkproof", &output.zkproof);
 
     // deserialize and check value commitment
-    let value_commitment = require_non_small_order_point(&output.value_commitment)?;
+    let value_commitment = record(
+        transcript,
+        "output.value_commitment.valid",
+        read_non_small_order_point(policy, &output.value_commitment)
+            .map_err(|_| Error::InvalidOutputValueCommitment),
+    )?;
 
     // accumulate value commitment
-    *total -= &value_commitment;
+    total.sub(&value_commitment);
 
-    // deserialize the anchor, which should be an element of Fr
-    let note_commitment =
-        <JubJubParameters as ModelParameters>::BaseField::read(&output.note_commitment as &[u8])
-            .map_err(|_| ())?;
+    // deserialize the note commitment, which should be an element of Fr
+    let note_commitment = record(
+        transcript,
+        "output.note_commitment.valid",
+        read_fr(policy, &output.note_commitment).map_err(|_| Error::InvalidNoteCommitment),
+    )?;
 
     // deserialize the ephemeral key
-    let ephemeral_key = require_non_small_order_point(&output.ephemeral_key)?;
+    let ephemeral_key = record(
+        transcript,
+        "output.ephemeral_key.valid",
+        read_non_small_order_point(policy, &output.ephemeral_key)
+            .map_err(|_| Error::InvalidEphemeralKey),
+    )?;
 
     // construct public input for circuit
+    let public_input = make_output_public_input(value_commitment, ephemeral_key, note_commitment);
+
+    // deserialize the proof
+    let zkproof = record(
+        transcript,
+        "output.zkproof.valid",
+        proof::read_proof(output.zkproof).map_err(|_| Error::MalformedOutputProof),
+    )?;
+
+    Ok((zkproof, public_input))
+}
+
+/// Decodes `spend` and computes the public input of its proof, exactly as `accept_spend` does.
+///
+/// Only the encodings are checked, neither the signature nor the proof is verified. The
+/// sighash is not an input of the spend circuit, hence not needed here.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn spend_public_inputs(
+    policy: &VerificationPolicy,
+    spend: &SaplingSpendDescription,
+) -> Result<[Fr; 7], Error> {
+    let value_commitment = read_non_small_order_point(policy, &spend.value_commitment)
+        .map_err(|_| Error::InvalidSpendValueCommitment)?;
+    let anchor = read_fr(policy, &spend.anchor).map_err(|_| Error::InvalidAnchor)?;
+    let randomized_key = read_randomized_key(policy, &spend.randomized_key)
+        .map_err(|_| Error::InvalidRandomizedKey)?;
+
+    Ok(make_spend_public_input(
+        spend,
+        value_commitment,
+        anchor,
+        &randomized_key,
+    ))
+}
+
+/// Decodes `output` and computes the public input of its proof, exactly as `accept_output`
+/// does.
+///
+/// Only the encodings are checked, the proof is not verified.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn output_public_inputs(
+    policy: &VerificationPolicy,
+    output: &SaplingOutputDescription,
+) -> Result<[Fr; 5], Error> {
+    let value_commitment = read_non_small_order_point(policy, &output.value_commitment)
+        .map_err(|_| Error::InvalidOutputValueCommitment)?;
+    let note_commitment =
+        read_fr(policy, &output.note_commitment).map_err(|_| Error::InvalidNoteCommitment)?;
+    let ephemeral_key = read_non_small_order_point(policy, &output.ephemeral_key)
+        .map_err(|_| Error::InvalidEphemeralKey)?;
+
+    Ok(make_output_public_input(
+        value_commitment,
+        ephemeral_key,
+        note_commitment,
+    ))
+}
+
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn make_spend_public_input(
+    spend: &SaplingSpendDescription,
+    value_commitment: Point,
+    anchor: Fr,
+    randomized_key: &PublicKey,
+) -> [Fr; 7] {
+    use algebra::ProjectiveCurve;
+
+    // Add the nullifier through multiscalar packing
+    let nullifier = nullifier_to_field_elements(&spend.nullifier);
+
+    let randomized_key_xy = randomized_key.point.into_affine();
+    let value_xy = value_commitment.into_affine();
+    [
+        randomized_key_xy.x,
+        randomized_key_xy.y,
+        value_xy.x,
+        value_xy.y,
+        anchor,
+        nullifier[0],
+        nullifier[1],
+    ]
+}
+
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn make_output_public_input(
+    value_commitment: Point,
+    ephemeral_key: Point,
+    note_commitment: Fr,
+) -> [Fr; 5] {
+    use algebra::ProjectiveCurve;
+
     let ephemeral_xy = ephemeral_key.into_affine();
     let value_xy = value_commitment.into_affine();
-    let public_input = [
+    [
         value_xy.x,
         value_xy.y,
         ephemeral_xy.x,
         ephemeral_xy.y,
         note_commitment,
-    ];
+    ]
+}
 
-    // deserialize the proof
-    // TODO: its currently unimplemented in groth16
-    let zkproof = proof::read_proof(output.zkproof)?;
+/// Maps a nullifier to the two public inputs of the spend circuit, exactly as `accept_spend`
+/// does.
+///
+/// The nullifier is read as 256 little-endian bits. The first element holds the low 254 bits,
+/// the second one the top 2 bits.
+#[cfg(feature = "verify-proofs")]
+pub fn nullifier_to_field_elements(nullifier: &[u8; 32]) -> [algebra::bls12_381::Fr; 2] {
+    multipack::pack_nullifier(nullifier)
+}
 
-    // check the proof
-    let is_verification_ok = verify_proof(&output_vk, &zkproof, &public_input).map_err(|_| ())?;
+/// Checks the balancing value and the binding signature of `sapling` against the value
+/// commitments accumulated in `total` by `accept_spend` and `accept_output`.
+#[cfg(feature = "verify-sigs")]
+pub fn accept_binding_sig(
+    policy: &VerificationPolicy,
+    sighash: &[u8; 32],
+    total: CvSum,
+    sapling: &Sapling,
+) -> Result<(), Error> {
+    accept_sapling_final(policy, sighash, total, sapling, &mut ())
+}
 
-    if !is_verification_ok {
-        return Err(());
-    }
+#[cfg(feature = "verify-sigs")]
+fn accept_sapling_final<T: Transcript>(
+    policy: &VerificationPolicy,
+    sighash: &[u8; 32],
+    total: CvSum,
+    sapling: &Sapling,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    transcript.append("binding_sig", &sapling.binding_sig);
 
-    Ok(())
+    let (binding_verification_key, data_to_be_signed) =
+        derive_binding_verification_key(sighash, total, sapling.balancing_value, transcript)?;
+
+    // deserialize the binding signature
+    let binding_sig = record(
+        transcript,
+        "binding_sig.valid",
+        read_signature(policy, &sapling.binding_sig).map_err(|_| Error::MalformedBindingSig),
+    )?;
+
+    verify_binding_sig(
+        &binding_verification_key,
+        &data_to_be_signed,
+        &binding_sig,
+        transcript,
+    )
 }
 
-fn accept_sapling_final(sighash: &[u8; 32], total: Point, sapling: &Sapling) -> Result<(), ()> {
+/// Derives bvk from the accumulated value commitments.
+///
+/// Returns the key and the message the binding signature signs.
+#[cfg(feature = "verify-sigs")]
+fn derive_binding_verification_key<T: Transcript>(
+    sighash: &[u8; 32],
+    total: CvSum,
+    balancing_value: i64,
+    transcript: &mut T,
+) -> Result<(PublicKey, [u8; 64]), Error> {
     // obtain current bvk from the context
-    let mut binding_verification_key = PublicKey::new(total);
+    let mut binding_verification_key = PublicKey::new(total.point());
 
     // compute value balance
-    let mut value_balance = compute_value_balance(sapling.balancing_value)?;
+    let mut value_balance = record(
+        transcript,
+        "balancing_value.valid",
+        compute_value_balance(balancing_value).map_err(|_| Error::InvalidBalancingValue),
+    )?;
 
     // subtract value_balance from current bvk to get final bvk
     value_balance = value_balance.neg();
@@ -181,24 +831,171 @@ fn accept_sapling_final(sighash: &[u8; 32], total: Point, sapling: &Sapling) ->
     )
     .expect("bvk is 32 bytes");
     data_to_be_signed[32..].copy_from_slice(&sighash[..]);
+    transcript.append("binding_verification_key", &data_to_be_signed[..32]);
 
-    // deserialize the binding signature
-    let binding_sig = Signature::read(&sapling.binding_sig[..])
-        .expect("only could fail if length of passed buffer != 64; qed");
+    Ok((binding_verification_key, data_to_be_signed))
+}
 
+#[cfg(feature = "verify-sigs")]
+fn verify_binding_sig<T: Transcript>(
+    binding_verification_key: &PublicKey,
+    data_to_be_signed: &[u8; 64],
+    binding_sig: &Signature,
+    transcript: &mut T,
+) -> Result<(), Error> {
     // check the binding signature
     let is_verification_ok = binding_verification_key.verify(
-        &data_to_be_signed,
-        &binding_sig,
+        data_to_be_signed,
+        binding_sig,
         FixedGenerators::ValueCommitmentRandomness,
     );
-    if !is_verification_ok {
+    let error = Error::BindingSigInvalid;
+    record(
+        transcript,
+        "binding_sig.verified",
+        require(is_verification_ok, error),
+    )
+}
+
+#[cfg(feature = "verify-sigs")]
+fn require(condition: bool, error: Error) -> Result<(), Error> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+/// Reads the randomized key of a spend, which must not be of small order
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn read_randomized_key(policy: &VerificationPolicy, data: &[u8; 32]) -> Result<PublicKey, ()> {
+    if !is_allowed_point_encoding(policy, &data[..]) {
         return Err(());
     }
 
-    Ok(())
+    let randomized_key = PublicKey::read(&data[..]).map_err(|_| ())?;
+    if is_small_order(&randomized_key.point) {
+        return Err(());
+    }
+
+    Ok(randomized_key)
+}
+
+/// Reads a little-endian element of Fr, reducing or rejecting non-canonical encodings
+/// depending on the policy
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn read_fr(policy: &VerificationPolicy, data: &[u8; 32]) -> Result<Fr, ()> {
+    if policy.canonical_field_encodings {
+        return read_canonical_fr(data);
+    }
+
+    let mut repr = read_le_repr(&data[..]);
+    while repr >= <Fr as PrimeField>::Params::MODULUS {
+        repr.sub_noborrow(&<Fr as PrimeField>::Params::MODULUS);
+    }
+
+    Ok(Fr::from_repr(repr))
 }
 
+/// Reads a little-endian element of Fr, rejecting encodings of values not below the modulus
+/// as zcashd does.
+///
+/// This is how anchors and note commitments are read when the policy requires
+/// `canonical_field_encodings`.
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+pub fn read_canonical_fr(data: &[u8; 32]) -> Result<Fr, ()> {
+    let repr = read_le_repr(&data[..]);
+    if repr >= <Fr as PrimeField>::Params::MODULUS {
+        return Err(());
+    }
+
+    Ok(Fr::from_repr(repr))
+}
+
+/// Reads a signature, applying the policy's encoding checks to `R` and `S`
+#[cfg(feature = "verify-sigs")]
+fn read_signature(policy: &VerificationPolicy, sig: &[u8; 64]) -> Result<Signature, ()> {
+    if !is_allowed_point_encoding(policy, &sig[..32]) {
+        return Err(());
+    }
+
+    if policy.canonical_signatures {
+        return read_canonical_signature(sig);
+    }
+
+    Signature::read(&sig[..]).map_err(|_| ())
+}
+
+/// Reads a signature, requiring `R` to be a valid point and `S` a canonical scalar
+#[cfg(feature = "verify-sigs")]
+fn read_canonical_signature(sig: &[u8; 64]) -> Result<Signature, ()> {
+    if read_point(&sig[..32]).is_none() {
+        return Err(());
+    }
+
+    if read_le_repr(&sig[32..]) >= <jubjub::Fr as PrimeField>::Params::MODULUS {
+        return Err(());
+    }
+
+    Signature::read(&sig[..]).map_err(|_| ())
+}
+
+/// Does the policy allow this point encoding?
+#[cfg(feature = "verify-sigs")]
+fn is_allowed_point_encoding(policy: &VerificationPolicy, data: &[u8]) -> bool {
+    !policy.canonical_point_encodings || is_canonical_point_encoding(data)
+}
+
+/// Is this a canonical point encoding as defined by ZIP 216?
+///
+/// `v` must be below the modulus and the sign bit of `u` must be clear when `u` is zero, which
+/// happens only for `v = 1` and `v = -1`.
+#[cfg(feature = "verify-sigs")]
+fn is_canonical_point_encoding(data: &[u8]) -> bool {
+    let mut v = [0u8; 32];
+    v.copy_from_slice(&data[..32]);
+    v[31] &= 0x7f;
+    let sign = data[31] & 0x80 != 0;
+
+    let modulus = <Fr as PrimeField>::Params::MODULUS;
+    let repr = read_le_repr(&v);
+    if repr >= modulus {
+        return false;
+    }
+
+    let one = BigInteger256::from(1);
+    let mut minus_one = modulus;
+    minus_one.sub_noborrow(&one);
+
+    !sign || (repr != one && repr != minus_one)
+}
+
+/// Interprets 32 bytes as a little-endian 256-bit integer
+#[cfg(feature = "verify-sigs")]
+fn read_le_repr(data: &[u8]) -> BigInteger256 {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(data.chunks(8)) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(bytes);
+    }
+
+    BigInteger256(limbs)
+}
+
+#[cfg(all(feature = "verify-proofs", feature = "verify-sigs"))]
+fn read_non_small_order_point(
+    policy: &VerificationPolicy,
+    point_buff: &[u8; 32],
+) -> Result<Point, ()> {
+    if !is_allowed_point_encoding(policy, &point_buff[..]) {
+        return Err(());
+    }
+
+    require_non_small_order_point(point_buff)
+}
+
+#[cfg(feature = "verify-sigs")]
 fn require_non_small_order_point(point_buff: &[u8; 32]) -> Result<Point, ()> {
     match read_point(&point_buff[..]) {
         Some(point) if !is_small_order(&point) => Ok(point),
@@ -207,11 +1004,13 @@ fn require_non_small_order_point(point_buff: &[u8; 32]) -> Result<Point, ()> {
 }
 
 /// Is this a small order point?
+#[cfg(feature = "verify-sigs")]
 fn is_small_order(point: &Point) -> bool {
     point.double().double().double().is_zero()
 }
 
 /// This function computes `value` in the exponent of the value commitment base
+#[cfg(feature = "verify-sigs")]
 fn compute_value_balance(value: i64) -> Result<Point, ()> {
     // Compute the absolute value (failing if -i64::MAX is the value)
     let abs = match value.checked_abs() {
@@ -236,15 +1035,57 @@ fn compute_value_balance(value: i64) -> Result<Point, ()> {
     Ok(value_balance.into())
 }
 
-#[cfg(test)]
+#[cfg(all(
+    test,
+    feature = "verify-sigs",
+    feature = "spend-vk",
+    feature = "output-vk"
+))]
 mod tests {
-    use super::{accept_sapling, Sapling, SaplingOutputDescription, SaplingSpendDescription};
+    use super::{
+        accept_binding_sig, accept_output, accept_sapling, accept_sapling_combined,
+        accept_sapling_combined_cancellable, accept_sapling_combined_with_hooks, accept_sapling_on,
+        accept_sapling_v5, accept_sapling_with_transcript, accept_spend, accept_typed_sapling,
+        is_canonical_point_encoding, nullifier_to_field_elements, output_public_inputs, proof,
+        read_canonical_fr, read_canonical_signature, read_fr, spend_public_inputs, CvSum, Error,
+        Groth16PreparedVerifyingKey, RecordingTranscript, Sapling, SaplingBundleV5,
+        SaplingOutputDescription, SaplingSpendDescription, SaplingSpendDescriptionV5, TypedSapling,
+        VerificationPolicy,
+    };
     use crate::zcash;
     use alloc::vec;
     use hex_literal::hex;
+    use rand_core::{CryptoRng, Error as RandError, RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
 
-    #[test]
-    fn test_lib() {
+    // deterministic weights are fine for tests, the combined checks only demand a marker
+    pub(crate) struct TestRng(XorShiftRng);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.0.fill_bytes(dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RandError> {
+            self.0.try_fill_bytes(dest)
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    pub(crate) fn test_rng() -> TestRng {
+        TestRng(XorShiftRng::seed_from_u64(0))
+    }
+
+    pub(crate) fn mainnet_sapling() -> (Sapling, [u8; 32]) {
         // data comes from tx:
         // https://zcash.blockexplorer.com/tx/bd4fe81c15cfbd125f5ca6fe51fb5ac4ef340e64a36f576a6a09f7528eb2e176
         let test_sapling = Sapling {
@@ -274,10 +1115,429 @@ mod tests {
 
         let sighash = hex!("839321aa5e46473277cc3828564f2a7b60d3fb1264320d6c436e74e7ffc75888");
 
-        let spend_vk = zcash::spend_vk();
-        let output_vk = zcash::output_vk();
+        (test_sapling, sighash)
+    }
+
+    fn prepared_vks() -> (Groth16PreparedVerifyingKey, Groth16PreparedVerifyingKey) {
+        (zcash::spend_vk().into(), zcash::output_vk().into())
+    }
+
+    #[test]
+    fn test_lib() {
+        let (test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+
+        let policy = VerificationPolicy::strict();
+        let _ = accept_sapling(&policy, &spend_vk, &output_vk, &sighash, &test_sapling).unwrap();
+
+        // verification is deterministic, so are the transcripts
+        let mut transcripts = [RecordingTranscript::new(), RecordingTranscript::new()];
+        for transcript in transcripts.iter_mut() {
+            accept_sapling_with_transcript(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                &test_sapling,
+                transcript,
+            )
+            .unwrap();
+        }
+        assert_eq!(transcripts[0], transcripts[1]);
+
+        // proofs are verified last, after the binding signature
+        let expected_end = b"output.zkproof.verified\x01\x00\x00\x00\x01";
+        assert!(transcripts[0].as_bytes().ends_with(expected_end));
+    }
+
+    #[test]
+    fn test_invalid_binding_sig_skips_proofs() {
+        let (mut test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        test_sapling.binding_sig[40] ^= 1;
+
+        let mut transcript = RecordingTranscript::new();
+        let result = accept_sapling_with_transcript(
+            &VerificationPolicy::strict(),
+            &spend_vk,
+            &output_vk,
+            &sighash,
+            &test_sapling,
+            &mut transcript,
+        );
+        assert_eq!(result, Err(Error::BindingSigInvalid));
+
+        let expected_end = b"binding_sig.verified\x01\x00\x00\x00\x00";
+        assert!(transcript.as_bytes().ends_with(expected_end));
+    }
+
+    #[test]
+    fn test_accept_sapling_v5() {
+        let (test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        let policy = VerificationPolicy::strict();
+
+        let spend = &test_sapling.spends[0];
+        let mut bundle = SaplingBundleV5 {
+            balancing_value: test_sapling.balancing_value,
+            anchor: spend.anchor,
+            spends: vec![SaplingSpendDescriptionV5 {
+                value_commitment: spend.value_commitment,
+                nullifier: spend.nullifier,
+                randomized_key: spend.randomized_key,
+                zkproof: spend.zkproof,
+                spend_auth_sig: spend.spend_auth_sig,
+            }],
+            outputs: test_sapling.outputs.clone(),
+            binding_sig: test_sapling.binding_sig,
+        };
+        assert_eq!(bundle.to_sapling(), test_sapling);
+        assert_eq!(
+            accept_sapling_v5(&policy, &spend_vk, &output_vk, &sighash, &bundle),
+            Ok(())
+        );
+
+        bundle.anchor[0] ^= 1;
+        assert_eq!(
+            accept_sapling_v5(&policy, &spend_vk, &output_vk, &sighash, &bundle),
+            Err(Error::SpendProofInvalid { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_accept_binding_sig_with_merged_sums() {
+        let (test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        let policy = VerificationPolicy::strict();
+
+        // accumulate spends and outputs separately, as independent validators would
+        let mut spends = CvSum::new();
+        for (index, spend) in test_sapling.spends.iter().enumerate() {
+            accept_spend(&policy, &spend_vk, &sighash, &mut spends, spend, index).unwrap();
+        }
+        let mut outputs = CvSum::new();
+        for (index, output) in test_sapling.outputs.iter().enumerate() {
+            accept_output(&policy, &output_vk, &mut outputs, output, index).unwrap();
+        }
+
+        let outputs = CvSum::from_bytes(&outputs.to_bytes()).unwrap();
+        spends.merge(&outputs);
+        assert_eq!(
+            accept_binding_sig(&policy, &sighash, spends, &test_sapling),
+            Ok(())
+        );
+        assert_eq!(
+            accept_binding_sig(&policy, &sighash, CvSum::new(), &test_sapling),
+            Err(Error::BindingSigInvalid)
+        );
+    }
+
+    #[test]
+    fn test_accept_sapling_on() {
+        let (test_sapling, sighash) = mainnet_sapling();
+        let network = zcash::ZcashMainnet::new();
+        let policy = VerificationPolicy::strict();
+
+        assert_eq!(
+            accept_sapling_on(&network, &policy, &sighash, &test_sapling),
+            Ok(())
+        );
+        assert_eq!(
+            accept_sapling_on(&network, &policy, &[0u8; 32], &test_sapling),
+            Err(Error::SpendAuthSigInvalid)
+        );
+    }
+
+    #[test]
+    fn test_accept_typed_sapling() {
+        let (mut test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        let policy = VerificationPolicy::strict();
+
+        let typed = test_sapling.parse(&policy).unwrap();
+        assert_eq!(
+            accept_typed_sapling(&policy, &spend_vk, &output_vk, &sighash, &typed),
+            Ok(())
+        );
+        assert_eq!(
+            accept_typed_sapling(&policy, &spend_vk, &output_vk, &[0u8; 32], &typed),
+            Err(Error::SpendAuthSigInvalid)
+        );
+
+        // keys are rejected on construction, y = 2 is not on the curve
+        test_sapling.spends[0].randomized_key = [0u8; 32];
+        test_sapling.spends[0].randomized_key[0] = 2;
+        assert_eq!(
+            TypedSapling::new(&policy, &test_sapling).err(),
+            Some(Error::InvalidRandomizedKey)
+        );
+        assert_eq!(
+            test_sapling.spends[0].parse(&policy).err(),
+            Some(Error::InvalidRandomizedKey)
+        );
+    }
+
+    #[test]
+    fn test_accept_sapling_combined() {
+        let (mut test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        let policy = VerificationPolicy::strict();
+        let mut rng = test_rng();
+
+        assert_eq!(
+            accept_sapling_combined(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                &test_sapling,
+                &mut rng,
+            ),
+            Ok(())
+        );
+
+        // swapping proofs between the descriptions must be detected
+        let spend_proof = test_sapling.spends[0].zkproof;
+        test_sapling.spends[0].zkproof = test_sapling.outputs[0].zkproof;
+        test_sapling.outputs[0].zkproof = spend_proof;
+        assert_eq!(
+            accept_sapling_combined(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                &test_sapling,
+                &mut rng,
+            ),
+            Err(Error::CombinedProofsInvalid)
+        );
+    }
+
+    #[test]
+    fn test_nullifier_to_field_elements() {
+        use algebra::{bls12_381::Fr, prelude::Zero, BigInteger256, PrimeField};
+
+        assert_eq!(nullifier_to_field_elements(&[0u8; 32]), [Fr::zero(); 2]);
+
+        let max = u64::max_value();
+        assert_eq!(
+            nullifier_to_field_elements(&[0xff; 32]),
+            [
+                Fr::from_repr(BigInteger256([max, max, max, max >> 2])),
+                Fr::from(3u64)
+            ]
+        );
+
+        let mut nullifier = [0u8; 32];
+        nullifier[0] = 1;
+        nullifier[31] = 0x40;
+        assert_eq!(
+            nullifier_to_field_elements(&nullifier),
+            [Fr::from(1u64), Fr::from(1u64)]
+        );
+    }
+
+    #[test]
+    fn test_public_inputs() {
+        let (sapling, _) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        let policy = VerificationPolicy::strict();
+
+        let spend = &sapling.spends[0];
+        let public_input = spend_public_inputs(&policy, spend).unwrap();
+        let zkproof = proof::read_proof(spend.zkproof).unwrap();
+        assert_eq!(
+            proof::verify_proof(&spend_vk, &zkproof, &public_input),
+            Ok(true)
+        );
+
+        let output = &sapling.outputs[0];
+        let public_input = output_public_inputs(&policy, output).unwrap();
+        let zkproof = proof::read_proof(output.zkproof).unwrap();
+        assert_eq!(
+            proof::verify_proof(&output_vk, &zkproof, &public_input),
+            Ok(true)
+        );
+
+        let mut invalid = output.clone();
+        invalid.ephemeral_key = [0u8; 32];
+        assert_eq!(
+            output_public_inputs(&policy, &invalid),
+            Err(Error::InvalidEphemeralKey)
+        );
+    }
+
+    #[test]
+    fn test_accept_sapling_combined_cancellable() {
+        use super::BatchError;
+        use core::sync::atomic::AtomicBool;
+
+        let (test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        let policy = VerificationPolicy::strict();
+        let mut rng = test_rng();
+
+        let cancel = AtomicBool::new(false);
+        assert_eq!(
+            accept_sapling_combined_cancellable(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                &test_sapling,
+                &mut rng,
+                &cancel,
+            ),
+            Ok(())
+        );
+
+        let cancel = AtomicBool::new(true);
+        assert_eq!(
+            accept_sapling_combined_cancellable(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                &test_sapling,
+                &mut rng,
+                &cancel,
+            ),
+            Err(BatchError::Cancelled)
+        );
+
+        assert_eq!(
+            accept_sapling_combined_cancellable(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &[0u8; 32],
+                &test_sapling,
+                &mut rng,
+                &(),
+            ),
+            Err(BatchError::Invalid(Error::SpendAuthSigInvalid))
+        );
+    }
+
+    #[test]
+    fn test_accept_sapling_combined_with_hooks() {
+        use super::ProgressStage;
+        use alloc::vec::Vec;
+
+        let (test_sapling, sighash) = mainnet_sapling();
+        let (spend_vk, output_vk) = prepared_vks();
+        let policy = VerificationPolicy::strict();
+        let mut rng = test_rng();
+
+        let mut reports = Vec::new();
+        let mut progress = |stage, completed, total| reports.push((stage, completed, total));
+        assert_eq!(
+            accept_sapling_combined_with_hooks(
+                &policy,
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                &test_sapling,
+                &mut rng,
+                &(),
+                &mut progress,
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            reports,
+            vec![
+                (ProgressStage::Descriptions, 1, 2),
+                (ProgressStage::Descriptions, 2, 2),
+                (ProgressStage::Proofs, 2, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_canonical_fr() {
+        use algebra::{
+            bls12_381::Fr,
+            prelude::{One, Zero},
+        };
+
+        // r in little-endian
+        let modulus = hex!("01000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73");
+
+        let mut below = modulus;
+        below[0] -= 1;
+        assert_eq!(read_canonical_fr(&below), Ok(-Fr::one()));
+        assert_eq!(read_canonical_fr(&[0u8; 32]), Ok(Fr::zero()));
+
+        let mut above = modulus;
+        above[0] += 1;
+        assert_eq!(read_canonical_fr(&modulus), Err(()));
+        assert_eq!(read_canonical_fr(&above), Err(()));
+        assert_eq!(read_canonical_fr(&[0xff; 32]), Err(()));
+    }
+
+    #[test]
+    fn test_read_canonical_signature() {
+        let sig = hex!("9cc80dc53d6b18d42033ec2c327170e2811fe8ec00feadeb1033eb48ab24a6dce2480ad428be57c4619466fc3181ece69b914fed30566ff853250ef19ef73706");
+        assert!(read_canonical_signature(&sig).is_ok());
+
+        // jubjub r in little-endian
+        let modulus = hex!("b72cf7d65e0e97d08210c8cc932068a6003b3401013b6706a9af3365eab47d0e");
+
+        let mut below = sig;
+        below[32..].copy_from_slice(&modulus);
+        below[32] -= 1;
+        assert!(read_canonical_signature(&below).is_ok());
+
+        let mut at = sig;
+        at[32..].copy_from_slice(&modulus);
+        assert!(read_canonical_signature(&at).is_err());
+
+        let mut above = sig;
+        above[32..].copy_from_slice(&[0xff; 32]);
+        assert!(read_canonical_signature(&above).is_err());
+
+        // there is no point with y = 2
+        let mut invalid_r = sig;
+        invalid_r[..32].copy_from_slice(&[0u8; 32]);
+        invalid_r[0] = 2;
+        assert!(read_canonical_signature(&invalid_r).is_err());
+    }
+
+    #[test]
+    fn test_read_fr_lenient() {
+        use algebra::{bls12_381::Fr, prelude::Zero};
+
+        let modulus = hex!("01000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73");
+        let mut policy = VerificationPolicy::strict();
+        assert_eq!(read_fr(&policy, &modulus), Err(()));
+
+        policy.canonical_field_encodings = false;
+        assert_eq!(read_fr(&policy, &modulus), Ok(Fr::zero()));
+        assert!(read_fr(&policy, &[0xff; 32]).is_ok());
+    }
+
+    #[test]
+    fn test_is_canonical_point_encoding() {
+        let modulus = hex!("01000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73");
+        let mut minus_one = modulus;
+        minus_one[0] -= 1;
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        let mut two = [0u8; 32];
+        two[0] = 2;
+
+        assert!(is_canonical_point_encoding(&one));
+        assert!(is_canonical_point_encoding(&minus_one));
+        assert!(!is_canonical_point_encoding(&modulus));
+
+        // u is zero, so its sign bit must not be set
+        one[31] |= 0x80;
+        minus_one[31] |= 0x80;
+        assert!(!is_canonical_point_encoding(&one));
+        assert!(!is_canonical_point_encoding(&minus_one));
 
-        let _ =
-            accept_sapling(&spend_vk.into(), &output_vk.into(), &sighash, &test_sapling).unwrap();
+        two[31] |= 0x80;
+        assert!(is_canonical_point_encoding(&two));
     }
 }
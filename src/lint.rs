@@ -0,0 +1,71 @@
+use crate::Sapling;
+use alloc::{collections::BTreeMap, vec::Vec};
+
+/// Policy warning about a bundle that is valid by consensus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    /// The output at index `output` reuses the ephemeral key of the earlier output `first`.
+    EphemeralKeyReuse { output: usize, first: usize },
+    /// The ephemeral key of the output at this index was seen recently by the caller.
+    RecentEphemeralKey(usize),
+}
+
+/// Flags ephemeral keys that are reused within `sapling` or for which `is_recent` returns
+/// `true`.
+///
+/// A builder derives a fresh ephemeral key per output, so reuse indicates a broken builder
+/// and links the outputs. These warnings are not consensus failures.
+pub fn lint_ephemeral_keys<F>(sapling: &Sapling, is_recent: F) -> Vec<Lint>
+where
+    F: Fn(&[u8; 32]) -> bool,
+{
+    let mut lints = Vec::new();
+    let mut seen = BTreeMap::new();
+    for (index, output) in sapling.outputs.iter().enumerate() {
+        if let Some(&first) = seen.get(&output.ephemeral_key) {
+            lints.push(Lint::EphemeralKeyReuse {
+                output: index,
+                first,
+            });
+        } else {
+            seen.insert(output.ephemeral_key, index);
+        }
+
+        if is_recent(&output.ephemeral_key) {
+            lints.push(Lint::RecentEphemeralKey(index));
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint_ephemeral_keys, Lint};
+    use crate::{Sapling, SaplingOutputDescription};
+    use alloc::vec;
+
+    #[test]
+    fn test_lint_ephemeral_keys() {
+        let mut sapling = Sapling {
+            outputs: vec![SaplingOutputDescription::default(); 4],
+            ..Sapling::default()
+        };
+        for (i, output) in sapling.outputs.iter_mut().enumerate() {
+            output.ephemeral_key = [i as u8; 32];
+        }
+        assert!(lint_ephemeral_keys(&sapling, |_| false).is_empty());
+
+        sapling.outputs[3].ephemeral_key = [1; 32];
+        let recent = [[2u8; 32]];
+        assert_eq!(
+            lint_ephemeral_keys(&sapling, |key| recent.contains(key)),
+            vec![
+                Lint::RecentEphemeralKey(2),
+                Lint::EphemeralKeyReuse {
+                    output: 3,
+                    first: 1
+                },
+            ]
+        );
+    }
+}
@@ -1,39 +1,407 @@
 use algebra::{
+    bls12_381::Fr,
     fields::{Field, FpParameters, PrimeField},
     prelude::{One, Zero},
-    ModelParameters,
+    BigInteger, BigInteger256, ModelParameters,
 };
 use alloc::{vec, vec::Vec};
 use core::ops::AddAssign;
 
 pub fn bytes_to_bits_le(bytes: &[u8]) -> Vec<bool> {
+    bits_le(bytes).collect()
+}
+
+/// Iterates over the bits of `bytes`, least significant bit of each byte first.
+pub fn bits_le(bytes: &[u8]) -> impl Iterator<Item = bool> + '_ {
     bytes
         .iter()
         .flat_map(|&v| (0..8).map(move |i| (v >> i) & 1 == 1))
+}
+
+/// Inverse of `bytes_to_bits_le`. The last byte is padded with zero bits.
+pub fn bits_le_to_bytes(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|bits| {
+            bits.iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, bit)| byte | ((*bit as u8) << i))
+        })
         .collect()
 }
 
-pub fn compute_multipacking<E>(bits: &[bool]) -> Vec<E::ScalarField>
+pub fn compute_multipacking<F: PrimeField>(bits: &[bool]) -> Vec<F> {
+    pack_bits(bits.iter().cloned())
+}
+
+/// Packs the bits of `bytes` like `compute_multipacking(&bytes_to_bits_le(bytes))`, without
+/// materializing the bits.
+pub fn pack_bytes<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    pack_bits(bits_le(bytes))
+}
+
+/// Packs a nullifier into the two scalars used as spend circuit inputs, without allocating.
+///
+/// Equivalent to `pack_bytes::<Fr>(nullifier)`: the first element holds the low 254 bits
+/// (`CAPACITY`), the second one the remaining 2 bits.
+pub fn pack_nullifier(nullifier: &[u8; 32]) -> [Fr; 2] {
+    let mut low = [0u64; 4];
+    for (limb, chunk) in low.iter_mut().zip(nullifier.chunks(8)) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(bytes);
+    }
+    low[3] &= u64::max_value() >> 2;
+
+    let high = [u64::from(nullifier[31] >> 6), 0, 0, 0];
+
+    [
+        Fr::from_repr(BigInteger256(low)),
+        Fr::from_repr(BigInteger256(high)),
+    ]
+}
+
+/// Packs a stream of bits into field elements, `CAPACITY` bits per element.
+pub fn pack_bits<F, I>(bits: I) -> Vec<F>
+where
+    F: PrimeField,
+    I: IntoIterator<Item = bool>,
+{
+    pack_bits_chunked(bits, F::Params::CAPACITY as usize)
+}
+
+/// Packs a stream of bits into field elements, `chunk_size` bits per element.
+///
+/// Fails unless `0 < chunk_size <= CAPACITY`, so that every chunk fits in the field.
+pub fn pack_bits_with_chunk_size<F, I>(bits: I, chunk_size: usize) -> Result<Vec<F>, ()>
+where
+    F: PrimeField,
+    I: IntoIterator<Item = bool>,
+{
+    check_chunk_size::<F>(chunk_size)?;
+    Ok(pack_bits_chunked(bits, chunk_size))
+}
+
+fn check_chunk_size<F: PrimeField>(chunk_size: usize) -> Result<(), ()> {
+    if chunk_size == 0 || chunk_size > F::Params::CAPACITY as usize {
+        return Err(());
+    }
+
+    Ok(())
+}
+
+fn pack_bits_chunked<F, I>(bits: I, chunk_size: usize) -> Vec<F>
 where
-    E: ModelParameters,
-    E::ScalarField: One + Zero + Zero,
+    F: PrimeField,
+    I: IntoIterator<Item = bool>,
 {
     let mut result = vec![];
 
-    for bits in bits.chunks(<E::ScalarField as PrimeField>::Params::CAPACITY as usize) {
-        let mut cur = E::ScalarField::zero();
-        let mut coeff = E::ScalarField::one();
+    let mut cur = F::zero();
+    let mut coeff = F::one();
+    let mut len = 0;
 
-        for bit in bits {
-            if *bit {
-                cur.add_assign(&coeff);
-            }
+    for bit in bits {
+        if bit {
+            cur.add_assign(&coeff);
+        }
 
-            coeff.double_in_place();
+        coeff.double_in_place();
+        len += 1;
+
+        if len == chunk_size {
+            result.push(cur);
+            cur = F::zero();
+            coeff = F::one();
+            len = 0;
         }
+    }
 
+    if len != 0 {
         result.push(cur);
     }
 
     result
 }
+
+/// Packs bits into elements of the scalar field of the curve `E`.
+#[deprecated(note = "use compute_multipacking::<E::ScalarField> instead")]
+pub fn compute_curve_multipacking<E: ModelParameters>(bits: &[bool]) -> Vec<E::ScalarField> {
+    compute_multipacking(bits)
+}
+
+/// Inverse of `compute_multipacking`, recovering `num_bits` bits from the packed elements.
+///
+/// Fails if the number of elements doesn't match `num_bits` or if any element has bits set
+/// above the chunk it encodes.
+pub fn compute_unpacking<F: PrimeField>(elements: &[F], num_bits: usize) -> Result<Vec<bool>, ()> {
+    unpack_chunked(elements, num_bits, F::Params::CAPACITY as usize)
+}
+
+/// Inverse of `pack_bits_with_chunk_size`.
+pub fn compute_unpacking_with_chunk_size<F: PrimeField>(
+    elements: &[F],
+    num_bits: usize,
+    chunk_size: usize,
+) -> Result<Vec<bool>, ()> {
+    check_chunk_size::<F>(chunk_size)?;
+    unpack_chunked(elements, num_bits, chunk_size)
+}
+
+fn unpack_chunked<F: PrimeField>(
+    elements: &[F],
+    num_bits: usize,
+    chunk_size: usize,
+) -> Result<Vec<bool>, ()> {
+    if elements.len() != (num_bits + chunk_size - 1) / chunk_size {
+        return Err(());
+    }
+
+    let mut result = Vec::with_capacity(num_bits);
+
+    for (i, element) in elements.iter().enumerate() {
+        let chunk_len = core::cmp::min(chunk_size, num_bits - i * chunk_size);
+        let repr = element.into_repr();
+
+        if (chunk_len..repr.as_ref().len() * 64).any(|bit| repr.get_bit(bit)) {
+            return Err(());
+        }
+
+        result.extend((0..chunk_len).map(|bit| repr.get_bit(bit)));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bits_le_to_bytes, bytes_to_bits_le, compute_multipacking, compute_unpacking,
+        compute_unpacking_with_chunk_size, pack_bits, pack_bits_with_chunk_size, pack_bytes,
+        pack_nullifier,
+    };
+    use algebra::{
+        bls12_381::Fr,
+        fields::{Field, PrimeField},
+        prelude::One,
+        BigInteger256,
+    };
+    use alloc::{vec, vec::Vec};
+    use hex_literal::hex;
+
+    #[test]
+    fn test_bits_roundtrip() {
+        let bytes = hex!("ee5bc95a9af453bb9cc7e2c544aa29efa20011a65b624998369c849aa8f0bc83");
+        let bits = bytes_to_bits_le(&bytes);
+        assert_eq!(bits.len(), 256);
+        assert_eq!(bits_le_to_bytes(&bits), bytes.to_vec());
+
+        // the last byte is zero padded
+        assert_eq!(bits_le_to_bytes(&[true, false, true]), vec![0b101]);
+    }
+
+    #[test]
+    fn test_unpacking() {
+        let bytes = hex!("ee5bc95a9af453bb9cc7e2c544aa29efa20011a65b624998369c849aa8f0bc83");
+        let bits = bytes_to_bits_le(&bytes);
+        let packed = compute_multipacking::<Fr>(&bits);
+        assert_eq!(packed.len(), 2);
+
+        let unpacked = compute_unpacking::<Fr>(&packed, 256).unwrap();
+        assert_eq!(bits_le_to_bytes(&unpacked), bytes.to_vec());
+
+        // wrong number of elements
+        assert!(compute_unpacking::<Fr>(&packed[..1], 256).is_err());
+        assert!(compute_unpacking::<Fr>(&packed, 254).is_err());
+
+        // the second element may only use 2 bits
+        let mut invalid = packed.clone();
+        invalid[1] = Fr::one().double().double();
+        assert!(compute_unpacking::<Fr>(&invalid, 256).is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_compute_curve_multipacking() {
+        use super::compute_curve_multipacking;
+        use algebra::bls12_381::g1::Parameters;
+
+        let bits = bytes_to_bits_le(&[0xee; 40]);
+        assert_eq!(
+            compute_curve_multipacking::<Parameters>(&bits),
+            compute_multipacking::<Fr>(&bits)
+        );
+    }
+
+    #[test]
+    fn test_pack_bytes() {
+        // bellman vector, see `test_bellman_vectors`
+        let bytes = hex!("ee5bc95a9af453bb9cc7e2c544aa29efa20011a65b624998369c849aa8f0bc83");
+        let expected = vec![
+            fr([
+                0xbb53f49a5ac95bee,
+                0xef29aa44c5e2c79c,
+                0x9849625ba61100a2,
+                0x03bcf0a89a849c36,
+            ]),
+            fr([2, 0, 0, 0]),
+        ];
+        assert_eq!(pack_bytes::<Fr>(&bytes), expected);
+        assert_eq!(pack_bits::<Fr, _>(bytes_to_bits_le(&bytes)), expected);
+
+        // chunk boundaries, every chunk of 254 set bits is 2^254 - 1
+        let full = fr([
+            0xffffffffffffffff,
+            0xffffffffffffffff,
+            0xffffffffffffffff,
+            0x3fffffffffffffff,
+        ]);
+        let vectors = vec![
+            (0, vec![]),
+            (1, vec![fr([1, 0, 0, 0])]),
+            (
+                253,
+                vec![fr([
+                    0xffffffffffffffff,
+                    0xffffffffffffffff,
+                    0xffffffffffffffff,
+                    0x1fffffffffffffff,
+                ])],
+            ),
+            (254, vec![full]),
+            (255, vec![full, fr([1, 0, 0, 0])]),
+            (508, vec![full, full]),
+            (510, vec![full, full, fr([3, 0, 0, 0])]),
+        ];
+
+        for (len, expected) in vectors {
+            assert_eq!(pack_bits::<Fr, _>((0..len).map(|_| true)), expected);
+        }
+
+        assert!(pack_bytes::<Fr>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_pack_nullifier() {
+        let nullifiers = [
+            hex!("ee5bc95a9af453bb9cc7e2c544aa29efa20011a65b624998369c849aa8f0bc83"),
+            [0u8; 32],
+            [0xff; 32],
+        ];
+
+        for nullifier in nullifiers.iter() {
+            assert_eq!(
+                pack_nullifier(nullifier).to_vec(),
+                pack_bytes::<Fr>(nullifier)
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunk_size() {
+        let bytes = hex!("ee5bc95a9af453bb9cc7e2c544aa29efa20011a65b624998369c849aa8f0bc83");
+        let bits = bytes_to_bits_le(&bytes);
+
+        // CAPACITY behaves like the default packing
+        let packed = pack_bits_with_chunk_size::<Fr, _>(bits.clone(), 254).unwrap();
+        assert_eq!(packed, compute_multipacking::<Fr>(&bits));
+
+        for chunk_size in &[1, 8, 64, 253] {
+            let packed = pack_bits_with_chunk_size::<Fr, _>(bits.clone(), *chunk_size).unwrap();
+            assert_eq!(packed.len(), (256 + chunk_size - 1) / chunk_size);
+
+            let unpacked =
+                compute_unpacking_with_chunk_size::<Fr>(&packed, 256, *chunk_size).unwrap();
+            assert_eq!(unpacked, bits);
+        }
+
+        // the chunk has to fit in the field
+        assert!(pack_bits_with_chunk_size::<Fr, _>(bits.clone(), 0).is_err());
+        assert!(pack_bits_with_chunk_size::<Fr, _>(bits.clone(), 255).is_err());
+        assert!(compute_unpacking_with_chunk_size::<Fr>(&[], 0, 255).is_err());
+    }
+
+    fn fr(limbs: [u64; 4]) -> Fr {
+        Fr::from_repr(BigInteger256(limbs))
+    }
+
+    // Generated with bellman 0.14, `gadgets::multipack::bytes_to_bits_le` followed by
+    // `compute_multipacking` over `bls12_381::Scalar` 0.8, as limbs of `Scalar::to_bytes`.
+    #[test]
+    fn test_bellman_vectors() {
+        let vectors: Vec<(Vec<u8>, Vec<Fr>)> = vec![
+            (vec![], vec![]),
+            (vec![0x01], vec![fr([1, 0, 0, 0])]),
+            (
+                hex!("ee5bc95a9af453bb9cc7e2c544aa29efa20011a65b624998369c849aa8f0bc83").to_vec(),
+                vec![
+                    fr([
+                        0xbb53f49a5ac95bee,
+                        0xef29aa44c5e2c79c,
+                        0x9849625ba61100a2,
+                        0x03bcf0a89a849c36,
+                    ]),
+                    fr([2, 0, 0, 0]),
+                ],
+            ),
+            (vec![0x00; 32], vec![fr([0, 0, 0, 0]), fr([0, 0, 0, 0])]),
+            (
+                vec![0xff; 32],
+                vec![
+                    fr([
+                        0xffffffffffffffff,
+                        0xffffffffffffffff,
+                        0xffffffffffffffff,
+                        0x3fffffffffffffff,
+                    ]),
+                    fr([3, 0, 0, 0]),
+                ],
+            ),
+            (
+                vec![0xff; 33],
+                vec![
+                    fr([
+                        0xffffffffffffffff,
+                        0xffffffffffffffff,
+                        0xffffffffffffffff,
+                        0x3fffffffffffffff,
+                    ]),
+                    fr([0x3ff, 0, 0, 0]),
+                ],
+            ),
+            (
+                hex!("839321aa5e46473277cc3828564f2a7b60d3fb1264320d6c436e74e7ffc75888ee5bc95a9af453bb9cc7e2c544aa29efa20011a65b624998369c849aa8f0bc83").to_vec(),
+                vec![
+                    fr([
+                        0x3247465eaa219383,
+                        0x7b2a4f562838cc77,
+                        0x6c0d326412fbd360,
+                        0x0858c7ffe7746e43,
+                    ]),
+                    fr([
+                        0xed4fd2696b256fba,
+                        0xbca6a913178b1e72,
+                        0x6125896e9844028b,
+                        0x0ef3c2a26a1270da,
+                    ]),
+                    fr([8, 0, 0, 0]),
+                ],
+            ),
+        ];
+
+        for (bytes, expected) in vectors {
+            let bits = bytes_to_bits_le(&bytes);
+            assert_eq!(compute_multipacking::<Fr>(&bits), expected);
+            assert_eq!(pack_bytes::<Fr>(&bytes), expected);
+            assert_eq!(
+                compute_unpacking::<Fr>(&expected, bits.len()).unwrap(),
+                bits
+            );
+
+            if bytes.len() == 32 {
+                let mut nullifier = [0u8; 32];
+                nullifier.copy_from_slice(&bytes);
+                assert_eq!(pack_nullifier(&nullifier).to_vec(), expected);
+            }
+        }
+    }
+}
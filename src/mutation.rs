@@ -0,0 +1,134 @@
+//! Systematically invalid bundles for negative tests.
+//!
+//! Every mutation breaks exactly one consensus rule of an otherwise valid bundle. The
+//! verifier rejects the result with `MutationKind::expected_error`, at the check named by
+//! `MutationKind::failing_check`, which is the label recorded into the transcript.
+
+use crate::{Error, Sapling};
+
+/// A single rule violation applied by `mutate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationKind {
+    /// Toggles the sign flag of `A` in the first spend proof, negating `A`. The proof still
+    /// decodes but does not verify.
+    FlipProofSignBit,
+    /// Replaces the anchor of the first spend with zero.
+    WrongAnchor,
+    /// Replaces the value commitment of the first spend with the identity.
+    SmallOrderCv,
+    /// Replaces the signature of the first spend with the binding signature, which is well
+    /// formed but signs a different key.
+    WrongSpendAuthSig,
+    /// Zeroes the binding signature.
+    ZeroBindingSig,
+}
+
+impl MutationKind {
+    /// Every mutation kind.
+    pub const ALL: [MutationKind; 5] = [
+        MutationKind::FlipProofSignBit,
+        MutationKind::WrongAnchor,
+        MutationKind::SmallOrderCv,
+        MutationKind::WrongSpendAuthSig,
+        MutationKind::ZeroBindingSig,
+    ];
+
+    /// Transcript label of the check that rejects the mutated bundle.
+    pub fn failing_check(&self) -> &'static str {
+        match self {
+            MutationKind::FlipProofSignBit | MutationKind::WrongAnchor => "spend.zkproof.verified",
+            MutationKind::SmallOrderCv => "spend.value_commitment.valid",
+            MutationKind::WrongSpendAuthSig => "spend.spend_auth_sig.verified",
+            MutationKind::ZeroBindingSig => "binding_sig.verified",
+        }
+    }
+
+    /// Error returned by `accept_sapling` for the mutated bundle.
+    pub fn expected_error(&self) -> Error {
+        match self {
+            MutationKind::FlipProofSignBit | MutationKind::WrongAnchor => {
+                Error::SpendProofInvalid { index: 0 }
+            }
+            MutationKind::SmallOrderCv => Error::InvalidSpendValueCommitment,
+            MutationKind::WrongSpendAuthSig => Error::SpendAuthSigInvalid,
+            MutationKind::ZeroBindingSig => Error::BindingSigInvalid,
+        }
+    }
+}
+
+/// Returns a copy of `sapling` violating the rule described by `kind`.
+///
+/// Returns `None` if `kind` needs a spend and `sapling` has none.
+pub fn mutate(sapling: &Sapling, kind: MutationKind) -> Option<Sapling> {
+    let mut result = sapling.clone();
+    let binding_sig = result.binding_sig;
+    match kind {
+        MutationKind::FlipProofSignBit => result.spends.first_mut()?.zkproof[0] ^= 0x20,
+        MutationKind::WrongAnchor => result.spends.first_mut()?.anchor = [0u8; 32],
+        MutationKind::SmallOrderCv => {
+            let mut identity = [0u8; 32];
+            identity[0] = 1;
+            result.spends.first_mut()?.value_commitment = identity;
+        }
+        MutationKind::WrongSpendAuthSig => result.spends.first_mut()?.spend_auth_sig = binding_sig,
+        MutationKind::ZeroBindingSig => result.binding_sig = [0u8; 64],
+    }
+    Some(result)
+}
+
+#[cfg(all(
+    test,
+    feature = "verify-sigs",
+    feature = "spend-vk",
+    feature = "output-vk"
+))]
+mod tests {
+    use super::{mutate, MutationKind};
+    use crate::{
+        accept_sapling_with_transcript, tests::mainnet_sapling, zcash, Groth16PreparedVerifyingKey,
+        RecordingTranscript, VerificationPolicy,
+    };
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_mutations_fail_at_expected_check() {
+        let (sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+
+        for kind in MutationKind::ALL.iter() {
+            let mut transcript = RecordingTranscript::new();
+            let result = accept_sapling_with_transcript(
+                &VerificationPolicy::strict(),
+                &spend_vk,
+                &output_vk,
+                &sighash,
+                &mutate(&sapling, *kind).unwrap(),
+                &mut transcript,
+            );
+            assert_eq!(result, Err(kind.expected_error()), "{:?}", kind);
+
+            // the failing check is the last record and is a failure
+            let label = kind.failing_check();
+            let mut expected_end = Vec::new();
+            expected_end.extend_from_slice(label.as_bytes());
+            expected_end.extend_from_slice(&[1, 0, 0, 0, 0]);
+            assert!(transcript.as_bytes().ends_with(&expected_end), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_mutate_without_spends() {
+        let (mut sapling, _) = mainnet_sapling();
+        sapling.spends.clear();
+
+        for kind in MutationKind::ALL.iter() {
+            let mutated = mutate(&sapling, *kind);
+            if *kind == MutationKind::ZeroBindingSig {
+                assert_eq!(mutated.unwrap().binding_sig[..], [0u8; 64][..]);
+            } else {
+                assert_eq!(mutated, None, "{:?}", kind);
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::Groth16PreparedVerifyingKey;
+
+/// Verifying keys of a chain using Sapling, so that forks and test networks with their own
+/// trusted setup can reuse the verifier.
+///
+/// `zcash::ZcashMainnet` provides the Zcash keys. Only the keys vary between networks: the
+/// value commitment bases used by `compute_value_balance` and the binding signature, and the
+/// spend authorization generator, are the Zcash ones, since `zexe-redjubjub` only verifies
+/// against its own `FixedGenerators`.
+pub trait SaplingNetwork {
+    /// Prepared verifying key of the spend circuit.
+    fn spend_vk(&self) -> &Groth16PreparedVerifyingKey;
+
+    /// Prepared verifying key of the output circuit.
+    fn output_vk(&self) -> &Groth16PreparedVerifyingKey;
+}
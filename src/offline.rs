@@ -0,0 +1,439 @@
+//! Payloads exchanged with an offline signer.
+//!
+//! The host sends a `SigningRequest` with everything needed to produce the spend authorization
+//! signatures of a bundle and gets back a `SigningResponse`. Both encode as
+//! `version || body`, with counts and lengths as little-endian `u32`.
+
+use crate::{
+    read_le_repr, read_randomized_key, read_signature, require, Error, Point, Sapling,
+    VerificationPolicy,
+};
+use algebra::{
+    fields::{FpParameters, PrimeField},
+    jubjub,
+    prelude::Group,
+};
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str};
+use zexe_redjubjub::{read_point, FixedGenerators};
+
+/// Current version of both payloads.
+pub const PAYLOAD_VERSION: u8 = 2;
+
+/// Reason for rejecting a payload or the signatures it carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningPayloadError {
+    /// The payload has an unknown version.
+    UnsupportedVersion(u8),
+    /// The payload is truncated or has trailing bytes.
+    InvalidLength,
+    /// The context is not valid UTF-8.
+    InvalidContext,
+    /// The number of signatures does not match the number of spends.
+    CountMismatch,
+    /// The randomized key of the spend at this index is not `ak + [alpha] G`.
+    InconsistentRandomizer(usize),
+    /// The randomized key of the spend at this index differs from the requested one.
+    KeyMismatch(usize),
+    /// The signature at this index is malformed or does not verify.
+    InvalidSignature(usize),
+}
+
+/// What the signer needs to authorize one spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendAuthorization {
+    /// `rk` of the spend, the key the signature is checked against.
+    pub randomized_key: [u8; 32],
+    /// Spend validating key. The signer only signs if it holds the matching `ask`.
+    pub ak: [u8; 32],
+    /// Randomizer of the spend, `rk = ak + [alpha] G`. The signer signs with `ask + alpha`.
+    pub alpha: [u8; 32],
+}
+
+impl SpendAuthorization {
+    /// Is `randomized_key` the randomization of `ak` by `alpha`?
+    fn is_consistent(&self) -> bool {
+        let ak = match read_point(&self.ak[..]) {
+            Some(ak) => ak,
+            None => return false,
+        };
+
+        let repr = read_le_repr(&self.alpha);
+        if repr >= <jubjub::Fr as PrimeField>::Params::MODULUS {
+            return false;
+        }
+
+        let randomizer: Point = FixedGenerators::SpendingKeyGenerator
+            .point()
+            .mul(&jubjub::Fr::from_repr(repr))
+            .into();
+        read_point(&self.randomized_key[..]) == Some(ak + &randomizer)
+    }
+}
+
+/// Everything an offline signer needs: the sighash, what is being signed, and the keys and
+/// randomizer of every spend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningRequest {
+    pub sighash: [u8; 32],
+    /// Description of the transaction for the signer to show before signing, e.g. the
+    /// recipients and amounts.
+    pub context: String,
+    pub spends: Vec<SpendAuthorization>,
+}
+
+impl SigningRequest {
+    /// Builds the request for the spends of `sapling`, given the `(ak, alpha)` of every spend.
+    ///
+    /// Fails unless every randomized key is the randomization of its `ak` by its `alpha`.
+    pub fn new(
+        sighash: [u8; 32],
+        sapling: &Sapling,
+        keys: &[([u8; 32], [u8; 32])],
+        context: String,
+    ) -> Result<Self, SigningPayloadError> {
+        if keys.len() != sapling.spends.len() {
+            return Err(SigningPayloadError::CountMismatch);
+        }
+
+        let spends = sapling.spends.iter().zip(keys.iter());
+        let request = SigningRequest {
+            sighash,
+            context,
+            spends: spends
+                .map(|(spend, (ak, alpha))| SpendAuthorization {
+                    randomized_key: spend.randomized_key,
+                    ak: *ak,
+                    alpha: *alpha,
+                })
+                .collect(),
+        };
+        request.check_randomizers()?;
+        Ok(request)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result =
+            Vec::with_capacity(1 + 32 + 4 + self.context.len() + 4 + 96 * self.spends.len());
+        result.push(PAYLOAD_VERSION);
+        result.extend_from_slice(&self.sighash);
+        result.extend_from_slice(&(self.context.len() as u32).to_le_bytes());
+        result.extend_from_slice(self.context.as_bytes());
+        result.extend_from_slice(&(self.spends.len() as u32).to_le_bytes());
+        for spend in self.spends.iter() {
+            result.extend_from_slice(&spend.randomized_key);
+            result.extend_from_slice(&spend.ak);
+            result.extend_from_slice(&spend.alpha);
+        }
+        result
+    }
+
+    /// Decodes a request, checking every randomized key against its `ak` and `alpha`.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SigningPayloadError> {
+        let body = read_version(data)?;
+        if body.len() < 32 + 4 {
+            return Err(SigningPayloadError::InvalidLength);
+        }
+
+        let mut sighash = [0u8; 32];
+        sighash.copy_from_slice(&body[..32]);
+        let (context, rest) = read_context(&body[32..])?;
+        let spends = read_items(rest, 96, |chunk| {
+            let mut spend = SpendAuthorization {
+                randomized_key: [0u8; 32],
+                ak: [0u8; 32],
+                alpha: [0u8; 32],
+            };
+            spend.randomized_key.copy_from_slice(&chunk[..32]);
+            spend.ak.copy_from_slice(&chunk[32..64]);
+            spend.alpha.copy_from_slice(&chunk[64..]);
+            spend
+        })?;
+
+        let request = SigningRequest {
+            sighash,
+            context,
+            spends,
+        };
+        request.check_randomizers()?;
+        Ok(request)
+    }
+
+    fn check_randomizers(&self) -> Result<(), SigningPayloadError> {
+        match self.spends.iter().position(|spend| !spend.is_consistent()) {
+            Some(index) => Err(SigningPayloadError::InconsistentRandomizer(index)),
+            None => Ok(()),
+        }
+    }
+
+    /// Verifies the signatures of `response` and writes them into the spends of `sapling`.
+    ///
+    /// `sapling` must be the bundle the request was built for. Nothing is written unless every
+    /// signature verifies.
+    pub fn apply(
+        &self,
+        policy: &VerificationPolicy,
+        response: &SigningResponse,
+        sapling: &mut Sapling,
+    ) -> Result<(), SigningPayloadError> {
+        if response.signatures.len() != self.spends.len()
+            || sapling.spends.len() != self.spends.len()
+        {
+            return Err(SigningPayloadError::CountMismatch);
+        }
+
+        let mut data_to_be_signed = [0u8; 64];
+        data_to_be_signed[32..].copy_from_slice(&self.sighash);
+
+        let items = self.spends.iter().zip(response.signatures.iter());
+        for (index, ((requested, sig), spend)) in items.zip(sapling.spends.iter()).enumerate() {
+            let key = &requested.randomized_key;
+            if spend.randomized_key != *key {
+                return Err(SigningPayloadError::KeyMismatch(index));
+            }
+
+            data_to_be_signed[..32].copy_from_slice(key);
+            verify_spend_auth_sig(policy, key, sig, &data_to_be_signed)
+                .map_err(|_| SigningPayloadError::InvalidSignature(index))?;
+        }
+
+        for (spend, sig) in sapling.spends.iter_mut().zip(response.signatures.iter()) {
+            spend.spend_auth_sig = *sig;
+        }
+
+        Ok(())
+    }
+}
+
+/// Spend authorization signatures returned by the signer, in the order of the request.
+#[derive(Clone)]
+pub struct SigningResponse {
+    pub signatures: Vec<[u8; 64]>,
+}
+
+impl SigningResponse {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(1 + 4 + 64 * self.signatures.len());
+        result.push(PAYLOAD_VERSION);
+        result.extend_from_slice(&(self.signatures.len() as u32).to_le_bytes());
+        for sig in self.signatures.iter() {
+            result.extend_from_slice(sig);
+        }
+        result
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SigningPayloadError> {
+        let body = read_version(data)?;
+        let signatures = read_items(body, 64, |chunk| {
+            let mut sig = [0u8; 64];
+            sig.copy_from_slice(chunk);
+            sig
+        })?;
+
+        Ok(SigningResponse { signatures })
+    }
+}
+
+impl PartialEq<SigningResponse> for SigningResponse {
+    fn eq(&self, other: &SigningResponse) -> bool {
+        self.signatures.len() == other.signatures.len()
+            && self
+                .signatures
+                .iter()
+                .zip(other.signatures.iter())
+                .all(|(a, b)| a.as_ref() == b.as_ref())
+    }
+}
+
+impl Eq for SigningResponse {}
+
+impl fmt::Debug for SigningResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.signatures.iter().map(|sig| &sig[..]))
+            .finish()
+    }
+}
+
+fn verify_spend_auth_sig(
+    policy: &VerificationPolicy,
+    key: &[u8; 32],
+    sig: &[u8; 64],
+    data_to_be_signed: &[u8; 64],
+) -> Result<(), Error> {
+    let randomized_key =
+        read_randomized_key(policy, key).map_err(|_| Error::InvalidRandomizedKey)?;
+    let sig = read_signature(policy, sig).map_err(|_| Error::MalformedSpendAuthSig)?;
+    require(
+        randomized_key.verify(
+            data_to_be_signed,
+            &sig,
+            FixedGenerators::SpendingKeyGenerator,
+        ),
+        Error::SpendAuthSigInvalid,
+    )
+}
+
+fn read_version(data: &[u8]) -> Result<&[u8], SigningPayloadError> {
+    match data.first() {
+        Some(&PAYLOAD_VERSION) => Ok(&data[1..]),
+        Some(&version) => Err(SigningPayloadError::UnsupportedVersion(version)),
+        None => Err(SigningPayloadError::InvalidLength),
+    }
+}
+
+/// Reads `len || context`, returning the context and the rest of `data`.
+fn read_context(data: &[u8]) -> Result<(String, &[u8]), SigningPayloadError> {
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&data[..4]);
+    let len = u32::from_le_bytes(len) as usize;
+    let rest = &data[4..];
+    if rest.len() < len {
+        return Err(SigningPayloadError::InvalidLength);
+    }
+
+    let context = str::from_utf8(&rest[..len]).map_err(|_| SigningPayloadError::InvalidContext)?;
+    Ok((context.into(), &rest[len..]))
+}
+
+/// Reads `count || items`, requiring the items to fill the rest of `data` exactly.
+fn read_items<T, F: Fn(&[u8]) -> T>(
+    data: &[u8],
+    item_len: usize,
+    read: F,
+) -> Result<Vec<T>, SigningPayloadError> {
+    if data.len() < 4 {
+        return Err(SigningPayloadError::InvalidLength);
+    }
+
+    let mut count = [0u8; 4];
+    count.copy_from_slice(&data[..4]);
+    let items = &data[4..];
+    if items.len() as u64 != u32::from_le_bytes(count) as u64 * item_len as u64 {
+        return Err(SigningPayloadError::InvalidLength);
+    }
+
+    Ok(items.chunks(item_len).map(read).collect())
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::{SigningPayloadError, SigningRequest, SigningResponse};
+    use crate::{tests::mainnet_sapling, Sapling, VerificationPolicy};
+    use alloc::{string::String, vec, vec::Vec};
+
+    /// Uses `rk` itself as `ak` with a zero randomizer, which is consistent.
+    fn request(sighash: [u8; 32], sapling: &Sapling) -> SigningRequest {
+        let keys: Vec<_> = sapling
+            .spends
+            .iter()
+            .map(|spend| (spend.randomized_key, [0u8; 32]))
+            .collect();
+        SigningRequest::new(sighash, sapling, &keys, String::from("send 1 ZEC")).unwrap()
+    }
+
+    #[test]
+    fn test_payload_roundtrip() {
+        let (sapling, sighash) = mainnet_sapling();
+        let request = request(sighash, &sapling);
+        let bytes = request.to_bytes();
+        assert_eq!(bytes.len(), 1 + 32 + 4 + 10 + 4 + 96);
+        assert_eq!(SigningRequest::from_bytes(&bytes), Ok(request));
+
+        let response = SigningResponse {
+            signatures: vec![[7u8; 64], [8u8; 64]],
+        };
+        assert_eq!(
+            SigningResponse::from_bytes(&response.to_bytes()),
+            Ok(response)
+        );
+
+        assert_eq!(
+            SigningRequest::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(SigningPayloadError::InvalidLength)
+        );
+        assert_eq!(
+            SigningResponse::from_bytes(&[1, 0, 0, 0, 0]),
+            Err(SigningPayloadError::UnsupportedVersion(1))
+        );
+
+        let mut invalid_context = bytes.clone();
+        invalid_context[1 + 32 + 4] = 0xff;
+        assert_eq!(
+            SigningRequest::from_bytes(&invalid_context),
+            Err(SigningPayloadError::InvalidContext)
+        );
+    }
+
+    #[test]
+    fn test_randomizer_consistency() {
+        let (sapling, sighash) = mainnet_sapling();
+        let rk = sapling.spends[0].randomized_key;
+        let context = String::new();
+
+        // rk is not rk + G
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert_eq!(
+            SigningRequest::new(sighash, &sapling, &[(rk, one)], context.clone()),
+            Err(SigningPayloadError::InconsistentRandomizer(0))
+        );
+
+        // alpha not below the order of the subgroup
+        assert_eq!(
+            SigningRequest::new(sighash, &sapling, &[(rk, [0xff; 32])], context.clone()),
+            Err(SigningPayloadError::InconsistentRandomizer(0))
+        );
+
+        // ak is not a point, its y coordinate is above the field modulus
+        assert_eq!(
+            SigningRequest::new(
+                sighash,
+                &sapling,
+                &[([0xff; 32], [0u8; 32])],
+                context.clone()
+            ),
+            Err(SigningPayloadError::InconsistentRandomizer(0))
+        );
+
+        assert_eq!(
+            SigningRequest::new(sighash, &sapling, &[], context),
+            Err(SigningPayloadError::CountMismatch)
+        );
+
+        // a decoded request is checked as well
+        let mut bytes = request(sighash, &sapling).to_bytes();
+        let len = bytes.len();
+        bytes[len - 32] = 1;
+        assert_eq!(
+            SigningRequest::from_bytes(&bytes),
+            Err(SigningPayloadError::InconsistentRandomizer(0))
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let policy = VerificationPolicy::strict();
+        let request = request(sighash, &sapling);
+        let signed = sapling.spends[0].spend_auth_sig;
+        sapling.spends[0].spend_auth_sig = [0u8; 64];
+
+        let mut invalid = signed;
+        invalid[40] ^= 1;
+        let response = SigningResponse {
+            signatures: vec![invalid],
+        };
+        assert_eq!(
+            request.apply(&policy, &response, &mut sapling),
+            Err(SigningPayloadError::InvalidSignature(0))
+        );
+        assert_eq!(sapling.spends[0].spend_auth_sig[..], [0u8; 64][..]);
+
+        let response = SigningResponse {
+            signatures: vec![signed],
+        };
+        assert_eq!(request.apply(&policy, &response, &mut sapling), Ok(()));
+        assert_eq!(sapling.spends[0].spend_auth_sig[..], signed[..]);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    affine::{read_g1affine_slice, read_g2affine_slice, AffineDecodeError},
+    Groth16VerifyingKey,
+};
+use alloc::vec::Vec;
+use hex_literal::hex;
+#[cfg(feature = "std")]
+use std::io;
+
+/// BLAKE2b-512 hash of the official `sapling-spend.params` file.
+pub const SPEND_PARAMS_HASH: [u8; 64] = hex!("8270785a1a0d0bc77196f000ee6d221c9c9894f55307bd9357c3f0105d31ca63991ab91324160d8f53e2bbd3c2633a6eb8bdf5205d822e7f3f73edac51b2b70c");
+
+/// BLAKE2b-512 hash of the official `sapling-output.params` file.
+pub const OUTPUT_PARAMS_HASH: [u8; 64] = hex!("657e3d38dbb5cb5e7dd2970e8b03d69b4787dd907285b5a7f0790dcc8072f60bf593b32cc2d1c030e00ff5ae64bf84c5c3beb84ddc841d48264b4a171744d028");
+
+/// Size of the verifying key fields preceding the `ic` bases.
+const VK_POINTS_SIZE: usize = 96 + 96 + 192 + 192 + 96 + 192;
+/// Largest number of `ic` bases accepted, far more than the Sapling circuits need.
+const MAX_IC_LEN: usize = 32;
+
+/// Reason for rejecting a parameter file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamsError {
+    /// The file ends inside the verifying key, or declares too many `ic` bases.
+    InvalidLength,
+    /// A point of the verifying key failed to decode.
+    InvalidPoint(AffineDecodeError),
+    /// The file does not have the expected hash.
+    HashMismatch,
+    /// The underlying reader failed.
+    #[cfg(feature = "std")]
+    Io(io::ErrorKind),
+}
+
+impl From<AffineDecodeError> for ParamsError {
+    fn from(err: AffineDecodeError) -> Self {
+        ParamsError::InvalidPoint(err)
+    }
+}
+
+/// Reads the verifying key of the official `sapling-spend.params` file.
+pub fn read_spend_params(data: &[u8]) -> Result<Groth16VerifyingKey, ParamsError> {
+    read_params_vk(data, &SPEND_PARAMS_HASH)
+}
+
+/// Reads the verifying key of the official `sapling-output.params` file.
+pub fn read_output_params(data: &[u8]) -> Result<Groth16VerifyingKey, ParamsError> {
+    read_params_vk(data, &OUTPUT_PARAMS_HASH)
+}
+
+/// Reads the verifying key at the beginning of a bellman parameter file, after checking that
+/// the BLAKE2b-512 hash of the whole file is `expected_hash`.
+///
+/// The file starts with the verifying key, `alpha_g1 || beta_g1 || beta_g2 || gamma_g2 ||
+/// delta_g1 || delta_g2 || ic`, where `ic` is prefixed with its big-endian `u32` length and
+/// every point is uncompressed. The proving material that follows is covered by the hash, but
+/// not decoded.
+pub fn read_params_vk(
+    data: &[u8],
+    expected_hash: &[u8; 64],
+) -> Result<Groth16VerifyingKey, ParamsError> {
+    if blake2b_simd::blake2b(data).as_bytes() != &expected_hash[..] {
+        return Err(ParamsError::HashMismatch);
+    }
+
+    if data.len() < VK_POINTS_SIZE + 4 {
+        return Err(ParamsError::InvalidLength);
+    }
+
+    let ic_len = read_ic_len(&data[VK_POINTS_SIZE..VK_POINTS_SIZE + 4])?;
+    let vk_len = VK_POINTS_SIZE + 4 + ic_len * 96;
+    if data.len() < vk_len {
+        return Err(ParamsError::InvalidLength);
+    }
+
+    decode_vk(&data[..vk_len])
+}
+
+/// Same as `read_params_vk` for a file read from `reader`, which is consumed to the end.
+///
+/// The file is hashed while it is read, so only the verifying key is kept in memory.
+#[cfg(feature = "std")]
+pub fn read_params_vk_from<R: io::Read>(
+    mut reader: R,
+    expected_hash: &[u8; 64],
+) -> Result<Groth16VerifyingKey, ParamsError> {
+    let mut state = blake2b_simd::State::new();
+    let mut read_exact = |out: &mut [u8]| -> Result<(), ParamsError> {
+        reader.read_exact(out).map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => ParamsError::InvalidLength,
+            kind => ParamsError::Io(kind),
+        })
+    };
+
+    let mut vk = Vec::new();
+    vk.resize(VK_POINTS_SIZE + 4, 0);
+    read_exact(&mut vk)?;
+    let ic_len = read_ic_len(&vk[VK_POINTS_SIZE..])?;
+    let offset = vk.len();
+    vk.resize(offset + ic_len * 96, 0);
+    read_exact(&mut vk[offset..])?;
+    state.update(&vk);
+
+    let mut buf = [0u8; 8192];
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(ParamsError::Io(err.kind())),
+        };
+        state.update(&buf[..len]);
+    }
+
+    if state.finalize().as_bytes() != &expected_hash[..] {
+        return Err(ParamsError::HashMismatch);
+    }
+
+    decode_vk(&vk)
+}
+
+fn read_ic_len(data: &[u8]) -> Result<usize, ParamsError> {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(data);
+    let len = u32::from_be_bytes(bytes) as usize;
+    // there is always at least one base
+    if len == 0 || len > MAX_IC_LEN {
+        return Err(ParamsError::InvalidLength);
+    }
+
+    Ok(len)
+}
+
+/// Decodes exactly the verifying key of a parameter file, `beta_g1` and `delta_g1` are
+/// checked and dropped as verification does not need them.
+fn decode_vk(data: &[u8]) -> Result<Groth16VerifyingKey, ParamsError> {
+    let (alpha_g1, mut offset) = read_g1affine_slice(data)?;
+    let (_beta_g1, len) = read_g1affine_slice(&data[offset..])?;
+    offset += len;
+    let (beta_g2, len) = read_g2affine_slice(&data[offset..])?;
+    offset += len;
+    let (gamma_g2, len) = read_g2affine_slice(&data[offset..])?;
+    offset += len;
+    let (_delta_g1, len) = read_g1affine_slice(&data[offset..])?;
+    offset += len;
+    let (delta_g2, len) = read_g2affine_slice(&data[offset..])?;
+    offset += len + 4;
+
+    let mut gamma_abc_g1 = Vec::with_capacity((data.len() - offset) / 96);
+    while offset < data.len() {
+        let (point, len) = read_g1affine_slice(&data[offset..])?;
+        gamma_abc_g1.push(point);
+        offset += len;
+    }
+
+    Ok(Groth16VerifyingKey {
+        alpha_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g2,
+        gamma_abc_g1,
+    })
+}
+
+#[cfg(all(test, feature = "spend-vk"))]
+mod tests {
+    use super::{read_params_vk, read_spend_params, ParamsError};
+    use crate::{
+        affine::{write_g1affine, write_g2affine},
+        zcash::{spend_vk, write_vk},
+    };
+    use alloc::vec::Vec;
+
+    /// Encodes the spend key as a parameter file followed by fake proving material.
+    fn params_file() -> Vec<u8> {
+        let vk = spend_vk();
+        let mut data = Vec::new();
+        data.extend_from_slice(&write_g1affine(&vk.alpha_g1));
+        // any valid points will do for the fields verification does not use
+        data.extend_from_slice(&write_g1affine(&vk.gamma_abc_g1[0]));
+        data.extend_from_slice(&write_g2affine(&vk.beta_g2));
+        data.extend_from_slice(&write_g2affine(&vk.gamma_g2));
+        data.extend_from_slice(&write_g1affine(&vk.gamma_abc_g1[1]));
+        data.extend_from_slice(&write_g2affine(&vk.delta_g2));
+        data.extend_from_slice(&(vk.gamma_abc_g1.len() as u32).to_be_bytes());
+        for point in &vk.gamma_abc_g1 {
+            data.extend_from_slice(&write_g1affine(point));
+        }
+        data.extend_from_slice(&[0u8; 4]);
+        data
+    }
+
+    #[test]
+    fn test_read_params_vk() {
+        let data = params_file();
+        let mut hash = [0u8; 64];
+        hash.copy_from_slice(blake2b_simd::blake2b(&data).as_bytes());
+
+        let vk = read_params_vk(&data, &hash).unwrap();
+        assert_eq!(write_vk(&vk), write_vk(&spend_vk()));
+
+        assert_eq!(
+            read_spend_params(&data).err(),
+            Some(ParamsError::HashMismatch)
+        );
+        assert_eq!(
+            read_params_vk(&data[..data.len() - 1], &hash).err(),
+            Some(ParamsError::HashMismatch)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_params_vk_from() {
+        use super::read_params_vk_from;
+
+        let data = params_file();
+        let mut hash = [0u8; 64];
+        hash.copy_from_slice(blake2b_simd::blake2b(&data).as_bytes());
+
+        let vk = read_params_vk_from(&data[..], &hash).unwrap();
+        assert_eq!(write_vk(&vk), write_vk(&spend_vk()));
+        assert_eq!(
+            read_params_vk_from(&data[..700], &hash).err(),
+            Some(ParamsError::InvalidLength)
+        );
+    }
+}
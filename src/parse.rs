@@ -0,0 +1,491 @@
+use crate::{Sapling, SaplingOutputDescription, SaplingRef, SaplingSpendDescription};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+
+/// `fOverwintered` flag and version of a v4 transaction header.
+const V4_HEADER: u32 = (1 << 31) | 4;
+/// Version group id of Sapling transactions.
+const SAPLING_VERSION_GROUP_ID: u32 = 0x892f_2085;
+/// Largest compact size accepted by zcashd.
+const MAX_COMPACT_SIZE: u64 = 0x0200_0000;
+/// Size of a JoinSplit description with a Groth16 proof.
+const JOINSPLIT_SIZE: usize = 8 + 8 + 32 + 2 * 32 + 2 * 32 + 32 + 32 + 2 * 32 + 192 + 2 * 601;
+
+/// Reason for rejecting an encoded transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadError {
+    /// The data ends before the transaction does.
+    UnexpectedEnd,
+    /// The transaction is not a Sapling v4 transaction.
+    InvalidHeader,
+    /// A compact size is not minimally encoded or is too large.
+    InvalidCompactSize,
+    /// Bytes follow the end of the transaction.
+    TrailingBytes,
+    /// A count exceeds its `ReadLimits` maximum.
+    TooLarge,
+    /// The underlying reader failed.
+    #[cfg(feature = "std")]
+    Io(io::ErrorKind),
+}
+
+/// Maximum counts accepted while reading a transaction.
+///
+/// Counts are checked as soon as they are read, before anything is allocated or skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    pub max_spends: usize,
+    pub max_outputs: usize,
+    /// Maximum number of transparent inputs.
+    pub max_inputs: usize,
+}
+
+impl ReadLimits {
+    /// Returns limits accepting every count zcashd can encode.
+    pub fn unlimited() -> Self {
+        ReadLimits {
+            max_spends: usize::max_value(),
+            max_outputs: usize::max_value(),
+            max_inputs: usize::max_value(),
+        }
+    }
+}
+
+impl Sapling {
+    /// Reads the Sapling fields of an encoded v4 transaction.
+    ///
+    /// The transparent and JoinSplit fields are skipped without being validated. `tx` must hold
+    /// exactly one transaction.
+    pub fn read(tx: &[u8]) -> Result<Self, ReadError> {
+        Self::read_with_limits(tx, &ReadLimits::unlimited())
+    }
+
+    /// Same as `read`, failing with `ReadError::TooLarge` if a count exceeds `limits`.
+    pub fn read_with_limits(tx: &[u8], limits: &ReadLimits) -> Result<Self, ReadError> {
+        let mut source = tx;
+        let sapling = read_transaction(&mut source, limits)?;
+        if !source.is_empty() {
+            return Err(ReadError::TrailingBytes);
+        }
+
+        Ok(sapling)
+    }
+
+    /// Same as `read` for a transaction read from `reader`, which is left at the end of the
+    /// transaction.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: io::Read>(reader: R) -> Result<Self, ReadError> {
+        Self::read_from_with_limits(reader, &ReadLimits::unlimited())
+    }
+
+    /// Same as `read_from`, failing with `ReadError::TooLarge` if a count exceeds `limits`.
+    #[cfg(feature = "std")]
+    pub fn read_from_with_limits<R: io::Read>(
+        reader: R,
+        limits: &ReadLimits,
+    ) -> Result<Self, ReadError> {
+        read_transaction(&mut IoSource(reader), limits)
+    }
+}
+
+impl<'a> SaplingRef<'a> {
+    /// Same as `Sapling::read`, borrowing the descriptions from `tx`.
+    pub fn read(tx: &'a [u8]) -> Result<Self, ReadError> {
+        Self::read_with_limits(tx, &ReadLimits::unlimited())
+    }
+
+    /// Same as `Sapling::read_with_limits`, borrowing the descriptions from `tx`.
+    pub fn read_with_limits(tx: &'a [u8], limits: &ReadLimits) -> Result<Self, ReadError> {
+        let mut source = tx;
+        let sapling = read_transaction_ref(&mut source, limits)?;
+        if !source.is_empty() {
+            return Err(ReadError::TrailingBytes);
+        }
+
+        Ok(sapling)
+    }
+}
+
+/// Byte stream a transaction is read from.
+trait Source {
+    fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), ReadError>;
+
+    fn skip(&mut self, len: usize) -> Result<(), ReadError>;
+
+    fn read_u32(&mut self) -> Result<u32, ReadError> {
+        let mut bytes = [0u8; 4];
+        self.read_bytes(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_compact_size(&mut self) -> Result<usize, ReadError> {
+        let mut first = [0u8; 1];
+        self.read_bytes(&mut first)?;
+        let (len, min) = match first[0] {
+            0xfd => (2, 0xfd),
+            0xfe => (4, 0x1_0000),
+            0xff => (8, 0x1_0000_0000),
+            value => return Ok(value as usize),
+        };
+
+        let mut bytes = [0u8; 8];
+        self.read_bytes(&mut bytes[..len])?;
+        let value = u64::from_le_bytes(bytes);
+        if value < min || value > MAX_COMPACT_SIZE {
+            return Err(ReadError::InvalidCompactSize);
+        }
+
+        Ok(value as usize)
+    }
+
+    /// Reads a compact size, failing with `ReadError::TooLarge` above `max`.
+    fn read_count(&mut self, max: usize) -> Result<usize, ReadError> {
+        let count = self.read_compact_size()?;
+        if count > max {
+            return Err(ReadError::TooLarge);
+        }
+
+        Ok(count)
+    }
+}
+
+impl Source for &[u8] {
+    fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), ReadError> {
+        if self.len() < out.len() {
+            return Err(ReadError::UnexpectedEnd);
+        }
+
+        out.copy_from_slice(&self[..out.len()]);
+        *self = &self[out.len()..];
+        Ok(())
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), ReadError> {
+        if self.len() < len {
+            return Err(ReadError::UnexpectedEnd);
+        }
+
+        *self = &self[len..];
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+struct IoSource<R>(R);
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Source for IoSource<R> {
+    fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), ReadError> {
+        self.0.read_exact(out).map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => ReadError::UnexpectedEnd,
+            kind => ReadError::Io(kind),
+        })
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), ReadError> {
+        let mut buffer = [0u8; 256];
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(buffer.len());
+            self.read_bytes(&mut buffer[..chunk])?;
+            remaining -= chunk;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_transaction<S: Source>(source: &mut S, limits: &ReadLimits) -> Result<Sapling, ReadError> {
+    let balancing_value = read_prefix(source, limits)?;
+
+    // counts come from untrusted data, so nothing is reserved up front
+    let mut spends = Vec::new();
+    let mut buffer = [0u8; SaplingOutputDescription::SIZE];
+    for _ in 0..source.read_count(limits.max_spends)? {
+        let data = &mut buffer[..SaplingSpendDescription::SIZE];
+        source.read_bytes(data)?;
+        let (spend, _) = SaplingSpendDescription::from_slice(data).expect("data has spend size");
+        spends.push(spend);
+    }
+
+    let mut outputs = Vec::new();
+    for _ in 0..source.read_count(limits.max_outputs)? {
+        source.read_bytes(&mut buffer)?;
+        let (output, _) =
+            SaplingOutputDescription::from_slice(&buffer).expect("data has output size");
+        outputs.push(output);
+    }
+
+    skip_joinsplits(source)?;
+
+    let mut binding_sig = [0u8; 64];
+    if !spends.is_empty() || !outputs.is_empty() {
+        source.read_bytes(&mut binding_sig)?;
+    }
+
+    Ok(Sapling {
+        balancing_value,
+        spends,
+        outputs,
+        binding_sig,
+    })
+}
+
+fn read_transaction_ref<'a>(
+    source: &mut &'a [u8],
+    limits: &ReadLimits,
+) -> Result<SaplingRef<'a>, ReadError> {
+    let balancing_value = read_prefix(source, limits)?;
+
+    let count = source.read_count(limits.max_spends)?;
+    let spends = take(source, count, SaplingSpendDescription::SIZE)?;
+    let count = source.read_count(limits.max_outputs)?;
+    let outputs = take(source, count, SaplingOutputDescription::SIZE)?;
+
+    skip_joinsplits(source)?;
+
+    let binding_sig = if !spends.is_empty() || !outputs.is_empty() {
+        take(source, 1, 64)?
+    } else {
+        &[]
+    };
+
+    Ok(SaplingRef {
+        balancing_value,
+        spends,
+        outputs,
+        binding_sig,
+    })
+}
+
+/// Reads the fields up to `valueBalance`, skipping the transparent ones.
+fn read_prefix<S: Source>(source: &mut S, limits: &ReadLimits) -> Result<i64, ReadError> {
+    if source.read_u32()? != V4_HEADER || source.read_u32()? != SAPLING_VERSION_GROUP_ID {
+        return Err(ReadError::InvalidHeader);
+    }
+
+    // transparent inputs: outpoint, script and sequence
+    for _ in 0..source.read_count(limits.max_inputs)? {
+        source.skip(32 + 4)?;
+        let script_len = source.read_compact_size()?;
+        source.skip(script_len + 4)?;
+    }
+
+    // transparent outputs: value and script
+    for _ in 0..source.read_compact_size()? {
+        source.skip(8)?;
+        let script_len = source.read_compact_size()?;
+        source.skip(script_len)?;
+    }
+
+    // lock time and expiry height
+    source.skip(4 + 4)?;
+
+    let mut balancing_value = [0u8; 8];
+    source.read_bytes(&mut balancing_value)?;
+    Ok(i64::from_le_bytes(balancing_value))
+}
+
+fn skip_joinsplits<S: Source>(source: &mut S) -> Result<(), ReadError> {
+    let joinsplits = source.read_compact_size()?;
+    if joinsplits > 0 {
+        let len = joinsplits.checked_mul(JOINSPLIT_SIZE);
+        source.skip(len.ok_or(ReadError::UnexpectedEnd)?)?;
+        // joinSplitPubKey and joinSplitSig
+        source.skip(32 + 64)?;
+    }
+
+    Ok(())
+}
+
+/// Borrows `count` items of `size` bytes.
+fn take<'a>(source: &mut &'a [u8], count: usize, size: usize) -> Result<&'a [u8], ReadError> {
+    let len = count.checked_mul(size).ok_or(ReadError::UnexpectedEnd)?;
+    if source.len() < len {
+        return Err(ReadError::UnexpectedEnd);
+    }
+
+    let (result, rest) = source.split_at(len);
+    *source = rest;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReadError, ReadLimits, JOINSPLIT_SIZE, SAPLING_VERSION_GROUP_ID, V4_HEADER};
+    use crate::{Sapling, SaplingOutputDescription, SaplingRef, SaplingSpendDescription};
+    use alloc::{vec, vec::Vec};
+
+    fn encode(spends: usize, outputs: usize, joinsplits: usize) -> Vec<u8> {
+        let mut tx = Vec::new();
+        tx.extend_from_slice(&V4_HEADER.to_le_bytes());
+        tx.extend_from_slice(&SAPLING_VERSION_GROUP_ID.to_le_bytes());
+        // one transparent input with a 2 byte script and no outputs
+        tx.push(1);
+        tx.extend_from_slice(&[7; 36]);
+        tx.extend_from_slice(&[2, 0xaa, 0xbb]);
+        tx.extend_from_slice(&[0xff; 4]);
+        tx.push(0);
+        tx.extend_from_slice(&[0; 8]);
+        tx.extend_from_slice(&(-5i64).to_le_bytes());
+        tx.push(spends as u8);
+        for _ in 0..spends {
+            tx.extend((0..SaplingSpendDescription::SIZE).map(|i| i as u8));
+        }
+        tx.push(outputs as u8);
+        for _ in 0..outputs {
+            tx.extend((0..SaplingOutputDescription::SIZE).map(|i| i as u8));
+        }
+        tx.push(joinsplits as u8);
+        if joinsplits > 0 {
+            tx.extend(vec![0; joinsplits * JOINSPLIT_SIZE + 32 + 64]);
+        }
+        if spends + outputs > 0 {
+            tx.extend_from_slice(&[9; 64]);
+        }
+        tx
+    }
+
+    #[test]
+    fn test_read() {
+        let sapling = Sapling::read(&encode(2, 1, 1)).unwrap();
+        assert_eq!(sapling.balancing_value, -5);
+        assert_eq!(sapling.spends.len(), 2);
+        assert_eq!(sapling.outputs.len(), 1);
+        assert_eq!(sapling.spends[1].anchor[0], 32);
+        assert_eq!(sapling.outputs[0].zkproof[191], 179);
+        assert_eq!(sapling.binding_sig[..], [9; 64][..]);
+
+        // no binding signature without descriptions
+        assert_eq!(
+            Sapling::read(&encode(0, 0, 0)),
+            Ok(Sapling {
+                balancing_value: -5,
+                ..Sapling::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_ref() {
+        let tx = encode(2, 1, 1);
+        let sapling = SaplingRef::read(&tx).unwrap();
+        assert_eq!(sapling.to_sapling(), Sapling::read(&tx).unwrap());
+
+        let spend = sapling.spends().nth(1).unwrap();
+        assert_eq!(spend.anchor()[0], 32);
+        assert_eq!(spend.zkproof().len(), 192);
+        assert_eq!(sapling.outputs().count(), 1);
+
+        assert_eq!(
+            SaplingRef::read(&tx[..tx.len() - 1]),
+            Err(ReadError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let tx = encode(1, 2, 0);
+        let bytes = Sapling::read(&tx).unwrap().to_bytes();
+
+        // the transparent fields come first, the JoinSplit count right before `bindingSig`
+        let prefix = tx.len() - bytes.len() - 1;
+        let (descriptions, binding_sig) = bytes.split_at(bytes.len() - 64);
+        assert_eq!(tx[prefix..tx.len() - 65], descriptions[..]);
+        assert_eq!(tx[tx.len() - 65], 0);
+        assert_eq!(tx[tx.len() - 64..], binding_sig[..]);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let tx = encode(1, 1, 0);
+        assert_eq!(
+            Sapling::read(&tx[..tx.len() - 1]),
+            Err(ReadError::UnexpectedEnd)
+        );
+
+        let mut trailing = tx.clone();
+        trailing.push(0);
+        assert_eq!(Sapling::read(&trailing), Err(ReadError::TrailingBytes));
+
+        let mut v3 = tx.clone();
+        v3[0] = 3;
+        assert_eq!(Sapling::read(&v3), Err(ReadError::InvalidHeader));
+
+        // a single input count encoded in three bytes
+        let mut non_minimal = tx[..8].to_vec();
+        non_minimal.extend_from_slice(&[0xfd, 1, 0]);
+        non_minimal.extend_from_slice(&tx[9..]);
+        assert_eq!(
+            Sapling::read(&non_minimal),
+            Err(ReadError::InvalidCompactSize)
+        );
+    }
+
+    #[test]
+    fn test_read_limits() {
+        let tx = encode(2, 1, 0);
+        let limits = ReadLimits {
+            max_spends: 2,
+            max_outputs: 1,
+            max_inputs: 1,
+        };
+        assert_eq!(Sapling::read_with_limits(&tx, &limits), Sapling::read(&tx));
+        assert_eq!(
+            SaplingRef::read_with_limits(&tx, &limits),
+            SaplingRef::read(&tx)
+        );
+
+        let too_large = [
+            ReadLimits {
+                max_spends: 1,
+                ..limits
+            },
+            ReadLimits {
+                max_outputs: 0,
+                ..limits
+            },
+            ReadLimits {
+                max_inputs: 0,
+                ..limits
+            },
+        ];
+        for limits in too_large.iter() {
+            assert_eq!(
+                Sapling::read_with_limits(&tx, limits),
+                Err(ReadError::TooLarge)
+            );
+            assert_eq!(
+                SaplingRef::read_with_limits(&tx, limits),
+                Err(ReadError::TooLarge)
+            );
+        }
+
+        // the count is rejected before the data it announces is needed
+        let mut truncated = tx[..8].to_vec();
+        truncated.extend_from_slice(&[0xfe, 0, 0, 0, 1]);
+        assert_eq!(
+            Sapling::read_with_limits(&truncated, &limits),
+            Err(ReadError::TooLarge)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_from() {
+        let tx = encode(1, 2, 0);
+        let mut reader = &tx[..];
+        assert_eq!(Sapling::read_from(&mut reader), Sapling::read(&tx));
+        assert!(reader.is_empty());
+
+        let limits = ReadLimits {
+            max_outputs: 1,
+            ..ReadLimits::unlimited()
+        };
+        assert_eq!(
+            Sapling::read_from_with_limits(&tx[..], &limits),
+            Err(ReadError::TooLarge)
+        );
+    }
+}
@@ -0,0 +1,97 @@
+/// Consensus branch id of Overwinter, the last upgrade before Sapling.
+pub const OVERWINTER_BRANCH_ID: u32 = 0x5ba8_1b19;
+/// Consensus branch id of Sapling.
+pub const SAPLING_BRANCH_ID: u32 = 0x76b8_09bb;
+/// Consensus branch id of Blossom.
+pub const BLOSSOM_BRANCH_ID: u32 = 0x2bb4_0e60;
+/// Consensus branch id of Heartwood.
+pub const HEARTWOOD_BRANCH_ID: u32 = 0xf5b9_230b;
+/// Consensus branch id of Canopy.
+pub const CANOPY_BRANCH_ID: u32 = 0xe9ff_75a6;
+/// Consensus branch id of NU5.
+pub const NU5_BRANCH_ID: u32 = 0xc2d6_d0b4;
+/// Consensus branch id of NU6.
+pub const NU6_BRANCH_ID: u32 = 0xc8e7_1055;
+
+/// Strictness toggles applied by every verification entry point.
+///
+/// Use `VerificationPolicy::consensus` to obtain the rules of a network upgrade instead of
+/// setting the fields by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationPolicy {
+    /// Reject anchors and note commitments encoding values not below the field modulus.
+    /// When disabled such values are reduced.
+    pub canonical_field_encodings: bool,
+    /// Reject signatures whose `R` is not a valid point or whose `S` is not below the order of
+    /// the Jubjub subgroup.
+    pub canonical_signatures: bool,
+    /// Reject non-canonical Jubjub point encodings, as specified by ZIP 216.
+    pub canonical_point_encodings: bool,
+}
+
+impl VerificationPolicy {
+    /// Returns the consensus rules for the network upgrade identified by `branch_id`.
+    ///
+    /// Returns `None` for Sprout and Overwinter, on which Sapling is not active. Branch ids
+    /// this crate does not know yet belong to later upgrades and get the newest rules.
+    pub fn consensus(branch_id: u32) -> Option<Self> {
+        match branch_id {
+            SAPLING_BRANCH_ID | BLOSSOM_BRANCH_ID | HEARTWOOD_BRANCH_ID | CANOPY_BRANCH_ID => {
+                Some(VerificationPolicy {
+                    canonical_field_encodings: true,
+                    canonical_signatures: true,
+                    canonical_point_encodings: false,
+                })
+            }
+            // sprout
+            0 | OVERWINTER_BRANCH_ID => None,
+            _ => Some(Self::strict()),
+        }
+    }
+
+    /// Returns a policy with every check enabled.
+    pub fn strict() -> Self {
+        VerificationPolicy {
+            canonical_field_encodings: true,
+            canonical_signatures: true,
+            canonical_point_encodings: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        VerificationPolicy, CANOPY_BRANCH_ID, NU5_BRANCH_ID, NU6_BRANCH_ID, OVERWINTER_BRANCH_ID,
+        SAPLING_BRANCH_ID,
+    };
+
+    #[test]
+    fn test_consensus_policy() {
+        let sapling = VerificationPolicy::consensus(SAPLING_BRANCH_ID).unwrap();
+        assert!(sapling.canonical_field_encodings);
+        assert!(!sapling.canonical_point_encodings);
+        assert_eq!(
+            VerificationPolicy::consensus(CANOPY_BRANCH_ID),
+            Some(sapling)
+        );
+        assert_eq!(
+            VerificationPolicy::consensus(NU5_BRANCH_ID),
+            Some(VerificationPolicy::strict())
+        );
+        assert_eq!(
+            VerificationPolicy::consensus(NU6_BRANCH_ID),
+            Some(VerificationPolicy::strict())
+        );
+
+        // sprout and overwinter
+        assert_eq!(VerificationPolicy::consensus(0), None);
+        assert_eq!(VerificationPolicy::consensus(OVERWINTER_BRANCH_ID), None);
+
+        // an upgrade after NU6
+        assert_eq!(
+            VerificationPolicy::consensus(0x4dec_4df0),
+            Some(VerificationPolicy::strict())
+        );
+    }
+}
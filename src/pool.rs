@@ -0,0 +1,115 @@
+use crate::Sapling;
+
+/// Net value a Sapling bundle moves across the boundary of the Sapling pool, in zatoshi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolTransfer {
+    /// The balancing value is zero.
+    None,
+    /// Value enters the Sapling pool, the balancing value is negative.
+    Shielding(u64),
+    /// Value leaves the Sapling pool, the balancing value is positive.
+    Deshielding(u64),
+}
+
+/// Kind of a transaction with respect to the Sapling pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+    /// The transaction has no Sapling descriptions.
+    Transparent,
+    /// The transaction has no transparent inputs or outputs. Its positive balancing value
+    /// only pays the fee.
+    FullyShielded,
+    /// Transparent value of this amount enters the Sapling pool.
+    Shielding(u64),
+    /// Sapling value of this amount leaves the pool to pay transparent outputs and the fee.
+    Deshielding(u64),
+    /// The transaction has transparent and Sapling parts, but no value crosses the boundary.
+    Mixed,
+}
+
+impl Sapling {
+    /// Classifies the bundle by the sign of its balancing value.
+    pub fn pool_transfer(&self) -> PoolTransfer {
+        let value = self.balancing_value;
+        if value < 0 {
+            // correct for i64::MIN as well
+            PoolTransfer::Shielding(0u64.wrapping_sub(value as u64))
+        } else if value > 0 {
+            PoolTransfer::Deshielding(value as u64)
+        } else {
+            PoolTransfer::None
+        }
+    }
+}
+
+/// Classifies a transaction given its Sapling bundle and whether it has transparent inputs
+/// or outputs.
+pub fn classify_transaction(
+    sapling: &Sapling,
+    has_transparent_inputs: bool,
+    has_transparent_outputs: bool,
+) -> TransactionKind {
+    if sapling.spends.is_empty() && sapling.outputs.is_empty() {
+        return TransactionKind::Transparent;
+    }
+
+    if !has_transparent_inputs && !has_transparent_outputs {
+        return TransactionKind::FullyShielded;
+    }
+
+    match sapling.pool_transfer() {
+        PoolTransfer::None => TransactionKind::Mixed,
+        PoolTransfer::Shielding(value) => TransactionKind::Shielding(value),
+        PoolTransfer::Deshielding(value) => TransactionKind::Deshielding(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_transaction, PoolTransfer, TransactionKind};
+    use crate::{Sapling, SaplingOutputDescription};
+    use alloc::vec;
+
+    fn bundle(balancing_value: i64) -> Sapling {
+        Sapling {
+            balancing_value,
+            outputs: vec![SaplingOutputDescription::default()],
+            ..Sapling::default()
+        }
+    }
+
+    #[test]
+    fn test_pool_transfer() {
+        assert_eq!(bundle(0).pool_transfer(), PoolTransfer::None);
+        assert_eq!(bundle(-5).pool_transfer(), PoolTransfer::Shielding(5));
+        assert_eq!(bundle(7).pool_transfer(), PoolTransfer::Deshielding(7));
+        assert_eq!(
+            bundle(i64::min_value()).pool_transfer(),
+            PoolTransfer::Shielding(1 << 63)
+        );
+    }
+
+    #[test]
+    fn test_classify_transaction() {
+        assert_eq!(
+            classify_transaction(&Sapling::default(), true, true),
+            TransactionKind::Transparent
+        );
+        assert_eq!(
+            classify_transaction(&bundle(1000), false, false),
+            TransactionKind::FullyShielded
+        );
+        assert_eq!(
+            classify_transaction(&bundle(-1000), true, false),
+            TransactionKind::Shielding(1000)
+        );
+        assert_eq!(
+            classify_transaction(&bundle(1000), false, true),
+            TransactionKind::Deshielding(1000)
+        );
+        assert_eq!(
+            classify_transaction(&bundle(0), true, true),
+            TransactionKind::Mixed
+        );
+    }
+}
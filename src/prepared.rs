@@ -0,0 +1,195 @@
+use crate::{
+    affine::{AffineDecodeError, EncodedField},
+    zcash::{read_vk, write_vk},
+    Groth16PreparedVerifyingKey,
+};
+use algebra::bls12_381::{Fq12, Fq2, Fq6, G2Prepared};
+use alloc::vec::Vec;
+
+/// First bytes of an encoded prepared verifying key.
+pub const PREPARED_VK_MAGIC: [u8; 4] = *b"ZSPV";
+/// Version of the encoding written by `write_prepared_vk`.
+pub const PREPARED_VK_VERSION: u8 = 1;
+
+/// Size of an encoded line coefficient, three `Fq2` elements.
+const ELL_COEFF_SIZE: usize = 3 * 96;
+
+/// Reason for rejecting an encoded prepared verifying key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreparedVkError {
+    /// The data does not start with `PREPARED_VK_MAGIC` and `PREPARED_VK_VERSION`.
+    InvalidHeader,
+    /// The data ends early or has trailing bytes.
+    InvalidLength,
+    /// A point or field element failed to decode.
+    InvalidEncoding(AffineDecodeError),
+}
+
+impl From<AffineDecodeError> for PreparedVkError {
+    fn from(err: AffineDecodeError) -> Self {
+        PreparedVkError::InvalidEncoding(err)
+    }
+}
+
+/// Writes `pvk` with its Miller loop precomputation, so that it can be loaded without
+/// preparing the key again.
+///
+/// The encoding is `PREPARED_VK_MAGIC || PREPARED_VK_VERSION || len(vk) || vk ||
+/// alpha_g1_beta_g2 || gamma_g2_neg_pc || delta_g2_neg_pc`, with `vk` as written by
+/// `zcash::write_vk` and lengths as big-endian `u32`.
+pub fn write_prepared_vk(pvk: &Groth16PreparedVerifyingKey) -> Vec<u8> {
+    let vk = write_vk(&pvk.vk);
+    let mut result = Vec::new();
+    result.extend_from_slice(&PREPARED_VK_MAGIC);
+    result.push(PREPARED_VK_VERSION);
+    result.extend_from_slice(&(vk.len() as u32).to_be_bytes());
+    result.extend_from_slice(&vk);
+
+    let fq12 = &pvk.alpha_g1_beta_g2;
+    for fq6 in &[fq12.c0, fq12.c1] {
+        for fq2 in &[fq6.c0, fq6.c1, fq6.c2] {
+            write_fq2(fq2, &mut result);
+        }
+    }
+
+    write_g2_prepared(&pvk.gamma_g2_neg_pc, &mut result);
+    write_g2_prepared(&pvk.delta_g2_neg_pc, &mut result);
+    result
+}
+
+/// Reads a prepared verifying key written by `write_prepared_vk`.
+///
+/// Points of the key are validated, but the precomputed values are not checked against them,
+/// as that would cost as much as preparing the key. Only read keys from trusted storage.
+pub fn read_prepared_vk(data: &[u8]) -> Result<Groth16PreparedVerifyingKey, PreparedVkError> {
+    let mut data = data;
+    let header = take(&mut data, 5)?;
+    if header[..4] != PREPARED_VK_MAGIC || header[4] != PREPARED_VK_VERSION {
+        return Err(PreparedVkError::InvalidHeader);
+    }
+
+    let vk_len = read_u32(&mut data)?;
+    let vk = read_vk(take(&mut data, vk_len)?)?;
+
+    let mut fq6 = [Fq6::default(); 2];
+    for c in fq6.iter_mut() {
+        *c = Fq6::new(
+            read_fq2(&mut data)?,
+            read_fq2(&mut data)?,
+            read_fq2(&mut data)?,
+        );
+    }
+
+    let alpha_g1_beta_g2 = Fq12::new(fq6[0], fq6[1]);
+    let gamma_g2_neg_pc = read_g2_prepared(&mut data)?;
+    let delta_g2_neg_pc = read_g2_prepared(&mut data)?;
+    if !data.is_empty() {
+        return Err(PreparedVkError::InvalidLength);
+    }
+
+    Ok(Groth16PreparedVerifyingKey {
+        vk,
+        alpha_g1_beta_g2,
+        gamma_g2_neg_pc,
+        delta_g2_neg_pc,
+    })
+}
+
+fn write_fq2(fq2: &Fq2, out: &mut Vec<u8>) {
+    let mut buf = [0u8; 96];
+    fq2.write(&mut buf);
+    out.extend_from_slice(&buf);
+}
+
+fn write_g2_prepared(prepared: &G2Prepared, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(prepared.ell_coeffs.len() as u32).to_be_bytes());
+    for (a, b, c) in &prepared.ell_coeffs {
+        write_fq2(a, out);
+        write_fq2(b, out);
+        write_fq2(c, out);
+    }
+    out.push(prepared.infinity as u8);
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], PreparedVkError> {
+    if data.len() < len {
+        return Err(PreparedVkError::InvalidLength);
+    }
+
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn read_u32(data: &mut &[u8]) -> Result<usize, PreparedVkError> {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(take(data, 4)?);
+    Ok(u32::from_be_bytes(bytes) as usize)
+}
+
+fn read_fq2(data: &mut &[u8]) -> Result<Fq2, PreparedVkError> {
+    Ok(Fq2::read_slice(take(data, 96)?, false)?)
+}
+
+fn read_g2_prepared(data: &mut &[u8]) -> Result<G2Prepared, PreparedVkError> {
+    let len = read_u32(data)?;
+    // checked before allocating, so that a corrupted length can not exhaust memory
+    if data.len() / ELL_COEFF_SIZE < len {
+        return Err(PreparedVkError::InvalidLength);
+    }
+
+    let mut ell_coeffs = Vec::with_capacity(len);
+    for _ in 0..len {
+        ell_coeffs.push((read_fq2(data)?, read_fq2(data)?, read_fq2(data)?));
+    }
+
+    let infinity = match take(data, 1)?[0] {
+        0 => false,
+        1 => true,
+        _ => {
+            return Err(PreparedVkError::InvalidEncoding(
+                AffineDecodeError::InvalidFlags,
+            ))
+        }
+    };
+
+    Ok(G2Prepared {
+        ell_coeffs,
+        infinity,
+    })
+}
+
+#[cfg(all(test, feature = "spend-vk"))]
+mod tests {
+    use super::{read_prepared_vk, write_prepared_vk, PreparedVkError};
+    use crate::{zcash, Groth16PreparedVerifyingKey};
+
+    #[test]
+    fn test_prepared_vk_round_trip() {
+        let pvk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let data = write_prepared_vk(&pvk);
+
+        let read = read_prepared_vk(&data).unwrap();
+        assert_eq!(read.alpha_g1_beta_g2, pvk.alpha_g1_beta_g2);
+        assert_eq!(write_prepared_vk(&read), data);
+
+        assert_eq!(
+            read_prepared_vk(&data[..data.len() - 1]).err(),
+            Some(PreparedVkError::InvalidLength)
+        );
+
+        let mut invalid = data.clone();
+        invalid[4] += 1;
+        assert_eq!(
+            read_prepared_vk(&invalid).err(),
+            Some(PreparedVkError::InvalidHeader)
+        );
+
+        let mut invalid = data;
+        invalid.push(0);
+        assert_eq!(
+            read_prepared_vk(&invalid).err(),
+            Some(PreparedVkError::InvalidLength)
+        );
+    }
+}
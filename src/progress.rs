@@ -0,0 +1,25 @@
+/// Stage of a verification reported to `Progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStage {
+    /// Structural checks and signatures of the descriptions, one item per description.
+    Descriptions,
+    /// Proof verification, one item per verified proof.
+    Proofs,
+}
+
+/// Receives progress of long verifications, e.g. to update a UI.
+pub trait Progress {
+    /// Called after each item of `stage`, with the number of items completed so far.
+    fn report(&mut self, stage: ProgressStage, completed: usize, total: usize);
+}
+
+/// Ignores progress.
+impl Progress for () {
+    fn report(&mut self, _stage: ProgressStage, _completed: usize, _total: usize) {}
+}
+
+impl<F: FnMut(ProgressStage, usize, usize)> Progress for F {
+    fn report(&mut self, stage: ProgressStage, completed: usize, total: usize) {
+        self(stage, completed, total)
+    }
+}
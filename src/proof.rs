@@ -1,21 +1,658 @@
-use crate::affine;
-use algebra::Bls12_381;
-use groth16::Proof;
-
-pub fn read_proof(proof: [u8; 192]) -> Result<Proof<Bls12_381>, ()> {
-    let mut a = [0u8; 48];
-    let mut b = [0u8; 96];
-    let mut c = [0u8; 48];
-
-    a.copy_from_slice(&proof[..48]);
-    b.copy_from_slice(&proof[48..48 + 96]);
-    c.copy_from_slice(&proof[48 + 96..]);
-
-    let proof = Proof {
-        a: affine::read_compressed_g1affine(a)?,
-        b: affine::read_compressed_g2affine(b)?,
-        c: affine::read_compressed_g1affine(c)?,
-    };
+use crate::{
+    affine::{self, AffineDecodeError, Infinity, SignConvention},
+    Error,
+};
+use algebra::{
+    bls12_381::{g1, g2, Fq12, Fr, G1Affine, G1Projective},
+    prelude::{One, Zero},
+    AffineCurve, BigInteger256, Bls12_381, Field, PairingEngine, PrimeField, ProjectiveCurve,
+};
+use alloc::vec::Vec;
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+use groth16::{PreparedVerifyingKey, Proof};
+use rand_core::{CryptoRng, RngCore};
+
+/// Size of a compressed Groth16 proof.
+pub const PROOF_SIZE: usize = 48 + 96 + 48;
+
+/// Size of an uncompressed Groth16 proof, as written by bellman's `Proof::write_uncompressed`.
+pub const UNCOMPRESSED_PROOF_SIZE: usize = 2 * PROOF_SIZE;
+
+/// Reason for rejecting a proof encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofDecodeError {
+    /// The data is shorter than the encoding being read, `PROOF_SIZE` or
+    /// `UNCOMPRESSED_PROOF_SIZE` bytes, or, for `read_proof_bytes`, is not exactly one of
+    /// these lengths.
+    InvalidLength,
+    /// The `a` element failed to decode.
+    A(AffineDecodeError),
+    /// The `b` element failed to decode.
+    B(AffineDecodeError),
+    /// The `c` element failed to decode.
+    C(AffineDecodeError),
+}
+
+pub fn read_proof(proof: [u8; 192]) -> Result<Proof<Bls12_381>, ProofDecodeError> {
+    read_proof_slice(&proof).map(|(proof, _)| proof)
+}
+
+/// Reads a compressed proof from the beginning of `data`.
+///
+/// Returns the proof and the number of bytes consumed.
+pub fn read_proof_slice(data: &[u8]) -> Result<(Proof<Bls12_381>, usize), ProofDecodeError> {
+    if data.len() < PROOF_SIZE {
+        return Err(ProofDecodeError::InvalidLength);
+    }
 
+    // proof elements are never allowed to be the point at infinity
+    let (infinity, convention) = (Infinity::Reject, SignConvention::Lexicographic);
+    let (a, a_len) =
+        affine::read_compressed_affine_slice::<g1::Parameters>(data, infinity, convention)
+            .map_err(ProofDecodeError::A)?;
+    let (b, b_len) = affine::read_compressed_affine_slice::<g2::Parameters>(
+        &data[a_len..],
+        infinity,
+        convention,
+    )
+    .map_err(ProofDecodeError::B)?;
+    let (c, c_len) = affine::read_compressed_affine_slice::<g1::Parameters>(
+        &data[a_len + b_len..],
+        infinity,
+        convention,
+    )
+    .map_err(ProofDecodeError::C)?;
+
+    Ok((Proof { a, b, c }, a_len + b_len + c_len))
+}
+
+/// Writes `proof` in the compressed encoding accepted by `read_proof`.
+pub fn write_proof(proof: &Proof<Bls12_381>) -> [u8; 192] {
+    let mut result = [0u8; 192];
+    result[..48].copy_from_slice(&affine::write_compressed_g1affine(&proof.a));
+    result[48..144].copy_from_slice(&affine::write_compressed_g2affine(&proof.b));
+    result[144..].copy_from_slice(&affine::write_compressed_g1affine(&proof.c));
+    result
+}
+
+/// Reads a proof in either encoding, told apart by the length of `data`.
+///
+/// `data` must be exactly `PROOF_SIZE` or `UNCOMPRESSED_PROOF_SIZE` bytes long.
+pub fn read_proof_bytes(data: &[u8]) -> Result<Proof<Bls12_381>, ProofDecodeError> {
+    let (proof, len) = match data.len() {
+        PROOF_SIZE => read_proof_slice(data)?,
+        UNCOMPRESSED_PROOF_SIZE => read_uncompressed_proof_slice(data)?,
+        _ => return Err(ProofDecodeError::InvalidLength),
+    };
+    debug_assert_eq!(len, data.len());
     Ok(proof)
 }
+
+pub fn read_uncompressed_proof(
+    proof: &[u8; UNCOMPRESSED_PROOF_SIZE],
+) -> Result<Proof<Bls12_381>, ProofDecodeError> {
+    read_uncompressed_proof_slice(proof).map(|(proof, _)| proof)
+}
+
+/// Reads an uncompressed proof from the beginning of `data`.
+///
+/// Returns the proof and the number of bytes consumed.
+pub fn read_uncompressed_proof_slice(
+    data: &[u8],
+) -> Result<(Proof<Bls12_381>, usize), ProofDecodeError> {
+    if data.len() < UNCOMPRESSED_PROOF_SIZE {
+        return Err(ProofDecodeError::InvalidLength);
+    }
+
+    // proof elements are never allowed to be the point at infinity
+    let infinity = Infinity::Reject;
+    let (a, a_len) =
+        affine::read_affine_slice::<g1::Parameters>(data, infinity).map_err(ProofDecodeError::A)?;
+    let (b, b_len) = affine::read_affine_slice::<g2::Parameters>(&data[a_len..], infinity)
+        .map_err(ProofDecodeError::B)?;
+    let (c, c_len) = affine::read_affine_slice::<g1::Parameters>(&data[a_len + b_len..], infinity)
+        .map_err(ProofDecodeError::C)?;
+
+    Ok((Proof { a, b, c }, a_len + b_len + c_len))
+}
+
+/// Writes `proof` in the uncompressed encoding accepted by `read_uncompressed_proof`.
+pub fn write_uncompressed_proof(proof: &Proof<Bls12_381>) -> [u8; UNCOMPRESSED_PROOF_SIZE] {
+    let mut result = [0u8; UNCOMPRESSED_PROOF_SIZE];
+    result[..96].copy_from_slice(&affine::write_g1affine(&proof.a));
+    result[96..288].copy_from_slice(&affine::write_g2affine(&proof.b));
+    result[288..].copy_from_slice(&affine::write_g1affine(&proof.c));
+    result
+}
+
+/// A decoded proof that compares and hashes by its canonical encoding.
+///
+/// Suitable as a key in deduplication caches and sets.
+#[derive(Clone)]
+pub struct SaplingProof {
+    proof: Proof<Bls12_381>,
+    encoded: [u8; 192],
+}
+
+impl SaplingProof {
+    pub fn read(data: [u8; 192]) -> Result<Self, ProofDecodeError> {
+        read_proof(data).map(Self::new)
+    }
+
+    /// Same as `read` for a proof at the beginning of `data`.
+    pub fn read_slice(data: &[u8]) -> Result<(Self, usize), ProofDecodeError> {
+        read_proof_slice(data).map(|(proof, len)| (Self::new(proof), len))
+    }
+
+    pub fn new(proof: Proof<Bls12_381>) -> Self {
+        let encoded = write_proof(&proof);
+        SaplingProof { proof, encoded }
+    }
+
+    pub fn proof(&self) -> &Proof<Bls12_381> {
+        &self.proof
+    }
+
+    pub fn to_bytes(&self) -> [u8; 192] {
+        self.encoded
+    }
+}
+
+impl From<SaplingProof> for Proof<Bls12_381> {
+    fn from(proof: SaplingProof) -> Self {
+        proof.proof
+    }
+}
+
+impl PartialEq for SaplingProof {
+    fn eq(&self, other: &Self) -> bool {
+        self.encoded[..] == other.encoded[..]
+    }
+}
+
+impl Eq for SaplingProof {}
+
+impl Hash for SaplingProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.encoded[..].hash(state)
+    }
+}
+
+impl fmt::Debug for SaplingProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SaplingProof")
+            .field(&&self.encoded[..])
+            .finish()
+    }
+}
+
+/// Heavy group operations of proof verification, so that accelerators for multi-scalar
+/// multiplication and Miller loops can be plugged in.
+///
+/// `CpuBackend` is the pure software implementation used by default.
+pub trait ArithmeticBackend {
+    /// Computes `sum(scalars[i] * bases[i])`. Both slices have the same length.
+    fn msm(&self, bases: &[G1Affine], scalars: &[Fr]) -> G1Projective;
+
+    /// Computes the product of the Miller loops of all pairs, before final exponentiation.
+    fn multi_miller_loop(&self, pairs: &[(G1Prepared, G2Prepared)]) -> Fq12;
+}
+
+/// Prepared G1 element of a Miller loop pair.
+pub type G1Prepared = <Bls12_381 as PairingEngine>::G1Prepared;
+/// Prepared G2 element of a Miller loop pair.
+pub type G2Prepared = <Bls12_381 as PairingEngine>::G2Prepared;
+
+/// Software implementation of `ArithmeticBackend`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CpuBackend;
+
+impl ArithmeticBackend for CpuBackend {
+    fn msm(&self, bases: &[G1Affine], scalars: &[Fr]) -> G1Projective {
+        let mut acc = G1Projective::zero();
+        for (base, scalar) in bases.iter().zip(scalars.iter()) {
+            acc += &base.mul(scalar.into_repr());
+        }
+        acc
+    }
+
+    fn multi_miller_loop(&self, pairs: &[(G1Prepared, G2Prepared)]) -> Fq12 {
+        Bls12_381::miller_loop(pairs.iter())
+    }
+}
+
+/// Accumulates `public_inputs` against the `gamma_abc_g1` bases of the verifying key.
+///
+/// The result depends only on the key and the inputs, so callers verifying the same
+/// description more than once can cache it and use `verify_proof_with_prepared_inputs`.
+pub fn prepare_inputs(
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    public_inputs: &[Fr],
+) -> Result<G1Projective, Error> {
+    prepare_inputs_with_backend(pvk, public_inputs, &CpuBackend)
+}
+
+/// Same as `prepare_inputs`, computing the multi-scalar multiplication with `backend`.
+pub fn prepare_inputs_with_backend<B: ArithmeticBackend + ?Sized>(
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    public_inputs: &[Fr],
+    backend: &B,
+) -> Result<G1Projective, Error> {
+    let bases = &pvk.vk.gamma_abc_g1;
+    if public_inputs.len() + 1 != bases.len() {
+        return Err(Error::InvalidPublicInputs);
+    }
+
+    Ok(bases[0].into_projective() + &backend.msm(&bases[1..], public_inputs))
+}
+
+/// Verifies `proof` against inputs already accumulated by `prepare_inputs`.
+pub fn verify_proof_with_prepared_inputs(
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    proof: &Proof<Bls12_381>,
+    prepared_inputs: &G1Projective,
+) -> Result<bool, Error> {
+    let qap = Bls12_381::miller_loop(
+        [
+            (proof.a.into(), proof.b.into()),
+            (
+                prepared_inputs.into_affine().into(),
+                pvk.gamma_g2_neg_pc.clone(),
+            ),
+            (proof.c.into(), pvk.delta_g2_neg_pc.clone()),
+        ]
+        .iter(),
+    );
+
+    // the miller loop output of a valid proof is never zero
+    let test = match Bls12_381::final_exponentiation(&qap) {
+        Some(test) => test,
+        None => return Ok(false),
+    };
+
+    Ok(test == pvk.alpha_g1_beta_g2)
+}
+
+/// Verifies several proofs, each with inputs accumulated by `prepare_inputs`, sharing a single
+/// Miller loop and final exponentiation.
+///
+/// Every check is weighted by a random 128-bit scalar drawn from `rng`. Without the weights the
+/// errors of two invalid proofs could cancel out, and weights a prover can predict allow the
+/// same. Only reports whether all proofs are valid.
+pub fn verify_proofs_combined<R: RngCore + CryptoRng>(
+    items: &[(
+        &PreparedVerifyingKey<Bls12_381>,
+        Proof<Bls12_381>,
+        G1Projective,
+    )],
+    rng: &mut R,
+) -> Result<bool, Error> {
+    verify_proofs_combined_with_backend(items, rng, &CpuBackend)
+}
+
+/// Same as `verify_proofs_combined`, running the group operations on `backend`.
+pub fn verify_proofs_combined_with_backend<R, B>(
+    items: &[(
+        &PreparedVerifyingKey<Bls12_381>,
+        Proof<Bls12_381>,
+        G1Projective,
+    )],
+    rng: &mut R,
+    backend: &B,
+) -> Result<bool, Error>
+where
+    R: RngCore + CryptoRng,
+    B: ArithmeticBackend + ?Sized,
+{
+    let mut pairs = Vec::with_capacity(items.len() * 3);
+    let mut expected = Fq12::one();
+    for (pvk, proof, prepared_inputs) in items {
+        let weight = BigInteger256([rng.next_u64(), rng.next_u64(), 0, 0]);
+        let scalar = [Fr::from_repr(weight)];
+        let weighted =
+            |point: G1Affine| -> G1Prepared { backend.msm(&[point], &scalar).into_affine().into() };
+
+        pairs.push((weighted(proof.a), proof.b.into()));
+        pairs.push((
+            weighted(prepared_inputs.into_affine()),
+            pvk.gamma_g2_neg_pc.clone(),
+        ));
+        pairs.push((weighted(proof.c), pvk.delta_g2_neg_pc.clone()));
+
+        expected *= &pvk.alpha_g1_beta_g2.pow(weight);
+    }
+
+    let qap = backend.multi_miller_loop(&pairs);
+    let test = match Bls12_381::final_exponentiation(&qap) {
+        Some(test) => test,
+        None => return Ok(false),
+    };
+
+    Ok(test == expected)
+}
+
+/// Reason `verify_sapling_proof` could not decide whether a proof is valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyProofError {
+    /// The proof bytes failed to decode.
+    Decode(ProofDecodeError),
+    /// The number of public inputs does not match the verifying key.
+    InvalidInputs,
+}
+
+/// Decodes a compressed proof and verifies it against `public_inputs`.
+pub fn verify_sapling_proof(
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    proof: &[u8; 192],
+    public_inputs: &[Fr],
+) -> Result<bool, VerifyProofError> {
+    let proof = read_proof(*proof).map_err(VerifyProofError::Decode)?;
+    verify_proof(pvk, &proof, public_inputs).map_err(|_| VerifyProofError::InvalidInputs)
+}
+
+/// Verifies `proof` against `public_inputs`.
+pub fn verify_proof(
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    proof: &Proof<Bls12_381>,
+    public_inputs: &[Fr],
+) -> Result<bool, Error> {
+    let prepared_inputs = prepare_inputs(pvk, public_inputs)?;
+    verify_proof_with_prepared_inputs(pvk, proof, &prepared_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "output-vk")]
+    use super::prepare_inputs;
+    use super::{
+        read_proof, read_proof_bytes, read_proof_slice, read_uncompressed_proof,
+        read_uncompressed_proof_slice, write_proof, write_uncompressed_proof, ProofDecodeError,
+        SaplingProof, PROOF_SIZE, UNCOMPRESSED_PROOF_SIZE,
+    };
+    #[cfg(feature = "spend-vk")]
+    use super::{verify_sapling_proof, VerifyProofError};
+    use crate::affine::AffineDecodeError;
+    #[cfg(feature = "output-vk")]
+    use algebra::AffineCurve;
+    #[cfg(any(feature = "spend-vk", feature = "output-vk"))]
+    use algebra::{bls12_381::Fr, prelude::Zero};
+    use alloc::vec::Vec;
+    use core::hash::{Hash, Hasher};
+    use hex_literal::hex;
+
+    const PROOF: [u8; 192] = hex!("8e6c30876e36a18d8d935238815c8d9205a4f1f523ff76b51f614bff1064d1c5fa0a27ec0c43c8a6c2714e7234d32e9a8934a3e9c0f74f1fdac2ddf6be3b13bc933b0478cae556a2d387cc23b05e8b0bd53d9e838ad2d2cb31daccefe256087511b044dfae665f0af0fa968edeea4cbb437a8099724159471adf7946eec434cccc1129f4d1e31d7f3f8be524226c65f28897d3604c14efb64bea6a889b2705617432927229dfa382e78c0ace31cc158fbf3ec1597242955e45af1ee5cfaffd78");
+
+    #[test]
+    fn test_read_proof_slice() {
+        let proof = read_proof(PROOF).unwrap();
+
+        let mut data = PROOF.to_vec();
+        data.extend_from_slice(&[0xff; 10]);
+        let (sliced, len) = read_proof_slice(&data).unwrap();
+        assert_eq!(len, PROOF_SIZE);
+        assert_eq!(sliced.a, proof.a);
+        assert_eq!(sliced.b, proof.b);
+        assert_eq!(sliced.c, proof.c);
+
+        assert_eq!(
+            read_proof_slice(&PROOF[..191]).err(),
+            Some(ProofDecodeError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_read_proof_reports_component() {
+        let mut proof = PROOF;
+        proof[0] &= 0x7f;
+        assert_eq!(
+            read_proof(proof).err(),
+            Some(ProofDecodeError::A(AffineDecodeError::InvalidFlags))
+        );
+
+        let mut proof = PROOF;
+        proof[48] = 0xc0;
+        assert_eq!(
+            read_proof(proof).err(),
+            Some(ProofDecodeError::B(AffineDecodeError::UnexpectedInfinity))
+        );
+
+        let mut proof = PROOF;
+        proof[144] &= 0x7f;
+        assert_eq!(
+            read_proof(proof).err(),
+            Some(ProofDecodeError::C(AffineDecodeError::InvalidFlags))
+        );
+    }
+
+    #[test]
+    fn test_write_proof() {
+        assert_eq!(write_proof(&read_proof(PROOF).unwrap())[..], PROOF[..]);
+    }
+
+    #[test]
+    fn test_uncompressed_proof() {
+        let proof = read_proof(PROOF).unwrap();
+        let uncompressed = write_uncompressed_proof(&proof);
+        let decoded = read_uncompressed_proof(&uncompressed).unwrap();
+        assert_eq!(write_proof(&decoded)[..], PROOF[..]);
+
+        let mut data = uncompressed.to_vec();
+        data.push(0xff);
+        let (sliced, len) = read_uncompressed_proof_slice(&data).unwrap();
+        assert_eq!(len, UNCOMPRESSED_PROOF_SIZE);
+        assert_eq!(write_proof(&sliced)[..], PROOF[..]);
+
+        // the encodings are told apart by their length only
+        assert_eq!(
+            write_proof(&read_proof_bytes(&PROOF).unwrap())[..],
+            PROOF[..]
+        );
+        assert_eq!(
+            write_proof(&read_proof_bytes(&uncompressed).unwrap())[..],
+            PROOF[..]
+        );
+        assert_eq!(
+            read_proof_bytes(&data).err(),
+            Some(ProofDecodeError::InvalidLength)
+        );
+
+        // b is never the point at infinity
+        let mut infinity = uncompressed;
+        infinity[96..288].copy_from_slice(&[0u8; 192]);
+        infinity[96] = 0x40;
+        assert_eq!(
+            read_uncompressed_proof(&infinity).err(),
+            Some(ProofDecodeError::B(AffineDecodeError::UnexpectedInfinity))
+        );
+    }
+
+    #[test]
+    fn test_sapling_proof_eq() {
+        let a = SaplingProof::read(PROOF).unwrap();
+        let b = SaplingProof::new(read_proof(PROOF).unwrap());
+        assert_eq!(a, b);
+        assert_eq!(a.to_bytes()[..], PROOF[..]);
+
+        // swapping a and c produces a different, still valid proof
+        let mut swapped = PROOF;
+        swapped[..48].copy_from_slice(&PROOF[144..]);
+        swapped[144..].copy_from_slice(&PROOF[..48]);
+        let c = SaplingProof::read(swapped).unwrap();
+        assert_ne!(a, c);
+
+        assert_eq!(hash_bytes(&a), hash_bytes(&b));
+        assert_ne!(hash_bytes(&a), hash_bytes(&c));
+    }
+
+    fn hash_bytes(proof: &SaplingProof) -> Vec<u8> {
+        struct Recorder(Vec<u8>);
+
+        impl Hasher for Recorder {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.extend_from_slice(bytes);
+            }
+        }
+
+        let mut recorder = Recorder(Vec::new());
+        proof.hash(&mut recorder);
+        recorder.0
+    }
+
+    #[test]
+    #[cfg(feature = "output-vk")]
+    fn test_prepare_inputs_with_backend() {
+        use super::{prepare_inputs_with_backend, ArithmeticBackend, CpuBackend};
+        use super::{G1Prepared, G2Prepared};
+        use crate::{zcash, Groth16PreparedVerifyingKey};
+        use algebra::bls12_381::{Fq12, G1Affine, G1Projective};
+        use core::cell::Cell;
+
+        // counts calls and delegates to the software implementation
+        struct Counting(Cell<usize>);
+
+        impl ArithmeticBackend for Counting {
+            fn msm(&self, bases: &[G1Affine], scalars: &[Fr]) -> G1Projective {
+                self.0.set(self.0.get() + 1);
+                CpuBackend.msm(bases, scalars)
+            }
+
+            fn multi_miller_loop(&self, pairs: &[(G1Prepared, G2Prepared)]) -> Fq12 {
+                CpuBackend.multi_miller_loop(pairs)
+            }
+        }
+
+        let pvk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let inputs = [
+            Fr::from(1u64),
+            Fr::from(2u64),
+            Fr::from(3u64),
+            Fr::from(4u64),
+            Fr::zero(),
+        ];
+        let backend = Counting(Cell::new(0));
+        assert_eq!(
+            prepare_inputs_with_backend(&pvk, &inputs, &backend),
+            prepare_inputs(&pvk, &inputs)
+        );
+        assert_eq!(backend.0.get(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "output-vk")]
+    fn test_prepare_inputs() {
+        use crate::{zcash, Groth16PreparedVerifyingKey};
+
+        let pvk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+
+        // the output circuit has five public inputs
+        assert!(prepare_inputs(&pvk, &[Fr::zero(); 4]).is_err());
+        assert!(prepare_inputs(&pvk, &[Fr::zero(); 6]).is_err());
+
+        let prepared = prepare_inputs(&pvk, &[Fr::zero(); 5]).unwrap();
+        assert_eq!(prepared, pvk.vk.gamma_abc_g1[0].into_projective());
+    }
+
+    #[test]
+    #[cfg(all(feature = "verify-sigs", feature = "spend-vk", feature = "output-vk"))]
+    fn test_verify_proof_with_prepared_inputs() {
+        use super::{prepare_inputs, verify_proof_with_prepared_inputs};
+        use crate::{
+            spend_public_inputs, tests::mainnet_sapling, zcash, Groth16PreparedVerifyingKey,
+            VerificationPolicy,
+        };
+
+        let pvk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let (sapling, _) = mainnet_sapling();
+        let spend = &sapling.spends[0];
+        assert_eq!(spend.zkproof[..], PROOF[..]);
+
+        let public_input = spend_public_inputs(&VerificationPolicy::strict(), spend).unwrap();
+        let prepared = prepare_inputs(&pvk, &public_input).unwrap();
+        let proof = read_proof(PROOF).unwrap();
+        assert_eq!(
+            verify_proof_with_prepared_inputs(&pvk, &proof, &prepared),
+            Ok(true)
+        );
+
+        // inputs prepared for another spend
+        let mut other_input = public_input;
+        other_input[6] = Fr::zero();
+        let other = prepare_inputs(&pvk, &other_input).unwrap();
+        assert_eq!(
+            verify_proof_with_prepared_inputs(&pvk, &proof, &other),
+            Ok(false)
+        );
+
+        // a and c swapped
+        let mut swapped = PROOF;
+        swapped[..48].copy_from_slice(&PROOF[144..]);
+        swapped[144..].copy_from_slice(&PROOF[..48]);
+        let swapped = read_proof(swapped).unwrap();
+        assert_eq!(
+            verify_proof_with_prepared_inputs(&pvk, &swapped, &prepared),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "verify-sigs", feature = "spend-vk", feature = "output-vk"))]
+    fn test_verify_sapling_proof_valid() {
+        use crate::{
+            output_public_inputs, spend_public_inputs, tests::mainnet_sapling, zcash,
+            Groth16PreparedVerifyingKey, VerificationPolicy,
+        };
+
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+        let (sapling, _) = mainnet_sapling();
+
+        let spend = &sapling.spends[0];
+        let public_input = spend_public_inputs(&policy, spend).unwrap();
+        assert_eq!(
+            verify_sapling_proof(&spend_vk, &spend.zkproof, &public_input),
+            Ok(true)
+        );
+
+        let output = &sapling.outputs[0];
+        let public_input = output_public_inputs(&policy, output).unwrap();
+        assert_eq!(
+            verify_sapling_proof(&output_vk, &output.zkproof, &public_input),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "spend-vk")]
+    fn test_verify_sapling_proof() {
+        use crate::{zcash, Groth16PreparedVerifyingKey};
+
+        let pvk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+
+        // a mainnet spend proof, checked against the wrong inputs
+        assert_eq!(
+            verify_sapling_proof(&pvk, &PROOF, &[Fr::zero(); 7]),
+            Ok(false)
+        );
+        assert_eq!(
+            verify_sapling_proof(&pvk, &PROOF, &[Fr::zero(); 5]),
+            Err(VerifyProofError::InvalidInputs)
+        );
+
+        let mut proof = PROOF;
+        proof[0] &= 0x7f;
+        assert_eq!(
+            verify_sapling_proof(&pvk, &proof, &[Fr::zero(); 7]),
+            Err(VerifyProofError::Decode(ProofDecodeError::A(
+                AffineDecodeError::InvalidFlags
+            )))
+        );
+    }
+}
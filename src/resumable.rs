@@ -0,0 +1,201 @@
+use crate::{
+    accept_binding_sig, accept_output_with_transcript, accept_spend_with_transcript, CvSum, Error,
+    Groth16PreparedVerifyingKey, Sapling, VerificationPolicy,
+};
+
+/// Size of an encoded `ResumableVerifier`.
+pub const RESUMABLE_STATE_SIZE: usize = 32 + 4 + 4 + 32;
+
+/// Reason `ResumableVerifier::step` did not make progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeError {
+    /// The bundle is invalid.
+    Invalid(Error),
+    /// The sighash differs from the one the verifier was started with.
+    WrongBundle,
+}
+
+impl From<Error> for ResumeError {
+    fn from(err: Error) -> Self {
+        ResumeError::Invalid(err)
+    }
+}
+
+/// Verifier of a single bundle that can run in slices of a few descriptions at a time, for
+/// environments with a time budget per call.
+///
+/// The state after each slice can be persisted with `to_bytes` and resumed later with
+/// `from_bytes`. The state records which descriptions were already accepted, so it must be kept
+/// where it can not be tampered with. It is tied to the sighash of the bundle, which commits to
+/// every description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumableVerifier {
+    sighash: [u8; 32],
+    spends_done: u32,
+    outputs_done: u32,
+    total: CvSum,
+}
+
+impl ResumableVerifier {
+    /// Starts verifying the bundle signed with `sighash`.
+    pub fn new(sighash: [u8; 32]) -> Self {
+        ResumableVerifier {
+            sighash,
+            spends_done: 0,
+            outputs_done: 0,
+            total: CvSum::new(),
+        }
+    }
+
+    /// Verifies at most `budget` further descriptions of `sapling`, spends first.
+    ///
+    /// Returns `Ok(true)` once every description and the binding signature were accepted and
+    /// `Ok(false)` if work remains. Every call must pass the same bundle and parameters, a
+    /// sighash other than the one passed to `new` fails with `ResumeError::WrongBundle`.
+    pub fn step(
+        &mut self,
+        policy: &VerificationPolicy,
+        spend_vk: &Groth16PreparedVerifyingKey,
+        output_vk: &Groth16PreparedVerifyingKey,
+        sighash: &[u8; 32],
+        sapling: &Sapling,
+        budget: usize,
+    ) -> Result<bool, ResumeError> {
+        if *sighash != self.sighash {
+            return Err(ResumeError::WrongBundle);
+        }
+
+        let mut remaining = budget;
+        while remaining > 0 {
+            if let Some(spend) = sapling.spends.get(self.spends_done as usize) {
+                let index = self.spends_done as usize;
+                accept_spend_with_transcript(
+                    policy,
+                    spend_vk,
+                    sighash,
+                    &mut self.total,
+                    spend,
+                    index,
+                    &mut (),
+                )?;
+                self.spends_done += 1;
+            } else if let Some(output) = sapling.outputs.get(self.outputs_done as usize) {
+                let index = self.outputs_done as usize;
+                accept_output_with_transcript(
+                    policy,
+                    output_vk,
+                    &mut self.total,
+                    output,
+                    index,
+                    &mut (),
+                )?;
+                self.outputs_done += 1;
+            } else {
+                break;
+            }
+            remaining -= 1;
+        }
+
+        if (self.spends_done as usize) < sapling.spends.len()
+            || (self.outputs_done as usize) < sapling.outputs.len()
+        {
+            return Ok(false);
+        }
+
+        accept_binding_sig(policy, sighash, self.total, sapling)?;
+        Ok(true)
+    }
+
+    /// Encodes the state as `sighash || spends_done || outputs_done || total`, counts as
+    /// little-endian `u32`.
+    pub fn to_bytes(&self) -> [u8; RESUMABLE_STATE_SIZE] {
+        let mut result = [0u8; RESUMABLE_STATE_SIZE];
+        result[..32].copy_from_slice(&self.sighash);
+        result[32..36].copy_from_slice(&self.spends_done.to_le_bytes());
+        result[36..40].copy_from_slice(&self.outputs_done.to_le_bytes());
+        result[40..].copy_from_slice(&self.total.to_bytes());
+        result
+    }
+
+    pub fn from_bytes(data: &[u8; RESUMABLE_STATE_SIZE]) -> Result<Self, ()> {
+        let mut sighash = [0u8; 32];
+        sighash.copy_from_slice(&data[..32]);
+        let mut spends_done = [0u8; 4];
+        spends_done.copy_from_slice(&data[32..36]);
+        let mut outputs_done = [0u8; 4];
+        outputs_done.copy_from_slice(&data[36..40]);
+        let mut total = [0u8; 32];
+        total.copy_from_slice(&data[40..]);
+
+        Ok(ResumableVerifier {
+            sighash,
+            spends_done: u32::from_le_bytes(spends_done),
+            outputs_done: u32::from_le_bytes(outputs_done),
+            total: CvSum::from_bytes(&total)?,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::{ResumableVerifier, ResumeError};
+    use crate::{
+        tests::mainnet_sapling, zcash, Error, Groth16PreparedVerifyingKey, VerificationPolicy,
+    };
+
+    #[test]
+    fn test_resume_after_each_description() {
+        let (sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        let mut verifier = ResumableVerifier::new(sighash);
+        let mut steps = 0;
+        loop {
+            // persist and restore the state between slices
+            verifier = ResumableVerifier::from_bytes(&verifier.to_bytes()).unwrap();
+            steps += 1;
+            let done = verifier
+                .step(&policy, &spend_vk, &output_vk, &sighash, &sapling, 1)
+                .unwrap();
+            if done {
+                break;
+            }
+        }
+        assert_eq!(steps, 2);
+
+        let mut verifier = ResumableVerifier::new([0u8; 32]);
+        assert_eq!(
+            verifier.step(&policy, &spend_vk, &output_vk, &[0u8; 32], &sapling, 10),
+            Err(ResumeError::Invalid(Error::SpendAuthSigInvalid))
+        );
+    }
+
+    #[test]
+    fn test_resume_other_bundle() {
+        let (sapling, sighash) = mainnet_sapling();
+        let spend_vk: Groth16PreparedVerifyingKey = zcash::spend_vk().into();
+        let output_vk: Groth16PreparedVerifyingKey = zcash::output_vk().into();
+        let policy = VerificationPolicy::strict();
+
+        let mut verifier = ResumableVerifier::new(sighash);
+        assert_eq!(
+            verifier.step(&policy, &spend_vk, &output_vk, &sighash, &sapling, 1),
+            Ok(false)
+        );
+
+        // a persisted state only resumes the bundle it was started with
+        let mut other = [0u8; 32];
+        other[0] = 1;
+        let mut verifier = ResumableVerifier::from_bytes(&verifier.to_bytes()).unwrap();
+        assert_eq!(
+            verifier.step(&policy, &spend_vk, &output_vk, &other, &sapling, 1),
+            Err(ResumeError::WrongBundle)
+        );
+        assert_eq!(
+            verifier.step(&policy, &spend_vk, &output_vk, &sighash, &sapling, 1),
+            Ok(true)
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    check_spend_commitments, derive_binding_verification_key, read_non_small_order_point,
+    read_randomized_key, read_signature, CvSum, Error, Sapling, VerificationPolicy,
+};
+use rand_core::{CryptoRng, RngCore};
+use redjubjub::{batch, Binding, SpendAuth, VerificationKeyBytes};
+
+/// Accumulator of spend authorization and binding signatures, e.g. of a whole block, verified
+/// with a single multi-scalar multiplication.
+///
+/// Every queued signature is weighted by a random scalar, so that errors of invalid signatures
+/// can not cancel out. A failed `verify` does not tell which signature is invalid.
+///
+/// The check is delegated to the `redjubjub` crate, which rejects encodings of `R` and `S`
+/// that are not canonical regardless of the policy used to queue them.
+#[derive(Default)]
+pub struct SignatureBatch {
+    verifier: batch::Verifier,
+    len: usize,
+}
+
+impl SignatureBatch {
+    pub fn new() -> Self {
+        SignatureBatch::default()
+    }
+
+    /// Queues the spend authorization signature of a spend with randomized key
+    /// `randomized_key`.
+    pub fn queue_spend_auth_sig(
+        &mut self,
+        randomized_key: &[u8; 32],
+        sighash: &[u8; 32],
+        spend_auth_sig: &[u8; 64],
+    ) {
+        let key = VerificationKeyBytes::<SpendAuth>::from(*randomized_key);
+        let sig = redjubjub::Signature::<SpendAuth>::from(*spend_auth_sig);
+        self.verifier.queue((key, sig, sighash));
+        self.len += 1;
+    }
+
+    /// Queues a binding signature made with the encoded binding verification key
+    /// `binding_verification_key`.
+    pub fn queue_binding_sig(
+        &mut self,
+        binding_verification_key: &[u8; 32],
+        sighash: &[u8; 32],
+        binding_sig: &[u8; 64],
+    ) {
+        let key = VerificationKeyBytes::<Binding>::from(*binding_verification_key);
+        let sig = redjubjub::Signature::<Binding>::from(*binding_sig);
+        self.verifier.queue((key, sig, sighash));
+        self.len += 1;
+    }
+
+    /// Checks the encodings of every signature and key of `sapling` and queues its signatures.
+    ///
+    /// Value commitments are checked too, as the binding verification key is derived from them.
+    /// The proofs are left to the caller. Nothing is queued if the bundle is rejected.
+    pub fn queue_sapling(
+        &mut self,
+        policy: &VerificationPolicy,
+        sighash: &[u8; 32],
+        sapling: &Sapling,
+    ) -> Result<(), Error> {
+        let mut total = CvSum::new();
+        for spend in sapling.spends.iter() {
+            check_spend_commitments(policy, &mut total, spend, &mut ())?;
+            read_randomized_key(policy, &spend.randomized_key)
+                .map_err(|_| Error::InvalidRandomizedKey)?;
+            read_signature(policy, &spend.spend_auth_sig)
+                .map_err(|_| Error::MalformedSpendAuthSig)?;
+        }
+
+        for output in sapling.outputs.iter() {
+            let value_commitment = read_non_small_order_point(policy, &output.value_commitment)
+                .map_err(|_| Error::InvalidOutputValueCommitment)?;
+            total.sub(&value_commitment);
+        }
+
+        let (_, data_to_be_signed) =
+            derive_binding_verification_key(sighash, total, sapling.balancing_value, &mut ())?;
+        read_signature(policy, &sapling.binding_sig).map_err(|_| Error::MalformedBindingSig)?;
+
+        for spend in sapling.spends.iter() {
+            self.queue_spend_auth_sig(&spend.randomized_key, sighash, &spend.spend_auth_sig);
+        }
+
+        let mut binding_verification_key = [0u8; 32];
+        binding_verification_key.copy_from_slice(&data_to_be_signed[..32]);
+        self.queue_binding_sig(&binding_verification_key, sighash, &sapling.binding_sig);
+        Ok(())
+    }
+
+    /// Number of queued signatures.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Verifies every queued signature. `rng` provides the weights of the combined check.
+    pub fn verify<R: RngCore + CryptoRng>(self, rng: &mut R) -> Result<(), Error> {
+        self.verifier
+            .verify(rng)
+            .map_err(|_| Error::CombinedSignaturesInvalid)
+    }
+}
+
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
+mod tests {
+    use super::SignatureBatch;
+    use crate::{
+        tests::{mainnet_sapling, test_rng},
+        Error, VerificationPolicy,
+    };
+
+    #[test]
+    fn test_signature_batch() {
+        let (mut sapling, sighash) = mainnet_sapling();
+        let policy = VerificationPolicy::strict();
+        let mut rng = test_rng();
+
+        let mut batch = SignatureBatch::new();
+        assert!(batch.is_empty());
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch.verify(&mut rng), Ok(()));
+
+        let mut batch = SignatureBatch::new();
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        sapling.binding_sig[40] ^= 1;
+        batch.queue_sapling(&policy, &sighash, &sapling).unwrap();
+        assert_eq!(
+            batch.verify(&mut rng),
+            Err(Error::CombinedSignaturesInvalid)
+        );
+    }
+}
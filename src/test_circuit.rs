@@ -0,0 +1,90 @@
+//! A tiny circuit proving knowledge of `a` and `b` with `a * b = c`, where `c` is the only
+//! public input.
+//!
+//! Only meant for exercising proof decoding and verification with freshly generated proofs.
+//! The parameters come from a random, untrusted setup.
+
+use algebra::{bls12_381::Fr, Bls12_381};
+use groth16::{create_random_proof, generate_random_parameters, Parameters, Proof};
+use r1cs_core::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+use rand_core::RngCore;
+
+/// Number of public inputs of the circuit.
+pub const PUBLIC_INPUTS: usize = 1;
+
+struct MulCircuit {
+    a: Option<Fr>,
+    b: Option<Fr>,
+    c: Option<Fr>,
+}
+
+impl ConstraintSynthesizer<Fr> for MulCircuit {
+    fn generate_constraints<CS: ConstraintSystem<Fr>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        let a = cs.alloc(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+        let b = cs.alloc(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+        let c = cs.alloc_input(|| "c", || self.c.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| "a * b = c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+        Ok(())
+    }
+}
+
+/// Runs a fresh random setup for the circuit.
+pub fn generate_parameters<R: RngCore>(rng: &mut R) -> Parameters<Bls12_381> {
+    let circuit = MulCircuit {
+        a: None,
+        b: None,
+        c: None,
+    };
+    generate_random_parameters(circuit, rng).expect("circuit is well formed")
+}
+
+/// Proves that `a * b = c`.
+///
+/// The prover does not check the witness, so passing `c != a * b` yields a well-formed proof
+/// that fails verification.
+pub fn prove<R: RngCore>(
+    params: &Parameters<Bls12_381>,
+    a: Fr,
+    b: Fr,
+    c: Fr,
+    rng: &mut R,
+) -> Proof<Bls12_381> {
+    let circuit = MulCircuit {
+        a: Some(a),
+        b: Some(b),
+        c: Some(c),
+    };
+    create_random_proof(circuit, params, rng).expect("all assignments are provided")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_parameters, prove, PUBLIC_INPUTS};
+    use crate::{proof, Groth16PreparedVerifyingKey};
+    use algebra::bls12_381::Fr;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_fresh_proofs() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        let params = generate_parameters(&mut rng);
+        let pvk: Groth16PreparedVerifyingKey = params.vk.clone().into();
+        assert_eq!(pvk.vk.gamma_abc_g1.len(), PUBLIC_INPUTS + 1);
+
+        let (a, b) = (Fr::from(3u64), Fr::from(5u64));
+        let valid = prove(&params, a, b, a * &b, &mut rng);
+
+        // round trip through the wire encoding before verifying
+        let encoded = proof::write_proof(&valid);
+        let decoded = proof::read_proof(encoded).unwrap();
+        assert_eq!(proof::verify_proof(&pvk, &decoded, &[a * &b]), Ok(true));
+        assert_eq!(proof::verify_proof(&pvk, &decoded, &[a]), Ok(false));
+
+        let invalid = prove(&params, a, b, a, &mut rng);
+        assert_eq!(proof::verify_proof(&pvk, &invalid, &[a]), Ok(false));
+    }
+}
@@ -0,0 +1,82 @@
+use alloc::vec::Vec;
+
+/// Collects every input and decision made during verification.
+///
+/// Records arrive in a fixed order for a given bundle, so two verifiers fed the same data
+/// produce identical transcripts.
+pub trait Transcript {
+    /// Appends a single labeled record.
+    fn append(&mut self, label: &'static str, data: &[u8]);
+
+    /// Records the outcome of the check named by `label`, as a single byte record.
+    fn decision(&mut self, label: &'static str, passed: bool) {
+        self.append(label, &[passed as u8]);
+    }
+}
+
+/// Transcript that discards everything.
+impl Transcript for () {
+    fn append(&mut self, _label: &'static str, _data: &[u8]) {}
+}
+
+/// Transcript keeping all records in memory in a canonical byte format.
+///
+/// Each record is written as `label_len || label || data_len || data` with lengths encoded as
+/// little-endian `u32`. The bytes can be hashed and compared across machines and versions.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct RecordingTranscript {
+    bytes: Vec<u8>,
+}
+
+impl RecordingTranscript {
+    pub fn new() -> Self {
+        RecordingTranscript::default()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl Transcript for RecordingTranscript {
+    fn append(&mut self, label: &'static str, data: &[u8]) {
+        self.bytes
+            .extend_from_slice(&(label.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(label.as_bytes());
+        self.bytes
+            .extend_from_slice(&(data.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(data);
+    }
+}
+
+/// Records whether `result` is a success under `label` and passes it through.
+#[cfg(feature = "verify-sigs")]
+pub(crate) fn record<T: Transcript, R, E>(
+    transcript: &mut T,
+    label: &'static str,
+    result: Result<R, E>,
+) -> Result<R, E> {
+    transcript.decision(label, result.is_ok());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecordingTranscript, Transcript};
+
+    #[test]
+    fn test_recording_transcript() {
+        let mut transcript = RecordingTranscript::new();
+        transcript.append("ab", &[1, 2, 3]);
+        transcript.append("c", &[0]);
+
+        assert_eq!(
+            transcript.as_bytes(),
+            &[2, 0, 0, 0, b'a', b'b', 3, 0, 0, 0, 1, 2, 3, 1, 0, 0, 0, b'c', 1, 0, 0, 0, 0][..]
+        );
+    }
+}
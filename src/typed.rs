@@ -0,0 +1,88 @@
+use crate::{
+    read_randomized_key, read_signature, Error, Sapling, SaplingOutputDescription,
+    SaplingSpendDescription, VerificationPolicy,
+};
+use alloc::vec::Vec;
+use zexe_redjubjub::{PublicKey, Signature};
+
+/// Spend description whose randomized key and spend authorization signature were parsed and
+/// validated on construction.
+pub struct TypedSpendDescription {
+    description: SaplingSpendDescription,
+    randomized_key: PublicKey,
+    spend_auth_sig: Signature,
+}
+
+impl TypedSpendDescription {
+    /// Parses the randomized key and the signature of `description` under `policy`.
+    pub fn new(
+        policy: &VerificationPolicy,
+        description: SaplingSpendDescription,
+    ) -> Result<Self, Error> {
+        let randomized_key = read_randomized_key(policy, &description.randomized_key)
+            .map_err(|_| Error::InvalidRandomizedKey)?;
+        let spend_auth_sig = read_signature(policy, &description.spend_auth_sig)
+            .map_err(|_| Error::MalformedSpendAuthSig)?;
+        Ok(TypedSpendDescription {
+            description,
+            randomized_key,
+            spend_auth_sig,
+        })
+    }
+
+    /// The raw description, as it was encoded in the transaction.
+    pub fn description(&self) -> &SaplingSpendDescription {
+        &self.description
+    }
+
+    pub fn randomized_key(&self) -> &PublicKey {
+        &self.randomized_key
+    }
+
+    pub fn spend_auth_sig(&self) -> &Signature {
+        &self.spend_auth_sig
+    }
+}
+
+impl SaplingSpendDescription {
+    /// Same as `TypedSpendDescription::new`.
+    pub fn parse(&self, policy: &VerificationPolicy) -> Result<TypedSpendDescription, Error> {
+        TypedSpendDescription::new(policy, self.clone())
+    }
+}
+
+/// Sapling bundle with parsed spend keys and signatures.
+///
+/// Verified with `accept_typed_sapling`.
+pub struct TypedSapling {
+    pub balancing_value: i64,
+    pub spends: Vec<TypedSpendDescription>,
+    pub outputs: Vec<SaplingOutputDescription>,
+    pub binding_sig: Signature,
+}
+
+impl TypedSapling {
+    /// Parses every key and signature of `sapling` under `policy`.
+    pub fn new(policy: &VerificationPolicy, sapling: &Sapling) -> Result<Self, Error> {
+        let spends = sapling
+            .spends
+            .iter()
+            .map(|spend| TypedSpendDescription::new(policy, spend.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TypedSapling {
+            balancing_value: sapling.balancing_value,
+            spends,
+            outputs: sapling.outputs.clone(),
+            binding_sig: read_signature(policy, &sapling.binding_sig)
+                .map_err(|_| Error::MalformedBindingSig)?,
+        })
+    }
+}
+
+impl Sapling {
+    /// Same as `TypedSapling::new`.
+    pub fn parse(&self, policy: &VerificationPolicy) -> Result<TypedSapling, Error> {
+        TypedSapling::new(policy, self)
+    }
+}
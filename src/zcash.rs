@@ -1,65 +1,268 @@
+#[cfg(any(
+    all(feature = "spend-vk", feature = "output-vk"),
+    all(feature = "std", any(feature = "spend-vk", feature = "output-vk"))
+))]
+use crate::Groth16PreparedVerifyingKey;
+#[cfg(all(feature = "spend-vk", feature = "output-vk"))]
+use crate::SaplingNetwork;
 use crate::{
-    affine::{read_g1affine, read_g2affine},
+    affine::{
+        read_compressed_g1affine_slice, read_compressed_g2affine_slice, read_g1affine_slice,
+        read_g2affine_slice, write_compressed_g1affine, write_compressed_g2affine, write_g1affine,
+        write_g2affine, AffineDecodeError,
+    },
     Groth16VerifyingKey,
 };
-use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(any(feature = "spend-vk", feature = "output-vk"))]
 use hex_literal::hex;
+#[cfg(all(feature = "std", any(feature = "spend-vk", feature = "output-vk")))]
+use once_cell::sync::OnceCell;
 
+/// Spend circuit verifying key.
+///
+/// Encoded as `alpha_g1 || beta_g2 || gamma_g2 || delta_g2 || gamma_abc_g1`, every point
+/// uncompressed. See `read_vk`.
+#[cfg(feature = "spend-vk")]
+pub const SPEND_VK_BYTES: [u8; 1440] = hex!("0db882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2e19ab445dac720bb1f2b0cd3336f7a1acc62bf1b3a321826264dc7e469281e23b218394d598689da04e136878ff9a78970a416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed8838478104376a98072766c29959358e9cde6a4985618f65ea257e8f288974f4aedde52e5dac2fb7ae5d30eab7cd828a2c8b15f15b16f139f2c33ef33d63befe404e696c97077d17ea42f4ff9d82ec456aaf43914a3d07968111a3a348f157e64c0278a13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb80606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801077284b5eb4a16c08a9b4810bcb80336367adbc7425bb37cb5dfb41977a78e152125fad6cb858a38edbe25ad2a34f7f019841965c9831072efe2b9739766edd008283f835d09206e51bfc1f97d9b2eb1c8b726195b2ffb5e5506fccb8f93e6060bdfb00b4aa6237784e645e0cda032243c65b73634f508403ae8297e4b894d313bce5b96db0a3311c28182d3a798a02f185e77ca7a24fe2a1a9447c93de31bd3e481fa9f623ae8d27baf78e6547e83b3d99a6eda5b455b09469c0d6ecd442ba212daedb4014a51305d017ac2b895d7dae97d813b243a7d33ed0135ad58a7fbc80b8ebc25ee1ff1af7c9c21b4e4fdf5b213567233a168b9ec9b81ee411aa9937f9f89e847c26810bd5a177dfb9ad3a6a3ac1b46ddffcc32ce3b8277200d9bfb08175e64129e1d2394bca57349b2845248b7e92f41acdbb23ca1532898ca29fe08ce2a0ff8795c35fe711d6959ab1ba1051617085c03e25b57a689f5ecfa2ecda5ab0f5eff00079ea957283a482cf7de40839ed0683603af30768a3206ea816fd002198d34ecd17c02740fed470c026a5e4e5fe2ad22fb5ba7b68eac3fc82ef37dd921f7ae8e1773f4c164b447f550760101a1e6108959ce1c98a64b96bd76c474360c630cc5977267855288c0f9fca4a205604a77aa655507169929d7fb52eb95071151c8ae74d6bcb6e24b466c59d242009deaf65cd82fb9b662c867a7c88377c115e90f8bb9bda528b9f1541ac15d850befd35dd79811c12a7065a075bb2a412e28592cdd307e75c4231c8ce3e3f8b6ac1a5a87666c55f4f8bc2d770ea5259b0f008453cef037d48e211c6eea1610304c9e1aedc28b8208b9f16ba471156a9ab870bbba871f9b26c3a31a67268df26b11c9e64194deb7b9b095903271d42753f1d66fe66d11a82f8e12096333b06d6f35e675ff650189cbb9a010776fd73a950f51fec047929e2802c2840269daa7a7f082db690e6a49c16a749df8ab82a02eb7356f70dc8c7e68682695dfc815491e0172a3966c1e2effc8bc7d0fb462f033cdb93065c9e09d2865c99b494b5fabbddc86194a4b0109a4da58152990e68b93183d15a395bd62e4844440f3e9612db6315fd16f0b395155205464ecd9546477d797d353bc9c045acd18c5d9498071c416637dd87e22f4e26bfbf7b94d6cc073ab5cda9423f9f8a30cc024529ff8fea2c7338d34fec14a5f390b4fee213d4e4102d64a3c00693baa032b101608c51fc37aa1e99bf255bd4f293592ffd6bf2ffadfc842fc1dcf3b7142eeb3f8edce90e61885b1f5779fc3c818df56d05a48f96f9710652a8b43833a35fd947b9cf3081bc245bf6cf4c380c9b702e7b0b8338d86");
+
+/// Output circuit verifying key, in the same encoding as `SPEND_VK_BYTES`.
+#[cfg(feature = "output-vk")]
+pub const OUTPUT_VK_BYTES: [u8; 1248] = hex!("0db882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2e19ab445dac720bb1f2b0cd3336f7a1acc62bf1b3a321826264dc7e469281e23b218394d598689da04e136878ff9a78970a416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed8838478104376a98072766c29959358e9cde6a4985618f65ea257e8f288974f4aedde52e5dac2fb7ae5d30eab7cd828a2c8b15f15b16f139f2c33ef33d63befe404e696c97077d17ea42f4ff9d82ec456aaf43914a3d07968111a3a348f157e64c0278a13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb80606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801050302fecf4d86671c66ed1ee097efccd2a2add6fd42c9d0a809bb6a3e0f8348bfac6cfa4427c83d5ed1ff844a5b1b1209c069a8a1ccd8c7c22b2a84fede0e53b536cabd7d4c7f0ddc53bec42eeda2b09190d43bcbaece88f7a2a1fc686076d20f2acbc06f28f913a2a77a731d96133aeb5282461cd452a3f3f1d3b63907840dc79b1066e898a335c3a676de9c97507c0c4824b4c9ac0dfc2b1b017e1ebe1b96920a80a7f7e61e39d2f275c51ea8c0b4a6aa86643ee4696af6611d027c58401c04fd67184c37c5240183cff73f52544ecd6b629e775ad6648696f99a431a63ca8b386bdcb94cb1e4c53c4ec0276d3dc50c2bd5d31bf904b30b9d0508d59c915bfaea64830425366257fe599f2eaf2f6650bea1abe4bab1955da88a6860c31b4d073143b431c7e8e9323f3693c470fd9a3adf199a31b6e3006c64189bde7a07a316f9f7252d042147ac4c68be3a62a367012534e285c0ac0e01c7c8f9d013f13aa1da0c06777564d76b27ab788fd46ca5a9a9854424c325eab85298044b258da313cca7cdbcfb681f36c0a3d9ab957960b57dd73002f042f7222eec23d5d60a30bf93bd8d0564113c0be8f1baf577ca570088e381355d3f7e8dab653e7a2e758724b39a9d63e4905fbc2927d5246d2e6072ad142f81498e6d5aecdf36200de5790ffb9b423f4631fbbeb423d81fe9b244953a2b701eb2f5d96bf97981eb50227705128a401c10789582bbc8648150270f0d77cb44de2f73bdea9e4405174ef3034ce88bc4b02d4e0886b3fa5fb5e6b0b1f404bfa18ad308aadddd2634c9765d5b0f91ffe602148dd9a6404f1701af31a48481ef23bf05beed476428178c2f972082d533348904348831ea61ce33bf6ccc0b04fae453962cb4c2634bc75eaecaaf9a034cb30d82968bb08a46403b308ac0be82f5964c0a9631032c363147097ad20936d64376b8cff46bf7a9c3db65b47ad83c1cdf2cea84f2671278d597f8867a3a4a955aaab958169bf96839b923b27a08e7d52742571a73eb842f9c31b8972505079760a63c909f0033ba1331c8131c3eafb8945f2112c405869b54721b014e");
+
+/// Spend circuit verifying key with every point compressed. See `read_compressed_vk`.
+#[cfg(feature = "spend-vk")]
+pub const SPEND_VK_COMPRESSED_BYTES: [u8; 720] = hex!("adb882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2eaa416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed883847893e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8877284b5eb4a16c08a9b4810bcb80336367adbc7425bb37cb5dfb41977a78e152125fad6cb858a38edbe25ad2a34f7f019841965c9831072efe2b9739766edd008283f835d09206e51bfc1f97d9b2eb1c8b726195b2ffb5e5506fccb8f93e606b2daedb4014a51305d017ac2b895d7dae97d813b243a7d33ed0135ad58a7fbc80b8ebc25ee1ff1af7c9c21b4e4fdf5b2b75e64129e1d2394bca57349b2845248b7e92f41acdbb23ca1532898ca29fe08ce2a0ff8795c35fe711d6959ab1ba10582198d34ecd17c02740fed470c026a5e4e5fe2ad22fb5ba7b68eac3fc82ef37dd921f7ae8e1773f4c164b447f5507601871151c8ae74d6bcb6e24b466c59d242009deaf65cd82fb9b662c867a7c88377c115e90f8bb9bda528b9f1541ac15d85af008453cef037d48e211c6eea1610304c9e1aedc28b8208b9f16ba471156a9ab870bbba871f9b26c3a31a67268df26b8f51fec047929e2802c2840269daa7a7f082db690e6a49c16a749df8ab82a02eb7356f70dc8c7e68682695dfc815491eb83d15a395bd62e4844440f3e9612db6315fd16f0b395155205464ecd9546477d797d353bc9c045acd18c5d9498071c4a2d64a3c00693baa032b101608c51fc37aa1e99bf255bd4f293592ffd6bf2ffadfc842fc1dcf3b7142eeb3f8edce90e6");
+
+/// Output circuit verifying key with every point compressed. See `read_compressed_vk`.
+#[cfg(feature = "output-vk")]
+pub const OUTPUT_VK_COMPRESSED_BYTES: [u8; 624] = hex!("adb882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2eaa416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed883847893e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8a50302fecf4d86671c66ed1ee097efccd2a2add6fd42c9d0a809bb6a3e0f8348bfac6cfa4427c83d5ed1ff844a5b1b1209c069a8a1ccd8c7c22b2a84fede0e53b536cabd7d4c7f0ddc53bec42eeda2b09190d43bcbaece88f7a2a1fc686076d284fd67184c37c5240183cff73f52544ecd6b629e775ad6648696f99a431a63ca8b386bdcb94cb1e4c53c4ec0276d3dc5873143b431c7e8e9323f3693c470fd9a3adf199a31b6e3006c64189bde7a07a316f9f7252d042147ac4c68be3a62a36793cca7cdbcfb681f36c0a3d9ab957960b57dd73002f042f7222eec23d5d60a30bf93bd8d0564113c0be8f1baf577ca57affb9b423f4631fbbeb423d81fe9b244953a2b701eb2f5d96bf97981eb50227705128a401c10789582bbc8648150270f8f91ffe602148dd9a6404f1701af31a48481ef23bf05beed476428178c2f972082d533348904348831ea61ce33bf6ccc8936d64376b8cff46bf7a9c3db65b47ad83c1cdf2cea84f2671278d597f8867a3a4a955aaab958169bf96839b923b27a");
+
+/// Returns the spend verifying key.
+///
+/// With the `small-binary` feature only the compressed key is embedded and it is
+/// decompressed on every call, so callers should prepare it once and keep it around.
+#[cfg(feature = "spend-vk")]
 pub fn spend_vk() -> Groth16VerifyingKey {
-    fn inner() -> Result<Groth16VerifyingKey, ()> {
-        let key = Groth16VerifyingKey {
-            alpha_g1: read_g1affine(hex!("0db882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2e19ab445dac720bb1f2b0cd3336f7a1acc62bf1b3a321826264dc7e469281e23b218394d598689da04e136878ff9a7897"))?,
-            beta_g2: read_g2affine(hex!("0a416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed8838478104376a98072766c29959358e9cde6a4985618f65ea257e8f288974f4aedde52e5dac2fb7ae5d30eab7cd828a2c8b15f15b16f139f2c33ef33d63befe404e696c97077d17ea42f4ff9d82ec456aaf43914a3d07968111a3a348f157e64c0278a"))?,
-            gamma_g2: read_g2affine(hex!("13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb80606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801"))?,
-            delta_g2: read_g2affine(hex!("077284b5eb4a16c08a9b4810bcb80336367adbc7425bb37cb5dfb41977a78e152125fad6cb858a38edbe25ad2a34f7f019841965c9831072efe2b9739766edd008283f835d09206e51bfc1f97d9b2eb1c8b726195b2ffb5e5506fccb8f93e6060bdfb00b4aa6237784e645e0cda032243c65b73634f508403ae8297e4b894d313bce5b96db0a3311c28182d3a798a02f185e77ca7a24fe2a1a9447c93de31bd3e481fa9f623ae8d27baf78e6547e83b3d99a6eda5b455b09469c0d6ecd442ba2"))?,
-            gamma_abc_g1: vec![
-                read_g1affine(hex!("12daedb4014a51305d017ac2b895d7dae97d813b243a7d33ed0135ad58a7fbc80b8ebc25ee1ff1af7c9c21b4e4fdf5b213567233a168b9ec9b81ee411aa9937f9f89e847c26810bd5a177dfb9ad3a6a3ac1b46ddffcc32ce3b8277200d9bfb08"))?,
-                read_g1affine(hex!("175e64129e1d2394bca57349b2845248b7e92f41acdbb23ca1532898ca29fe08ce2a0ff8795c35fe711d6959ab1ba1051617085c03e25b57a689f5ecfa2ecda5ab0f5eff00079ea957283a482cf7de40839ed0683603af30768a3206ea816fd0"))?,
-                read_g1affine(hex!("02198d34ecd17c02740fed470c026a5e4e5fe2ad22fb5ba7b68eac3fc82ef37dd921f7ae8e1773f4c164b447f550760101a1e6108959ce1c98a64b96bd76c474360c630cc5977267855288c0f9fca4a205604a77aa655507169929d7fb52eb95"))?,
-                read_g1affine(hex!("071151c8ae74d6bcb6e24b466c59d242009deaf65cd82fb9b662c867a7c88377c115e90f8bb9bda528b9f1541ac15d850befd35dd79811c12a7065a075bb2a412e28592cdd307e75c4231c8ce3e3f8b6ac1a5a87666c55f4f8bc2d770ea5259b"))?,
-                read_g1affine(hex!("0f008453cef037d48e211c6eea1610304c9e1aedc28b8208b9f16ba471156a9ab870bbba871f9b26c3a31a67268df26b11c9e64194deb7b9b095903271d42753f1d66fe66d11a82f8e12096333b06d6f35e675ff650189cbb9a010776fd73a95"))?,
-                read_g1affine(hex!("0f51fec047929e2802c2840269daa7a7f082db690e6a49c16a749df8ab82a02eb7356f70dc8c7e68682695dfc815491e0172a3966c1e2effc8bc7d0fb462f033cdb93065c9e09d2865c99b494b5fabbddc86194a4b0109a4da58152990e68b93"))?,
-                read_g1affine(hex!("183d15a395bd62e4844440f3e9612db6315fd16f0b395155205464ecd9546477d797d353bc9c045acd18c5d9498071c416637dd87e22f4e26bfbf7b94d6cc073ab5cda9423f9f8a30cc024529ff8fea2c7338d34fec14a5f390b4fee213d4e41"))?,
-                read_g1affine(hex!("02d64a3c00693baa032b101608c51fc37aa1e99bf255bd4f293592ffd6bf2ffadfc842fc1dcf3b7142eeb3f8edce90e61885b1f5779fc3c818df56d05a48f96f9710652a8b43833a35fd947b9cf3081bc245bf6cf4c380c9b702e7b0b8338d86"))?
-            ]
-        };
-
-        Ok(key)
-    }
+    #[cfg(not(feature = "small-binary"))]
+    let key = read_vk(&SPEND_VK_BYTES);
+    #[cfg(feature = "small-binary")]
+    let key = read_compressed_vk(&SPEND_VK_COMPRESSED_BYTES);
 
-    inner().unwrap()
+    key.expect("embedded spend key is valid; qed")
 }
 
+/// Returns the output verifying key. See `spend_vk`.
+#[cfg(feature = "output-vk")]
 pub fn output_vk() -> Groth16VerifyingKey {
-    fn inner() -> Result<Groth16VerifyingKey, ()> {
-        let key = Groth16VerifyingKey {
-            alpha_g1: read_g1affine(hex!("0db882cf5db3e8567f16b4db1772d4d1f5a3fe8d62f0df2eb8a5cfa50806702afde8fc25335eb5ec859c2818b2610b2e19ab445dac720bb1f2b0cd3336f7a1acc62bf1b3a321826264dc7e469281e23b218394d598689da04e136878ff9a7897"))?,
-            beta_g2: read_g2affine(hex!("0a416b8187450b28f025c421e3ff14d38f9abd9af2f1046b914b53ab37e9aebba683cb25284e5c22fa341129985250a103547de5d005df48265f7cb258162253d56fbc682d106a1ecb07666ebf7524a364e512c37aa62f82d6e7dd4ed8838478104376a98072766c29959358e9cde6a4985618f65ea257e8f288974f4aedde52e5dac2fb7ae5d30eab7cd828a2c8b15f15b16f139f2c33ef33d63befe404e696c97077d17ea42f4ff9d82ec456aaf43914a3d07968111a3a348f157e64c0278a"))?,
-            gamma_g2: read_g2affine(hex!("13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb80606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801"))?,
-            delta_g2: read_g2affine(hex!("050302fecf4d86671c66ed1ee097efccd2a2add6fd42c9d0a809bb6a3e0f8348bfac6cfa4427c83d5ed1ff844a5b1b1209c069a8a1ccd8c7c22b2a84fede0e53b536cabd7d4c7f0ddc53bec42eeda2b09190d43bcbaece88f7a2a1fc686076d20f2acbc06f28f913a2a77a731d96133aeb5282461cd452a3f3f1d3b63907840dc79b1066e898a335c3a676de9c97507c0c4824b4c9ac0dfc2b1b017e1ebe1b96920a80a7f7e61e39d2f275c51ea8c0b4a6aa86643ee4696af6611d027c58401c"))?,
-            gamma_abc_g1: vec![
-                read_g1affine(hex!("04fd67184c37c5240183cff73f52544ecd6b629e775ad6648696f99a431a63ca8b386bdcb94cb1e4c53c4ec0276d3dc50c2bd5d31bf904b30b9d0508d59c915bfaea64830425366257fe599f2eaf2f6650bea1abe4bab1955da88a6860c31b4d"))?,
-                read_g1affine(hex!("073143b431c7e8e9323f3693c470fd9a3adf199a31b6e3006c64189bde7a07a316f9f7252d042147ac4c68be3a62a367012534e285c0ac0e01c7c8f9d013f13aa1da0c06777564d76b27ab788fd46ca5a9a9854424c325eab85298044b258da3"))?,
-                read_g1affine(hex!("13cca7cdbcfb681f36c0a3d9ab957960b57dd73002f042f7222eec23d5d60a30bf93bd8d0564113c0be8f1baf577ca570088e381355d3f7e8dab653e7a2e758724b39a9d63e4905fbc2927d5246d2e6072ad142f81498e6d5aecdf36200de579"))?,
-                read_g1affine(hex!("0ffb9b423f4631fbbeb423d81fe9b244953a2b701eb2f5d96bf97981eb50227705128a401c10789582bbc8648150270f0d77cb44de2f73bdea9e4405174ef3034ce88bc4b02d4e0886b3fa5fb5e6b0b1f404bfa18ad308aadddd2634c9765d5b"))?,
-                read_g1affine(hex!("0f91ffe602148dd9a6404f1701af31a48481ef23bf05beed476428178c2f972082d533348904348831ea61ce33bf6ccc0b04fae453962cb4c2634bc75eaecaaf9a034cb30d82968bb08a46403b308ac0be82f5964c0a9631032c363147097ad2"))?,
-                read_g1affine(hex!("0936d64376b8cff46bf7a9c3db65b47ad83c1cdf2cea84f2671278d597f8867a3a4a955aaab958169bf96839b923b27a08e7d52742571a73eb842f9c31b8972505079760a63c909f0033ba1331c8131c3eafb8945f2112c405869b54721b014e"))?,
-            ]
-        };
-
-        Ok(key)
+    #[cfg(not(feature = "small-binary"))]
+    let key = read_vk(&OUTPUT_VK_BYTES);
+    #[cfg(feature = "small-binary")]
+    let key = read_compressed_vk(&OUTPUT_VK_COMPRESSED_BYTES);
+
+    key.expect("embedded output key is valid; qed")
+}
+
+/// Returns the prepared spend verifying key, which is prepared on the first call and shared
+/// by all later ones.
+#[cfg(all(feature = "std", feature = "spend-vk"))]
+pub fn prepared_spend_vk() -> &'static Groth16PreparedVerifyingKey {
+    static KEY: OnceCell<Groth16PreparedVerifyingKey> = OnceCell::new();
+    KEY.get_or_init(|| spend_vk().into())
+}
+
+/// Returns the prepared output verifying key. See `prepared_spend_vk`.
+#[cfg(all(feature = "std", feature = "output-vk"))]
+pub fn prepared_output_vk() -> &'static Groth16PreparedVerifyingKey {
+    static KEY: OnceCell<Groth16PreparedVerifyingKey> = OnceCell::new();
+    KEY.get_or_init(|| output_vk().into())
+}
+
+/// The Zcash mainnet parameters, with both verifying keys prepared once.
+#[cfg(all(feature = "spend-vk", feature = "output-vk"))]
+pub struct ZcashMainnet {
+    spend_vk: Groth16PreparedVerifyingKey,
+    output_vk: Groth16PreparedVerifyingKey,
+}
+
+#[cfg(all(feature = "spend-vk", feature = "output-vk"))]
+impl ZcashMainnet {
+    pub fn new() -> Self {
+        ZcashMainnet {
+            spend_vk: spend_vk().into(),
+            output_vk: output_vk().into(),
+        }
+    }
+}
+
+#[cfg(all(feature = "spend-vk", feature = "output-vk"))]
+impl Default for ZcashMainnet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(feature = "spend-vk", feature = "output-vk"))]
+impl SaplingNetwork for ZcashMainnet {
+    fn spend_vk(&self) -> &Groth16PreparedVerifyingKey {
+        &self.spend_vk
+    }
+
+    fn output_vk(&self) -> &Groth16PreparedVerifyingKey {
+        &self.output_vk
+    }
+}
+
+/// Reads a verifying key encoded as `alpha_g1 || beta_g2 || gamma_g2 || delta_g2 || gamma_abc_g1`.
+pub fn read_vk(data: &[u8]) -> Result<Groth16VerifyingKey, AffineDecodeError> {
+    let (alpha_g1, mut offset) = read_g1affine_slice(data)?;
+    let (beta_g2, len) = read_g2affine_slice(&data[offset..])?;
+    offset += len;
+    let (gamma_g2, len) = read_g2affine_slice(&data[offset..])?;
+    offset += len;
+    let (delta_g2, len) = read_g2affine_slice(&data[offset..])?;
+    offset += len;
+
+    // there is always at least one base, and the rest of the data must consist of whole points
+    if offset == data.len() || (data.len() - offset) % 96 != 0 {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    let mut gamma_abc_g1 = Vec::with_capacity((data.len() - offset) / 96);
+    while offset < data.len() {
+        let (point, len) = read_g1affine_slice(&data[offset..])?;
+        gamma_abc_g1.push(point);
+        offset += len;
+    }
+
+    Ok(Groth16VerifyingKey {
+        alpha_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g2,
+        gamma_abc_g1,
+    })
+}
+
+/// Writes a verifying key in the encoding accepted by `read_vk`.
+pub fn write_vk(vk: &Groth16VerifyingKey) -> Vec<u8> {
+    let mut result = Vec::with_capacity(96 + 3 * 192 + vk.gamma_abc_g1.len() * 96);
+    result.extend_from_slice(&write_g1affine(&vk.alpha_g1));
+    result.extend_from_slice(&write_g2affine(&vk.beta_g2));
+    result.extend_from_slice(&write_g2affine(&vk.gamma_g2));
+    result.extend_from_slice(&write_g2affine(&vk.delta_g2));
+    for point in &vk.gamma_abc_g1 {
+        result.extend_from_slice(&write_g1affine(point));
+    }
+    result
+}
+
+/// Reads a verifying key in the layout of `read_vk` with every point compressed.
+pub fn read_compressed_vk(data: &[u8]) -> Result<Groth16VerifyingKey, AffineDecodeError> {
+    let (alpha_g1, mut offset) = read_compressed_g1affine_slice(data)?;
+    let (beta_g2, len) = read_compressed_g2affine_slice(&data[offset..])?;
+    offset += len;
+    let (gamma_g2, len) = read_compressed_g2affine_slice(&data[offset..])?;
+    offset += len;
+    let (delta_g2, len) = read_compressed_g2affine_slice(&data[offset..])?;
+    offset += len;
+
+    if offset == data.len() || (data.len() - offset) % 48 != 0 {
+        return Err(AffineDecodeError::InvalidLength);
+    }
+
+    let mut gamma_abc_g1 = Vec::with_capacity((data.len() - offset) / 48);
+    while offset < data.len() {
+        let (point, len) = read_compressed_g1affine_slice(&data[offset..])?;
+        gamma_abc_g1.push(point);
+        offset += len;
     }
 
-    inner().unwrap()
+    Ok(Groth16VerifyingKey {
+        alpha_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g2,
+        gamma_abc_g1,
+    })
+}
+
+/// Writes a verifying key in the encoding accepted by `read_compressed_vk`.
+pub fn write_compressed_vk(vk: &Groth16VerifyingKey) -> Vec<u8> {
+    let mut result = Vec::with_capacity(48 + 3 * 96 + vk.gamma_abc_g1.len() * 48);
+    result.extend_from_slice(&write_compressed_g1affine(&vk.alpha_g1));
+    result.extend_from_slice(&write_compressed_g2affine(&vk.beta_g2));
+    result.extend_from_slice(&write_compressed_g2affine(&vk.gamma_g2));
+    result.extend_from_slice(&write_compressed_g2affine(&vk.delta_g2));
+    for point in &vk.gamma_abc_g1 {
+        result.extend_from_slice(&write_compressed_g1affine(point));
+    }
+    result
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "spend-vk", feature = "output-vk"))]
 mod tests {
-    use super::{output_vk, spend_vk};
+    use super::{
+        output_vk, read_compressed_vk, read_vk, spend_vk, write_compressed_vk, write_vk,
+        OUTPUT_VK_BYTES, OUTPUT_VK_COMPRESSED_BYTES, SPEND_VK_BYTES, SPEND_VK_COMPRESSED_BYTES,
+    };
+    use crate::affine::AffineDecodeError;
 
     #[test]
     fn test_vk() {
-        let _spend_key = spend_vk();
-        let _output_key = output_vk();
+        let spend_key = spend_vk();
+        let output_key = output_vk();
+
+        // 7 public inputs for spends, 5 for outputs
+        assert_eq!(spend_key.gamma_abc_g1.len(), 8);
+        assert_eq!(output_key.gamma_abc_g1.len(), 6);
+
+        assert_eq!(write_vk(&spend_key)[..], SPEND_VK_BYTES[..]);
+        assert_eq!(write_vk(&output_key)[..], OUTPUT_VK_BYTES[..]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_prepared_vk() {
+        use super::{prepared_output_vk, prepared_spend_vk};
+
+        // the same keys are returned by every call
+        assert!(core::ptr::eq(prepared_spend_vk(), prepared_spend_vk()));
+        assert_eq!(prepared_spend_vk().vk.gamma_abc_g1.len(), 8);
+        assert_eq!(prepared_output_vk().vk.gamma_abc_g1.len(), 6);
+    }
+
+    #[test]
+    fn test_read_vk_length() {
+        assert_eq!(
+            read_vk(&SPEND_VK_BYTES[..672]).err(),
+            Some(AffineDecodeError::InvalidLength)
+        );
+        assert_eq!(
+            read_vk(&SPEND_VK_BYTES[..1439]).err(),
+            Some(AffineDecodeError::InvalidLength)
+        );
+        assert_eq!(
+            read_vk(&SPEND_VK_BYTES[..100]).err(),
+            Some(AffineDecodeError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_compressed_vk() {
+        let spend_key = read_compressed_vk(&SPEND_VK_COMPRESSED_BYTES).unwrap();
+        let output_key = read_compressed_vk(&OUTPUT_VK_COMPRESSED_BYTES).unwrap();
+
+        // both encodings describe the same keys
+        assert_eq!(write_vk(&spend_key)[..], SPEND_VK_BYTES[..]);
+        assert_eq!(write_vk(&output_key)[..], OUTPUT_VK_BYTES[..]);
+        assert_eq!(
+            write_compressed_vk(&spend_vk())[..],
+            SPEND_VK_COMPRESSED_BYTES[..]
+        );
+        assert_eq!(
+            write_compressed_vk(&output_vk())[..],
+            OUTPUT_VK_COMPRESSED_BYTES[..]
+        );
     }
 }
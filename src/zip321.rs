@@ -0,0 +1,492 @@
+//! ZIP-321 payment request URIs.
+//!
+//! Addresses are carried as strings and are not decoded or validated.
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+/// Number of zatoshis in a single ZEC.
+const COIN: u64 = 100_000_000;
+/// Maximum amount of ZEC that can exist.
+const MAX_MONEY: u64 = 21_000_000 * COIN;
+/// Maximum size of a decoded memo.
+const MEMO_SIZE: usize = 512;
+
+/// Reason for rejecting a payment request URI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Zip321Error {
+    /// The URI does not start with `zcash:`.
+    InvalidScheme,
+    /// A parameter is malformed or its index is invalid.
+    InvalidParameter(String),
+    /// The same parameter appears twice for one payment.
+    DuplicateParameter(String),
+    /// A `req-` parameter this implementation does not understand.
+    UnknownRequiredParameter(String),
+    /// The amount is malformed or exceeds the maximum supply.
+    InvalidAmount,
+    /// The memo is not valid base64url or is too long.
+    InvalidMemo,
+    /// A parameter value is not valid percent-encoded UTF-8.
+    InvalidEncoding,
+    /// A payment has parameters but no address.
+    MissingAddress(usize),
+    /// The request contains no payments.
+    Empty,
+}
+
+/// Single payment within a request.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Payment {
+    /// Recipient address.
+    pub address: String,
+    /// Requested amount in zatoshis.
+    pub amount: Option<u64>,
+    /// Memo bytes, at most 512.
+    pub memo: Option<Vec<u8>>,
+    /// Label for the recipient.
+    pub label: Option<String>,
+    /// Message for the payer.
+    pub message: Option<String>,
+    /// Parameters without special meaning, percent-decoded.
+    pub other_params: Vec<(String, String)>,
+}
+
+/// Payment request, as encoded in a `zcash:` URI.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TransactionRequest {
+    pub payments: Vec<Payment>,
+}
+
+impl TransactionRequest {
+    /// Parses a `zcash:` URI.
+    pub fn from_uri(uri: &str) -> Result<Self, Zip321Error> {
+        let rest = strip_prefix_ignore_case(uri, "zcash:").ok_or(Zip321Error::InvalidScheme)?;
+        let (path, query) = match rest.find('?') {
+            Some(position) => (&rest[..position], Some(&rest[position + 1..])),
+            None => (rest, None),
+        };
+
+        let mut payments: BTreeMap<usize, Payment> = BTreeMap::new();
+        if !path.is_empty() {
+            payments.entry(0).or_default().address = percent_decode(path)?;
+        }
+
+        for param in query.into_iter().flat_map(|query| query.split('&')) {
+            let (key, value) = match param.find('=') {
+                Some(position) => (&param[..position], &param[position + 1..]),
+                None => return Err(Zip321Error::InvalidParameter(param.to_string())),
+            };
+            let (name, index) = parse_key(key)?;
+
+            if name == "address" && index == 0 && !path.is_empty() {
+                return Err(Zip321Error::DuplicateParameter(key.to_string()));
+            }
+
+            let payment = payments.entry(index).or_default();
+            let duplicate = || Zip321Error::DuplicateParameter(key.to_string());
+            match name {
+                "address" => {
+                    if !payment.address.is_empty() {
+                        return Err(duplicate());
+                    }
+                    payment.address = percent_decode(value)?;
+                }
+                "amount" => {
+                    if payment.amount.is_some() {
+                        return Err(duplicate());
+                    }
+                    payment.amount = Some(parse_amount(value)?);
+                }
+                "memo" => {
+                    if payment.memo.is_some() {
+                        return Err(duplicate());
+                    }
+                    payment.memo = Some(decode_memo(value)?);
+                }
+                "label" => {
+                    if payment.label.is_some() {
+                        return Err(duplicate());
+                    }
+                    payment.label = Some(percent_decode(value)?);
+                }
+                "message" => {
+                    if payment.message.is_some() {
+                        return Err(duplicate());
+                    }
+                    payment.message = Some(percent_decode(value)?);
+                }
+                _ if name.starts_with("req-") => {
+                    return Err(Zip321Error::UnknownRequiredParameter(name.to_string()));
+                }
+                _ => {
+                    let name = name.to_string();
+                    if payment.other_params.iter().any(|(other, _)| *other == name) {
+                        return Err(duplicate());
+                    }
+                    payment.other_params.push((name, percent_decode(value)?));
+                }
+            }
+        }
+
+        if payments.is_empty() {
+            return Err(Zip321Error::Empty);
+        }
+
+        let mut result = Vec::with_capacity(payments.len());
+        for (index, payment) in payments {
+            if payment.address.is_empty() {
+                return Err(Zip321Error::MissingAddress(index));
+            }
+            result.push(payment);
+        }
+
+        Ok(TransactionRequest { payments: result })
+    }
+
+    /// Encodes the request as a `zcash:` URI.
+    ///
+    /// A single payment is encoded with its address in the path, multiple payments use indexed
+    /// parameters.
+    pub fn to_uri(&self) -> String {
+        let mut uri = String::from("zcash:");
+        let mut params = Vec::new();
+
+        if let [payment] = &self.payments[..] {
+            uri.push_str(&percent_encode(&payment.address));
+            push_params(&mut params, payment, "");
+        } else {
+            for (index, payment) in self.payments.iter().enumerate() {
+                let suffix = if index == 0 {
+                    String::new()
+                } else {
+                    let mut suffix = String::new();
+                    let _ = write!(suffix, ".{}", index);
+                    suffix
+                };
+                params.push(param("address", &suffix, &percent_encode(&payment.address)));
+                push_params(&mut params, payment, &suffix);
+            }
+        }
+
+        if !params.is_empty() {
+            uri.push('?');
+            uri.push_str(&params.join("&"));
+        }
+
+        uri
+    }
+}
+
+fn push_params(params: &mut Vec<String>, payment: &Payment, suffix: &str) {
+    if let Some(amount) = payment.amount {
+        params.push(param("amount", suffix, &format_amount(amount)));
+    }
+    if let Some(memo) = &payment.memo {
+        params.push(param("memo", suffix, &encode_memo(memo)));
+    }
+    if let Some(label) = &payment.label {
+        params.push(param("label", suffix, &percent_encode(label)));
+    }
+    if let Some(message) = &payment.message {
+        params.push(param("message", suffix, &percent_encode(message)));
+    }
+    for (name, value) in &payment.other_params {
+        params.push(param(name, suffix, &percent_encode(value)));
+    }
+}
+
+fn param(name: &str, suffix: &str, value: &str) -> String {
+    let mut result = String::with_capacity(name.len() + suffix.len() + value.len() + 1);
+    result.push_str(name);
+    result.push_str(suffix);
+    result.push('=');
+    result.push_str(value);
+    result
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let (bytes, prefix_bytes) = (s.as_bytes(), prefix.as_bytes());
+    if bytes.len() >= prefix.len() && bytes[..prefix.len()].eq_ignore_ascii_case(prefix_bytes) {
+        Some(&s[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// Splits `name.index` into its parts. Indices are 1 to 9999 without leading zeros, a missing
+/// index is index 0.
+fn parse_key(key: &str) -> Result<(&str, usize), Zip321Error> {
+    let invalid = || Zip321Error::InvalidParameter(key.to_string());
+
+    let (name, index) = match key.find('.') {
+        Some(position) => {
+            let digits = &key[position + 1..];
+            if digits.is_empty()
+                || digits.len() > 4
+                || digits.starts_with('0')
+                || !digits.bytes().all(|b| b.is_ascii_digit())
+            {
+                return Err(invalid());
+            }
+            (&key[..position], digits.parse().map_err(|_| invalid())?)
+        }
+        None => (key, 0),
+    };
+
+    let is_valid_name = !name.is_empty()
+        && name.as_bytes()[0].is_ascii_alphabetic()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'-');
+    if !is_valid_name {
+        return Err(invalid());
+    }
+
+    Ok((name, index))
+}
+
+/// Parses a decimal ZEC amount with at most 8 fractional digits into zatoshis.
+fn parse_amount(value: &str) -> Result<u64, Zip321Error> {
+    let (whole, fraction) = match value.find('.') {
+        Some(position) => (&value[..position], &value[position + 1..]),
+        None => (value, ""),
+    };
+
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty()
+        || !is_digits(whole)
+        || !is_digits(fraction)
+        || fraction.len() > 8
+        || (value.contains('.') && fraction.is_empty())
+    {
+        return Err(Zip321Error::InvalidAmount);
+    }
+
+    let whole: u64 = whole.parse().map_err(|_| Zip321Error::InvalidAmount)?;
+    let mut zats = 0u64;
+    for position in 0..8 {
+        let digit = fraction.as_bytes().get(position).map_or(0, |b| b - b'0');
+        zats = zats * 10 + digit as u64;
+    }
+
+    let amount = whole
+        .checked_mul(COIN)
+        .and_then(|whole| whole.checked_add(zats))
+        .ok_or(Zip321Error::InvalidAmount)?;
+    if amount > MAX_MONEY {
+        return Err(Zip321Error::InvalidAmount);
+    }
+
+    Ok(amount)
+}
+
+fn format_amount(amount: u64) -> String {
+    let mut result = String::new();
+    let _ = write!(result, "{}", amount / COIN);
+    let fraction = amount % COIN;
+    if fraction != 0 {
+        let mut digits = String::new();
+        let _ = write!(digits, "{:08}", fraction);
+        result.push('.');
+        result.push_str(digits.trim_end_matches('0'));
+    }
+    result
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Decodes unpadded base64url.
+fn decode_memo(value: &str) -> Result<Vec<u8>, Zip321Error> {
+    if value.len() % 4 == 1 {
+        return Err(Zip321Error::InvalidMemo);
+    }
+
+    let mut result = Vec::with_capacity(value.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for b in value.bytes() {
+        let sextet = BASE64URL
+            .iter()
+            .position(|c| *c == b)
+            .ok_or(Zip321Error::InvalidMemo)?;
+        acc = ((acc << 6) | sextet as u32) & 0xffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((acc >> bits) as u8);
+        }
+    }
+
+    // leftover bits must be zero for the encoding to be canonical
+    if acc & ((1 << bits) - 1) != 0 || result.len() > MEMO_SIZE {
+        return Err(Zip321Error::InvalidMemo);
+    }
+
+    Ok(result)
+}
+
+/// Encodes as unpadded base64url.
+fn encode_memo(memo: &[u8]) -> String {
+    let mut result = String::with_capacity((memo.len() * 4 + 2) / 3);
+    for chunk in memo.chunks(3) {
+        let mut block = [0u8; 3];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let acc = (block[0] as u32) << 16 | (block[1] as u32) << 8 | block[2] as u32;
+        for i in 0..=chunk.len() {
+            result.push(BASE64URL[(acc >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    result
+}
+
+/// Characters allowed unescaped in a query value.
+fn is_qchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~!$'()*+,;:@".contains(&b)
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if is_qchar(b) {
+            result.push(b as char);
+        } else {
+            let _ = write!(result, "%{:02X}", b);
+        }
+    }
+    result
+}
+
+fn percent_decode(value: &str) -> Result<String, Zip321Error> {
+    let bytes = value.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .ok_or(Zip321Error::InvalidEncoding)?;
+            let hex = core::str::from_utf8(hex).map_err(|_| Zip321Error::InvalidEncoding)?;
+            result.push(u8::from_str_radix(hex, 16).map_err(|_| Zip321Error::InvalidEncoding)?);
+            i += 3;
+        } else if is_qchar(bytes[i]) {
+            result.push(bytes[i]);
+            i += 1;
+        } else {
+            return Err(Zip321Error::InvalidEncoding);
+        }
+    }
+
+    String::from_utf8(result).map_err(|_| Zip321Error::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_memo, encode_memo, parse_amount, Payment, TransactionRequest, Zip321Error};
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    const ADDRESS: &str =
+        "zs1z7rejlpsa98s2rrrfkwmaxu53e4ue0ulcrw0h4x5g8jl04tak0d3mm47vdtahatqrlkngh9sly";
+
+    #[test]
+    fn test_single_payment() {
+        let uri = "zcash:zs1z7rejlpsa98s2rrrfkwmaxu53e4ue0ulcrw0h4x5g8jl04tak0d3mm47vdtahatqrlkngh9sly?amount=1&memo=VGhpcyBpcyBhIHNpbXBsZSBtZW1vLg&message=Thank%20you%20for%20your%20purchase";
+        let request = TransactionRequest::from_uri(uri).unwrap();
+
+        assert_eq!(
+            request.payments,
+            vec![Payment {
+                address: ADDRESS.to_string(),
+                amount: Some(100_000_000),
+                memo: Some(b"This is a simple memo.".to_vec()),
+                message: Some("Thank you for your purchase".to_string()),
+                ..Default::default()
+            }]
+        );
+        assert_eq!(request.to_uri(), uri);
+    }
+
+    #[test]
+    fn test_multiple_payments() {
+        let uri = "zcash:?address=tmEZhbWHTpdKMw5it8YDspUXSMGQyFwovpU&amount=123.456&address.1=zs1z7rejlpsa98s2rrrfkwmaxu53e4ue0ulcrw0h4x5g8jl04tak0d3mm47vdtahatqrlkngh9sly&amount.1=0.789&memo.1=VGhpcyBpcyBhIHVuaWNvZGUgbWVtbyDinKjwn6aE8J-PhvCfjok";
+        let request = TransactionRequest::from_uri(uri).unwrap();
+
+        assert_eq!(request.payments.len(), 2);
+        assert_eq!(request.payments[0].amount, Some(12_345_600_000));
+        assert_eq!(request.payments[1].address, ADDRESS);
+        assert_eq!(request.payments[1].amount, Some(78_900_000));
+        assert_eq!(request.to_uri(), uri);
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let invalid = [
+            ("bitcoin:abc", Zip321Error::InvalidScheme),
+            ("zcash:", Zip321Error::Empty),
+            ("zcash:?amount=1", Zip321Error::MissingAddress(0)),
+            (
+                "zcash:abc?address=def",
+                Zip321Error::DuplicateParameter("address".to_string()),
+            ),
+            (
+                "zcash:abc?amount=1&amount=2",
+                Zip321Error::DuplicateParameter("amount".to_string()),
+            ),
+            (
+                "zcash:abc?address.01=def",
+                Zip321Error::InvalidParameter("address.01".to_string()),
+            ),
+            (
+                "zcash:abc?label.10000=d",
+                Zip321Error::InvalidParameter("label.10000".to_string()),
+            ),
+            (
+                "zcash:abc?req-foo=1",
+                Zip321Error::UnknownRequiredParameter("req-foo".to_string()),
+            ),
+            ("zcash:abc?memo=a", Zip321Error::InvalidMemo),
+            ("zcash:abc?message=%zz", Zip321Error::InvalidEncoding),
+        ];
+
+        for (uri, error) in invalid.iter() {
+            assert_eq!(
+                TransactionRequest::from_uri(uri).as_ref(),
+                Err(error),
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("0"), Ok(0));
+        assert_eq!(parse_amount("0.00000001"), Ok(1));
+        assert_eq!(parse_amount("21000000"), Ok(21_000_000 * 100_000_000));
+        assert_eq!(
+            parse_amount("21000000.00000001"),
+            Err(Zip321Error::InvalidAmount)
+        );
+        assert_eq!(parse_amount("1.123456789"), Err(Zip321Error::InvalidAmount));
+        assert_eq!(parse_amount("1."), Err(Zip321Error::InvalidAmount));
+        assert_eq!(parse_amount(".1"), Err(Zip321Error::InvalidAmount));
+        assert_eq!(parse_amount("-1"), Err(Zip321Error::InvalidAmount));
+    }
+
+    #[test]
+    fn test_memo_roundtrip() {
+        for len in 0..8 {
+            let memo: Vec<u8> = (0..len).map(|i| 0xf0 + i as u8).collect();
+            assert_eq!(decode_memo(&encode_memo(&memo)), Ok(memo));
+        }
+
+        assert_eq!(
+            decode_memo(&encode_memo(&[0; 513])),
+            Err(Zip321Error::InvalidMemo)
+        );
+    }
+}